@@ -0,0 +1,157 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use crate::commands::Error;
+use clap::{Parser, Subcommand};
+use models::{Card, Todo};
+use std::{fmt, str::FromStr};
+
+
+#[derive(Parser)]
+#[command(name = "todo", version, about = "Manage the todo cards from the terminal")]
+pub struct Args {
+    /// Origin of the API [default: the one logged in to, or the public demo for `login`]
+    #[arg(long, env = "TODO_ORIGIN", global = true)]
+    pub origin: Option<String>,
+
+    /// Output as JSON instead of a table
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Sign up as a new user, or pair with an existing account
+    Login {
+        /// Code of the existing account, issued in Settings of the web
+        /// and valid only once for a few minutes
+        #[arg(long)]
+        code: Option<String>,
+    },
+    #[command(flatten)]
+    Cards(CardCommand),
+}
+
+/// Commands on the cards of the logged-in account
+#[derive(Subcommand)]
+pub enum CardCommand {
+    /// List the cards
+    List,
+    /// Create a card
+    Add {
+        /// Title of the card
+        title: String,
+        /// Contents of the todos, at most 10
+        #[arg(num_args = 0..=Card::N_TODOS)]
+        todos: Vec<String>,
+    },
+    /// Complete a todo with all its sub-todos
+    Check {
+        /// `#` number in `list`, or a prefix of the id
+        card: CardSelector,
+        /// Position like `3`, or `3.1` for a sub-todo
+        todo: TodoPath,
+        /// Uncomplete instead
+        #[arg(long)]
+        undo: bool,
+    },
+    /// Edit the title of a card, or the content or notes of a todo
+    Edit {
+        /// `#` number in `list`, or a prefix of the id
+        card: CardSelector,
+        /// Position like `3`, or `3.1` for a sub-todo
+        todo: Option<TodoPath>,
+        /// New title of the card
+        #[arg(long, conflicts_with = "todo")]
+        title: Option<String>,
+        /// New content of the todo ( a sub-todo is removed by an empty one )
+        #[arg(long, requires = "todo")]
+        content: Option<String>,
+        /// New notes of the todo ( removed by an empty one )
+        #[arg(long, requires = "todo")]
+        notes: Option<String>,
+    },
+    /// Move a card to the trash
+    Rm {
+        /// `#` number in `list`, or a prefix of the id
+        card: CardSelector,
+    },
+    /// Print all the cards with their notes and attachments, as Markdown
+    Export,
+}
+
+
+/// `#` number of a card in `list` ( with or without `#` ), or a prefix of its id
+#[derive(Clone)]
+pub struct CardSelector(String);
+
+impl FromStr for CardSelector {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim_start_matches('#') {
+            "" => Err("empty card".into()),
+            s  => Ok(Self(s.into())),
+        }
+    }
+}
+
+impl CardSelector {
+    pub fn select(self, mut cards: Vec<Card>) -> Result<Card, Error> {
+        if let Ok(n) = self.0.parse::<usize>() {
+            if (1..=cards.len()).contains(&n) {
+                return Ok(cards.swap_remove(n - 1))
+            }
+        }
+
+        let mut matched = cards.into_iter().filter(|c| c.id.starts_with(&self.0));
+        match (matched.next(), matched.next()) {
+            (Some(card), None) => Ok(card),
+            (None, _)          => Err(Error::NoSuchCard(self.0)),
+            (Some(_), Some(_)) => Err(Error::AmbiguousCard(self.0)),
+        }
+    }
+}
+
+
+/// 1-based position of a todo in a card followed by those of the sub-todos,
+/// joined by `.` like `3` or `3.1`
+#[derive(Clone, Debug)]
+pub struct TodoPath {
+    /// 0-based index in `Card::todos`
+    pub index: usize,
+    /// 0-based indices of `children` from the todo
+    pub path:  Vec<usize>,
+}
+
+impl FromStr for TodoPath {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut positions = s.split('.').map(|n| match n.parse::<usize>() {
+            Ok(n) if n >= 1 => Ok(n - 1),
+            _ => Err(format!("expected like `3` or `3.1`, found `{s}`")),
+        });
+        let index = positions.next().unwrap()?;
+        if index >= Card::N_TODOS {
+            return Err(format!("a card has only {} todos", Card::N_TODOS))
+        }
+        Ok(Self { index, path: positions.collect::<Result<_, _>>()? })
+    }
+}
+
+impl fmt::Display for TodoPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.index + 1)?;
+        for i in &self.path {
+            write!(f, ".{}", i + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl TodoPath {
+    pub fn get_mut<'t>(&self, todos: &'t mut [Todo; Card::N_TODOS]) -> Result<&'t mut Todo, Error> {
+        todos[self.index].get_mut(&self.path).ok_or_else(|| Error::NoSuchTodo(self.clone()))
+    }
+}
@@ -0,0 +1,158 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use crate::args::{Args, CardCommand, Command, TodoPath};
+use crate::credentials::Credentials;
+use crate::output;
+use client::{Client, StatusCode};
+use models::{Card, CreateCardRequest, UpdateCard};
+
+
+const PUBLIC_ORIGIN: &str = "https://ohkami-yew-todo.kanarus.workers.dev";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Api(#[from] client::Error),
+
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("Not logged in: run `todo login` first")]
+    NotLoggedIn,
+
+    #[error("The code is wrong, used or expired: issue a new one in Settings")]
+    InvalidCode,
+
+    #[error("Logged in to {logged_in}, not to {requested}: run `todo login --origin {requested}` to use it")]
+    OtherOrigin { logged_in: String, requested: String },
+
+    #[error("No directory to store the token in: set `TODO_CONFIG_DIR`")]
+    NoConfigDir,
+
+    #[error("No card matches `{0}`")]
+    NoSuchCard(String),
+
+    #[error("`{0}` matches more than one card")]
+    AmbiguousCard(String),
+
+    #[error("No todo {0} in the card")]
+    NoSuchTodo(TodoPath),
+
+    #[error("Nothing to edit: specify `--title`, or `--content` or `--notes` with a todo")]
+    NothingToEdit,
+}
+
+pub async fn run(Args { origin, json, command }: Args) -> Result<(), Error> {
+    let command = match command {
+        Command::Login { code } => return login(origin.unwrap_or_else(|| PUBLIC_ORIGIN.into()), code, json).await,
+        Command::Cards(command) => command,
+    };
+
+    let client = {
+        let credentials = Credentials::load()?.ok_or(Error::NotLoggedIn)?;
+        /* not to send the token to another server */
+        if let Some(requested) = origin.filter(|o| o.trim_end_matches('/') != credentials.origin.trim_end_matches('/')) {
+            return Err(Error::OtherOrigin { logged_in: credentials.origin, requested })
+        }
+        Client::new(credentials.origin, credentials.token)
+    };
+
+    match command {
+        CardCommand::List => {
+            let cards = client.list_cards().await?;
+            if json {output::json(&cards)?} else {output::cards(&cards)}
+        }
+
+        CardCommand::Add { title, todos } => {
+            let mut todos = todos.into_iter();
+            let id = client.create_card(&CreateCardRequest {
+                id: None,
+                title,
+                todos: std::array::from_fn(|_| todos.next().unwrap_or_default()),
+            }).await?;
+            show_card(&client, &id, json).await?
+        }
+
+        CardCommand::Check { card, todo, undo } => {
+            let Card { id, title, mut todos, .. } = card.select(client.list_cards().await?)?;
+            todo.get_mut(&mut todos)?.set_completed(!undo);
+            todos[todo.index].sync_completion();
+
+            client.update_card(&id, &UpdateCard { title, todos }).await?;
+            show_card(&client, &id, json).await?
+        }
+
+        CardCommand::Edit { card, todo, title: new_title, content, notes } => {
+            let Card { id, mut title, mut todos, .. } = card.select(client.list_cards().await?)?;
+            match todo {
+                None => title = new_title.ok_or(Error::NothingToEdit)?,
+                Some(_) if content.is_none() && notes.is_none() => return Err(Error::NothingToEdit),
+                Some(todo) => {
+                    let target = todo.get_mut(&mut todos)?;
+                    if let Some(notes) = notes {
+                        target.notes = (!notes.is_empty()).then_some(notes)
+                    }
+                    if let Some(content) = content {
+                        target.content = content
+                    }
+
+                    /* a sub-todo is removed when its content is cleared, as in the front */
+                    if let Some((last, parent_path)) = todo.path.split_last() {
+                        let parent = todos[todo.index].get_mut(parent_path).unwrap();
+                        if parent.children[*last].content.is_empty() {
+                            parent.children.remove(*last);
+                        }
+                    }
+                    todos[todo.index].sync_completion();
+                }
+            }
+
+            client.update_card(&id, &UpdateCard { title, todos }).await?;
+            show_card(&client, &id, json).await?
+        }
+
+        CardCommand::Rm { card } => {
+            let Card { id, .. } = card.select(client.list_cards().await?)?;
+            client.delete_card(&id).await?;
+            if json {output::json(&serde_json::json!({ "id": id }))?} else {println!("Moved {id} to the trash")}
+        }
+
+        CardCommand::Export => {
+            let cards = client.list_cards().await?;
+            if json {output::json(&cards)?} else {output::markdown(&cards, &client)}
+        }
+    }
+
+    Ok(())
+}
+
+async fn login(origin: String, code: Option<String>, json: bool) -> Result<(), Error> {
+    let client = match code {
+        Some(code) => Client::pair(origin.clone(), &code).await.map_err(|e| match e.status() {
+            Some(StatusCode::NOT_FOUND) => Error::InvalidCode,
+            _ => e.into(),
+        })?,
+        None => Client::signup(origin.clone()).await?,
+    };
+
+    let path = Credentials { origin: origin.clone(), token: client.token().into() }.save()?;
+
+    if json {
+        output::json(&serde_json::json!({ "origin": origin }))?
+    } else {
+        println!("Logged in to {origin} ( the token is saved in {} )", path.display())
+    }
+    Ok(())
+}
+
+/// Print the card as stored ( sanitized ) by the server
+async fn show_card(client: &Client, id: &str, json: bool) -> Result<(), Error> {
+    let cards = client.list_cards().await?;
+    let (i, card) = cards.iter().enumerate().find(|(_, c)| c.id == id)
+        .ok_or_else(|| Error::NoSuchCard(id.into()))?;
+    if json {output::json(card)?} else {output::card(i, card)}
+    Ok(())
+}
@@ -0,0 +1,53 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use crate::commands::Error;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+
+/// Origin and token of the logged-in account, in `credentials.json` of
+/// `$TODO_CONFIG_DIR`, or `ohkami-yew-todo` of the user's config directory
+#[derive(Serialize, Deserialize)]
+pub struct Credentials {
+    pub origin: String,
+    pub token:  String,
+}
+
+impl Credentials {
+    fn path() -> Result<PathBuf, Error> {
+        let var = |name| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+
+        let dir = var("TODO_CONFIG_DIR")
+            .or_else(|| var("XDG_CONFIG_HOME").map(|d| d.join("ohkami-yew-todo")))
+            .or_else(|| var("APPDATA").map(|d| d.join("ohkami-yew-todo")))
+            .or_else(|| var("HOME").map(|d| d.join(".config").join("ohkami-yew-todo")))
+            .ok_or(Error::NoConfigDir)?;
+        Ok(dir.join("credentials.json"))
+    }
+
+    pub fn load() -> Result<Option<Self>, Error> {
+        match std::fs::read(Self::path()?) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Save this and return the path, readable only by the user on Unix
+    pub fn save(&self) -> Result<PathBuf, Error> {
+        let path = Self::path()?;
+        std::fs::create_dir_all(path.parent().unwrap())?;
+
+        let mut file = std::fs::OpenOptions::new();
+        file.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut file, 0o600);
+        let file = file.open(&path)?;
+        /* `mode` is only for a new file */
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        serde_json::to_writer_pretty(file, self)?;
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,23 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+//! `todo` : the cards and todos from the terminal
+
+mod args;
+mod commands;
+mod credentials;
+mod output;
+
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> std::process::ExitCode {
+    use clap::Parser;
+
+    match commands::run(args::Args::parse()).await {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,79 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use client::Client;
+use models::{Card, Todo};
+use serde::Serialize;
+
+
+pub fn json(value: &impl Serialize) -> Result<(), serde_json::Error> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Print the cards as in the front: numbered by `#`, skipping the empty todos
+pub fn cards(cards: &[Card]) {
+    if cards.is_empty() {
+        return println!("No cards yet: create one by `todo add`")
+    }
+    for (i, card) in cards.iter().enumerate() {
+        if i > 0 {println!()}
+        self::card(i, card)
+    }
+}
+
+/// Print the `i`-th card
+pub fn card(i: usize, Card { id, title, todos, attachments }: &Card) {
+    let title = if title.is_empty() {"( untitled )"} else {title};
+    println!("#{}  {title}  [{}]", i + 1, &id[..id.len().min(8)]);
+
+    fn todo(position: String, Todo { content, notes, completed, children }: &Todo) {
+        let depth = position.matches('.').count();
+        println!("{:indent$}{position:>3} [{}] {content}{}",
+            "",
+            if *completed {'x'} else {' '},
+            if notes.is_some() {"  ( notes )"} else {""},
+            indent = 2 + 4 * depth,
+        );
+        for (i, child) in children.iter().enumerate() {
+            todo(format!("{position}.{}", i + 1), child)
+        }
+    }
+    for (i, t) in todos.iter().enumerate().filter(|(_, t)| !t.content.is_empty() || !t.children.is_empty()) {
+        todo((i + 1).to_string(), t)
+    }
+
+    for attachment in attachments {
+        println!("    + {} ( {}, {} bytes )", attachment.name, attachment.mime_type, attachment.size)
+    }
+}
+
+/// Print all the cards as Markdown task lists, with the notes and
+/// the links to the attachments ( valid for an hour )
+pub fn markdown(cards: &[Card], client: &Client) {
+    for (i, Card { title, todos, attachments, .. }) in cards.iter().enumerate() {
+        if i > 0 {println!()}
+        println!("## {}", if title.is_empty() {"( untitled )"} else {title});
+        println!();
+
+        fn todo(depth: usize, Todo { content, notes, completed, children }: &Todo) {
+            let indent = "  ".repeat(depth);
+            println!("{indent}- [{}] {content}", if *completed {'x'} else {' '});
+            for line in notes.iter().flat_map(|notes| notes.lines()) {
+                println!("{indent}  > {line}")
+            }
+            for child in children {
+                todo(depth + 1, child)
+            }
+        }
+        for t in todos.iter().filter(|t| !t.content.is_empty() || !t.children.is_empty()) {
+            todo(0, t)
+        }
+
+        if !attachments.is_empty() {
+            println!();
+            for attachment in attachments {
+                println!("- [{}]({})", attachment.name, client.url(&attachment.url))
+            }
+        }
+    }
+}
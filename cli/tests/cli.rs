@@ -0,0 +1,141 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+//! `todo` against the native server on a free local port with a fresh in-memory database
+
+use models::Card;
+use ohkami_yew_todo::native::{self, AttachmentsBucket};
+use ohkami_yew_todo::repository::SqliteRepository;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+
+/// Origin of a new server by `native::spawn`, and a new directory of its
+/// attachments and the config of `todo` in `config/` ( removed when dropped )
+fn start() -> (String, TempDir) {
+    std::env::set_var("JWT_SECRET_KEY", "ohkami-yew-todo-cli-test-jwt-secret-key");
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo   = SqliteRepository::open_in_memory().unwrap();
+    let bucket = AttachmentsBucket::open(dir.path().join("attachments")).unwrap();
+
+    (native::spawn(repo, bucket), dir)
+}
+
+/// Run `todo` with `args` and return its stdout, asserting it succeeds
+fn todo(origin: &str, config_dir: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_todo"))
+        .env("TODO_ORIGIN", origin)
+        .env("TODO_CONFIG_DIR", config_dir)
+        .args(args)
+        .output().unwrap();
+    assert!(output.status.success(), "`todo {}` failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Run `todo` with `args` and return its stderr, asserting it fails
+fn todo_fails(origin: &str, config_dir: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_todo"))
+        .env("TODO_ORIGIN", origin)
+        .env("TODO_CONFIG_DIR", config_dir)
+        .args(args)
+        .output().unwrap();
+    assert!(!output.status.success(), "`todo {}` succeeded", args.join(" "));
+    String::from_utf8(output.stderr).unwrap()
+}
+
+
+#[test]
+fn manage_cards_from_the_terminal() {
+    let (origin, dir) = start();
+    let config = dir.path().join("config");
+
+    assert!(todo_fails(&origin, &config, &["list"]).contains("todo login"));
+
+    todo(&origin, &config, &["login"]);
+    assert!(todo(&origin, &config, &["list"]).contains("No cards yet"));
+
+    let added = todo(&origin, &config, &["add", "groceries", "milk", "eggs"]);
+    assert!(added.starts_with("#1  groceries  ["), "{added}");
+    assert!(added.contains("  1 [ ] milk\n"), "{added}");
+    assert!(added.contains("  2 [ ] eggs\n"), "{added}");
+
+    todo(&origin, &config, &["check", "#1", "2"]);
+    todo(&origin, &config, &["edit", "1", "2", "--notes", "free range\na dozen"]);
+    todo(&origin, &config, &["edit", "1", "--title", "shopping"]);
+
+    let cards: Vec<Card> = serde_json::from_str(&todo(&origin, &config, &["list", "--json"])).unwrap();
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].title, "shopping");
+    assert!(!cards[0].todos[0].completed);
+    assert!(cards[0].todos[1].completed);
+    assert_eq!(cards[0].todos[1].notes.as_deref(), Some("free range\na dozen"));
+
+    /* selected also by a prefix of the id */
+    let id_prefix = &cards[0].id[..8];
+    todo(&origin, &config, &["check", id_prefix, "2", "--undo"]);
+
+    let exported = todo(&origin, &config, &["export"]);
+    assert_eq!(exported, "\
+        ## shopping\n\
+        \n\
+        - [ ] milk\n\
+        - [ ] eggs\n  \
+          > free range\n  \
+          > a dozen\n\
+    ");
+
+    assert!(todo_fails(&origin, &config, &["check", "1", "11"]).contains("only 10 todos"));
+    assert!(todo_fails(&origin, &config, &["check", "1", "2.1"]).contains("No todo 2.1"));
+    assert!(todo_fails(&origin, &config, &["rm", "9"]).contains("No card matches `9`"));
+    assert!(todo_fails(&origin, &config, &["edit", "1"]).contains("Nothing to edit"));
+
+    todo(&origin, &config, &["rm", "1"]);
+    assert_eq!(todo(&origin, &config, &["list", "--json"]).trim(), "[]");
+}
+
+#[test]
+fn pair_with_an_existing_account() {
+    let (origin, dir) = start();
+    let browser = dir.path().join("config");
+    let terminal = browser.with_file_name("config-of-another-terminal");
+
+    todo(&origin, &browser, &["login"]);
+    todo(&origin, &browser, &["add", "from the browser", "a"]);
+
+    let credentials: serde_json::Value = serde_json::from_slice(&std::fs::read(browser.join("credentials.json")).unwrap()).unwrap();
+    let token = credentials["token"].as_str().unwrap();
+
+    /* by a code issued in Settings */
+    let code = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build().unwrap()
+        .block_on(client::Client::new(&origin, token).create_pairing_code()).unwrap()
+        .code;
+
+    todo(&origin, &terminal, &["login", "--code", &code]);
+    assert!(todo(&origin, &terminal, &["list"]).contains("from the browser"));
+    assert!(todo_fails(&origin, &terminal, &["login", "--code", &code]).contains("wrong, used or expired"));
+}
+
+#[test]
+fn the_token_stays_with_its_origin() {
+    let (origin, dir) = start();
+    let config = dir.path().join("config");
+
+    /* readable only by the user, even when saved over an older one */
+    let credentials = config.join("credentials.json");
+    std::fs::create_dir_all(&config).unwrap();
+    std::fs::write(&credentials, "{}").unwrap();
+    #[cfg(unix)]
+    std::fs::set_permissions(&credentials, std::os::unix::fs::PermissionsExt::from_mode(0o644)).unwrap();
+
+    todo(&origin, &config, &["login"]);
+    #[cfg(unix)]
+    assert_eq!(std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(&credentials).unwrap().permissions()) & 0o777, 0o600);
+
+    let other = "http://127.0.0.1:1";
+    let err = todo_fails(&origin, &config, &["list", "--origin", other]);
+    assert!(err.contains(&format!("Logged in to {origin}, not to {other}")), "{err}");
+    assert!(todo(&origin, &config, &["list", "--origin", &format!("{origin}/")]).contains("No cards yet"));
+}
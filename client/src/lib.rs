@@ -0,0 +1,312 @@
+//! Typed client of the API, for the front and native tools
+//!
+//! Every method corresponds to a route in `ohkami` of the server
+//! ( checked by tests/client.rs of the root crate ), and fails
+//! with `Error` also when the response is not successful.
+//!
+//! Each attempt of a request times out in `Client::timeout`, and transient
+//! failures of the ones safe to repeat are retried by `Client::retry`.
+//! The POST requests are sent with `Idempotency-Key` for the server
+//! to do each of them only once.
+
+mod retry;
+
+pub use retry::{RetryPolicy, RetryState};
+pub use reqwest::StatusCode;
+
+use models::{Attachment, Card, CreateCardRequest, CreateCardResponse, SignupResponse, Template, UpdateCard, ID};
+use models::{CreateTemplateRequest, CreateTemplateResponse, PairRequest, PairingCodeResponse};
+use reqwest::{Method, RequestBuilder};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Failed to send the request, or the response is not successful
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+    #[error("No response in {} seconds", .0.as_secs_f32())]
+    Timeout(Duration),
+    #[error("Unexpected response: {0}")]
+    Decode(#[from] serde_json::Error),
+    /// `409` with `Retry-After` : the request of the same `Idempotency-Key`
+    /// is still in progress, not done yet
+    #[error("The same request is still in progress")]
+    InProgress,
+}
+
+impl Error {
+    /// Status of the unsuccessful response, if any
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Request(e) => e.status(),
+            Self::InProgress => Some(StatusCode::CONFLICT),
+            Self::Timeout(_) | Self::Decode(_) => None,
+        }
+    }
+
+    /// Whether the same request may succeed later
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Timeout(_) | Self::InProgress => true,
+            Self::Decode(_) => false,
+            Self::Request(e) => match e.status() {
+                Some(status) => matches!(status.as_u16(), 408 | 429 | 500 | 502 | 503 | 504),
+                None         => e.is_request() || e.is_timeout(), /* including connection errors */
+            },
+        }
+    }
+}
+
+
+#[derive(Clone)]
+pub struct Client {
+    origin:   String,
+    token:    String,
+    http:     reqwest::Client,
+    timeout:  Duration,
+    retry:    RetryPolicy,
+    on_retry: Option<retry::Listener>,
+
+    idempotency_key: Option<String>,
+}
+
+impl Client {
+    /// Client of the API at `origin` ( like `https://example.com`, without trailing `/` )
+    /// as the user of `token`
+    pub fn new(origin: impl Into<String>, token: impl Into<String>) -> Self {
+        Self {
+            origin: origin.into(),
+            token:  token.into(),
+            http:   reqwest::Client::new(),
+
+            timeout:  Duration::from_secs(15),
+            retry:    RetryPolicy::default(),
+            on_retry: None,
+
+            idempotency_key: None,
+        }
+    }
+
+    /// Sign up as a new user of the API at `origin` and return the client as the user
+    pub async fn signup(origin: impl Into<String>) -> Result<Self, Error> {
+        let mut client = Self::new(origin, "");
+        let SignupResponse { token } = client.receive(client.http.post(client.url("/signup"))).await?;
+        client.token = token.to_string();
+        Ok(client)
+    }
+
+    /// Log in to the account of `code` ( by `create_pairing_code` on another device )
+    /// at `origin` and return the client as the user
+    pub async fn pair(origin: impl Into<String>, code: &str) -> Result<Self, Error> {
+        let mut client = Self::new(origin, "");
+        let SignupResponse { token } = client.receive(client.http.post(client.url("/pair"))
+            .json(&PairRequest { code: code.into() })
+        ).await?;
+        client.token = token.to_string();
+        Ok(client)
+    }
+
+    /// Give up each attempt of a request in `timeout` ( 15 seconds by default )
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Call `listener` before every retry, and when the retried request is finished
+    #[cfg(target_arch = "wasm32")]
+    pub fn on_retry(mut self, listener: impl Fn(RetryState) + 'static) -> Self {
+        self.on_retry = Some(std::rc::Rc::new(listener));
+        self
+    }
+    /// Call `listener` before every retry, and when the retried request is finished
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn on_retry(mut self, listener: impl Fn(RetryState) + Send + Sync + 'static) -> Self {
+        self.on_retry = Some(std::sync::Arc::new(listener));
+        self
+    }
+
+    /// Clone of the client sending `key` ( like by `new_idempotency_key` ) as `Idempotency-Key`
+    /// of the POST requests, instead of a new one for each call
+    ///
+    /// Use the same key for the repeated calls of one user action
+    /// ( like a double click, or a retry by the user ) to have it done only once.
+    pub fn idempotent(&self, key: impl Into<String>) -> Self {
+        Self { idempotency_key: Some(key.into()), ..self.clone() }
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Resolve `path` ( like `Attachment::url` ) into the full URL of the API
+    pub fn url(&self, path: impl AsRef<str>) -> String {
+        format!("{}{}", self.origin, path.as_ref())
+    }
+
+    fn request(&self, method: Method, path: impl AsRef<str>) -> RequestBuilder {
+        let req = self.http.request(method.clone(), self.url(path)).bearer_auth(&self.token);
+        match method {
+            Method::POST => req.header(retry::IDEMPOTENCY_KEY, self.idempotency_key.clone().unwrap_or_else(new_idempotency_key)),
+            _ => req,
+        }
+    }
+
+    async fn receive<T: DeserializeOwned>(&self, req: RequestBuilder) -> Result<T, Error> {
+        let body = retry::send(req, self.timeout, &self.retry, self.on_retry.as_ref()).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    async fn done(&self, req: RequestBuilder) -> Result<(), Error> {
+        retry::send(req, self.timeout, &self.retry, self.on_retry.as_ref()).await?;
+        Ok(())
+    }
+}
+
+impl Client {
+    /// The cards on the board, neither archived nor in the trash
+    pub async fn list_cards(&self) -> Result<Vec<Card>, Error> {
+        self.receive(self.request(Method::GET, "/api/cards")).await
+    }
+
+    /// Create a card and return its id
+    pub async fn create_card(&self, req: &CreateCardRequest) -> Result<ID, Error> {
+        let CreateCardResponse { id } = self.receive(self.request(Method::POST, "/api/cards")
+            .json(req)
+        ).await?;
+        Ok(id)
+    }
+
+    pub async fn update_card(&self, id: &str, req: &UpdateCard) -> Result<(), Error> {
+        self.done(self.request(Method::PUT, format!("/api/cards/{id}"))
+            .json(req)
+        ).await
+    }
+
+    /// Move the card to the trash
+    pub async fn delete_card(&self, id: &str) -> Result<(), Error> {
+        self.done(self.request(Method::DELETE, format!("/api/cards/{id}"))).await
+    }
+
+    /// Create a copy of the card with all the todos uncompleted and return its id
+    pub async fn duplicate_card(&self, id: &str) -> Result<ID, Error> {
+        let CreateCardResponse { id } = self.receive(self.request(Method::POST, format!("/api/cards/{id}/duplicate"))).await?;
+        Ok(id)
+    }
+}
+
+impl Client {
+    /// The archived cards, the latest archived first
+    pub async fn list_archived_cards(&self) -> Result<Vec<Card>, Error> {
+        self.receive(self.request(Method::GET, "/api/archive")).await
+    }
+
+    pub async fn archive_card(&self, id: &str) -> Result<(), Error> {
+        self.done(self.request(Method::PUT, format!("/api/archive/{id}"))).await
+    }
+
+    /// Put the archived card back on the board
+    pub async fn unarchive_card(&self, id: &str) -> Result<(), Error> {
+        self.done(self.request(Method::DELETE, format!("/api/archive/{id}"))).await
+    }
+
+    /// The cards in the trash, the latest moved first
+    pub async fn list_trashed_cards(&self) -> Result<Vec<Card>, Error> {
+        self.receive(self.request(Method::GET, "/api/trash")).await
+    }
+
+    /// Put the card in the trash back on the board
+    pub async fn restore_card(&self, id: &str) -> Result<(), Error> {
+        self.done(self.request(Method::POST, format!("/api/trash/{id}/restore"))).await
+    }
+
+    /// Delete the card in the trash for good, with its attachments
+    pub async fn delete_card_forever(&self, id: &str) -> Result<(), Error> {
+        self.done(self.request(Method::DELETE, format!("/api/trash/{id}"))).await
+    }
+}
+
+impl Client {
+    /// Attach `bytes` of `mime_type` ( one of `Attachment::MIME_TYPES` ) to the card as `name`
+    pub async fn upload_attachment(&self,
+        card_id:   &str,
+        name:      &str,
+        mime_type: &str,
+        bytes:     Vec<u8>,
+    ) -> Result<Attachment, Error> {
+        self.receive(self.request(Method::POST, format!("/api/cards/{card_id}/attachments?name={}", encode_uri_component(name)))
+            .header("Content-Type", mime_type)
+            .body(bytes)
+        ).await
+    }
+
+    pub async fn delete_attachment(&self, card_id: &str, attachment_id: &str) -> Result<(), Error> {
+        self.done(self.request(Method::DELETE, format!("/api/cards/{card_id}/attachments/{attachment_id}"))).await
+    }
+}
+
+impl Client {
+    pub async fn list_templates(&self) -> Result<Vec<Template>, Error> {
+        self.receive(self.request(Method::GET, "/api/templates")).await
+    }
+
+    /// Save the title and todo contents of the card as a template and return its id
+    pub async fn create_template(&self, card_id: &str) -> Result<ID, Error> {
+        let CreateTemplateResponse { id } = self.receive(self.request(Method::POST, "/api/templates")
+            .json(&CreateTemplateRequest { card_id: card_id.into() })
+        ).await?;
+        Ok(id)
+    }
+
+    /// Create a card from the template and return its id
+    pub async fn instantiate_template(&self, id: &str) -> Result<ID, Error> {
+        let CreateCardResponse { id } = self.receive(self.request(Method::POST, format!("/api/templates/{id}/instantiate"))).await?;
+        Ok(id)
+    }
+
+    pub async fn delete_template(&self, id: &str) -> Result<(), Error> {
+        self.done(self.request(Method::DELETE, format!("/api/templates/{id}"))).await
+    }
+}
+
+impl Client {
+    /// Issue a code for another device to log in as the user by `Client::pair`
+    pub async fn create_pairing_code(&self) -> Result<PairingCodeResponse, Error> {
+        self.receive(self.request(Method::POST, "/api/pairing-codes")).await
+    }
+}
+
+/// New random key for `Client::idempotent`
+pub fn new_idempotency_key() -> String {
+    let mut bytes = [0; 16];
+    getrandom::getrandom(&mut bytes).expect("no source of randomness");
+    format!("{:032x}", u128::from_le_bytes(bytes))
+}
+
+/// New random UUID ( v4 ) for `CreateCardRequest::id`
+pub fn new_card_id() -> ID {
+    let mut bytes = [0; 16];
+    getrandom::getrandom(&mut bytes).expect("no source of randomness");
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = format!("{:032x}", u128::from_be_bytes(bytes));
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// Percent-encode all but the unreserved characters
+/// ( not `form_urlencoded`, whose `+` for space is not decoded by the server )
+fn encode_uri_component(s: &str) -> String {
+    s.bytes().fold(String::with_capacity(s.len()), |mut encoded, b| {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(b as char),
+            _ => encoded.push_str(&format!("%{b:02X}")),
+        }
+        encoded
+    })
+}
@@ -0,0 +1,156 @@
+//! Timeouts and retries of the requests in `Client`
+//!
+//! A request is retried only when repeating it is harmless : GET, PUT
+//! and DELETE, or POST with an `Idempotency-Key` that the server
+//! replays the first response for ( not `/signup` nor `/pair` ).
+//!
+//! PUT is not retried after a timeout : the timed-out attempt may still
+//! be applied by the server after the retry, and so after a newer PUT
+//! sent once the retry succeeds. The caller decides what to send then.
+
+use crate::Error;
+use reqwest::{header::RETRY_AFTER, Method, RequestBuilder, StatusCode};
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::task::Poll;
+use std::time::Duration;
+
+
+/// How many times and how long to wait for retrying a failed request
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt ( `0` to never retry )
+    pub max_retries: u32,
+    /// Upper bound of the delay before the first retry, doubled for each next one
+    pub base_delay: Duration,
+    /// Upper bound of the delay before any retry
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay:  Duration::from_millis(500),
+            max_delay:   Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn never() -> Self {
+        Self { max_retries: 0, ..Self::default() }
+    }
+
+    /// Random delay before the `attempt`-th retry ( from 1 ), up to
+    /// the exponential backoff so that clients don't retry all at once
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+        backoff.mul_f64(random_fraction())
+    }
+}
+
+/// Notified to `Client::on_retry` listener
+#[derive(Clone, Debug, PartialEq)]
+pub enum RetryState {
+    /// Waiting `delay` before the `attempt`-th retry of at most `max_retries`
+    /// because of `reason`
+    Waiting {
+        attempt:     u32,
+        max_retries: u32,
+        delay:       Duration,
+        reason:      String,
+    },
+    /// The retried request has finally succeeded or given up
+    Finished,
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) type Listener = std::rc::Rc<dyn Fn(RetryState)>;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) type Listener = std::sync::Arc<dyn Fn(RetryState) + Send + Sync>;
+
+
+/// Send `req` and read the whole body within `timeout`, retrying it by `policy`
+/// while it fails transiently and it's safe to repeat
+pub(crate) async fn send(
+    req:      RequestBuilder,
+    timeout:  Duration,
+    policy:   &RetryPolicy,
+    listener: Option<&Listener>,
+) -> Result<Vec<u8>, Error> {
+    let (http, req) = req.build_split();
+    let req = req?;
+
+    let repeatable = |e: &Error| e.is_transient() && match *req.method() {
+        Method::GET | Method::DELETE => true,
+        Method::PUT  => !matches!(e, Error::Timeout(_)),
+        Method::POST => req.headers().contains_key(IDEMPOTENCY_KEY),
+        _ => false,
+    };
+
+    let mut attempt = 0;
+    loop {
+        let this = req.try_clone().expect("bodies of `Client` are in memory");
+        let result = within(timeout, async {
+            let res = http.execute(this).await?;
+            if res.status() == StatusCode::CONFLICT && res.headers().contains_key(RETRY_AFTER) {
+                return Err(Error::InProgress)
+            }
+            Ok(res.error_for_status()?.bytes().await?.into())
+        }).await.unwrap_or(Err(Error::Timeout(timeout)));
+
+        match result {
+            Err(e) if repeatable(&e) && attempt < policy.max_retries => {
+                attempt += 1;
+                let delay = policy.delay(attempt);
+                if let Some(listener) = listener {
+                    listener(RetryState::Waiting {
+                        attempt,
+                        max_retries: policy.max_retries,
+                        delay,
+                        reason: e.to_string(),
+                    })
+                }
+                sleep(delay).await
+            }
+            result => {
+                if let (true, Some(listener)) = (attempt > 0, listener) {
+                    listener(RetryState::Finished)
+                }
+                return result
+            }
+        }
+    }
+}
+
+pub(crate) const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+
+fn random_fraction() -> f64 {
+    let mut bytes = [0; 8];
+    getrandom::getrandom(&mut bytes).expect("no source of randomness");
+    (u64::from_le_bytes(bytes) >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+/// `None` if `future` doesn't complete in `timeout`
+/// ( reqwest supports timeouts only on native )
+async fn within<T>(timeout: Duration, future: impl Future<Output = T>) -> Option<T> {
+    let (mut future, mut timer) = (pin!(future), pin!(sleep(timeout)));
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output))
+        }
+        timer.as_mut().poll(cx).map(|()| None)
+    }).await
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await
+}
+#[cfg(not(target_arch = "wasm32"))]
+async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
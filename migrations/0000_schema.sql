@@ -0,0 +1,74 @@
+CREATE TABLE IF NOT EXISTS users (
+    id TEXT NOT NULL, -- uuid v4
+
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE IF NOT EXISTS cards (
+    id           TEXT NOT NULL, -- uuid v4
+    user_id      TEXT NOT NULL, -- uuid v4
+    title        TEXT NOT NULL DEFAULT '',
+    created_at   INTEGER NOT NULL, -- unix timestamp (secs)
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES users (id)
+);
+
+CREATE TABLE IF NOT EXISTS todos (
+    id           INTEGER NOT NULL,
+    card_id      TEXT NOT NULL, -- uuid v4
+    content      TEXT NOT NULL DEFAULT '',
+    completed_at INTEGER, -- nullable unix timestamp (secs)
+
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE IF NOT EXISTS templates (
+    id           TEXT NOT NULL, -- uuid v4
+    user_id      TEXT NOT NULL, -- uuid v4
+    title        TEXT NOT NULL DEFAULT '',
+    created_at   INTEGER NOT NULL, -- unix timestamp (secs)
+
+    PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES users (id)
+);
+
+CREATE TABLE IF NOT EXISTS template_todos (
+    id           INTEGER NOT NULL,
+    template_id  TEXT NOT NULL, -- uuid v4
+    content      TEXT NOT NULL DEFAULT '',
+
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE IF NOT EXISTS attachments (
+    id           TEXT NOT NULL, -- uuid v4, also the key in R2 bucket as `attachments/{id}`
+    card_id      TEXT NOT NULL, -- uuid v4
+    name         TEXT NOT NULL,
+    mime_type    TEXT NOT NULL,
+    size         INTEGER NOT NULL, -- bytes
+    created_at   INTEGER NOT NULL, -- unix timestamp (secs)
+
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    user_id      TEXT NOT NULL, -- uuid v4
+    key          TEXT NOT NULL, -- `Idempotency-Key` header of the request
+    request      TEXT NOT NULL, -- `{method} {path} {SHA-256 of the body}` of the request
+    status       INTEGER, -- nullable, status of the response (NULL while the request is in progress)
+    body         TEXT, -- nullable, JSON body of the response
+    created_at   INTEGER NOT NULL, -- unix timestamp (secs)
+
+    PRIMARY KEY (user_id, key)
+);
+CREATE INDEX IF NOT EXISTS idempotency_keys_created_at ON idempotency_keys (created_at);
+
+CREATE TABLE IF NOT EXISTS pairing_codes (
+    code         TEXT NOT NULL, -- random, to be typed in `todo login --code`
+    user_id      TEXT NOT NULL, -- uuid v4
+    created_at   INTEGER NOT NULL, -- unix timestamp (secs)
+
+    PRIMARY KEY (code),
+    FOREIGN KEY (user_id) REFERENCES users (id)
+);
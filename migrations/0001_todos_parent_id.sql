@@ -0,0 +1,2 @@
+ALTER TABLE todos ADD COLUMN
+    parent_id    INTEGER; -- nullable, id of the parent todo (NULL for top-level todos of the card)
@@ -0,0 +1,2 @@
+ALTER TABLE todos ADD COLUMN
+    notes        TEXT; -- nullable, multi-line
@@ -0,0 +1,2 @@
+ALTER TABLE idempotency_keys ADD COLUMN
+    claimed_at   INTEGER NOT NULL DEFAULT 0; -- unix timestamp (secs), of the latest claim while in progress
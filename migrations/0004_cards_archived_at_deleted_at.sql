@@ -0,0 +1,4 @@
+ALTER TABLE cards ADD COLUMN
+    archived_at  INTEGER; -- nullable unix timestamp (secs), when archived ( NULL unless archived )
+ALTER TABLE cards ADD COLUMN
+    deleted_at   INTEGER; -- nullable unix timestamp (secs), when moved to the trash ( NULL unless in the trash )
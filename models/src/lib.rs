@@ -0,0 +1,233 @@
+//! Types shared by the server and the front
+
+pub mod markdown;
+mod locale;
+
+pub use locale::Locale;
+
+use ohkami::serde::{Deserialize, Serialize};
+use ohkami::fang::JWTToken;
+
+
+pub type ID = String;
+
+/// Whether `id` is a UUID like `67e55044-10b1-426f-9247-bb680e5fe0c8`,
+/// as the ids of cards chosen by clients must be
+pub fn is_uuid(id: &str) -> bool {
+    id.len() == 36 && id.char_indices().all(|(i, c)| match i {
+        8 | 13 | 18 | 23 => c == '-',
+        _                => c.is_ascii_hexdigit(),
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct Card {
+    pub id:    ID,
+    pub title: String,
+    pub todos: [Todo; Self::N_TODOS],
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+}
+impl Card {
+    pub const N_TODOS: usize = 10;
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct Todo {
+    pub content:   String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes:     Option<String>,
+    pub completed: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children:  Vec<Todo>,
+}
+impl Todo {
+    /// Most levels of sub-todos under a top-level todo
+    pub const MAX_DEPTH:    usize = 4;
+    /// Most sub-todos under a top-level todo, in all the levels
+    pub const MAX_SUBTODOS: usize = 100;
+
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content:   content.into(),
+            notes:     None,
+            completed: false,
+            children:  Vec::new(),
+        }
+    }
+
+    /// Get the descendant at `path` (indices of `children` from this todo).
+    /// An empty `path` points to this todo itself.
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut Todo> {
+        match path {
+            []             => Some(self),
+            [i, rest @ ..] => self.children.get_mut(*i)?.get_mut(rest),
+        }
+    }
+
+    /// Number of all the descendants
+    pub fn count_subtodos(&self) -> usize {
+        self.children.iter().map(|child| 1 + child.count_subtodos()).sum()
+    }
+
+    /// Levels of the descendants, 0 without any
+    pub fn depth(&self) -> usize {
+        self.children.iter().map(|child| 1 + child.depth()).max().unwrap_or(0)
+    }
+
+    /// Whether the sub-todos are within `MAX_DEPTH` and `MAX_SUBTODOS`
+    pub fn is_within_limits(&self) -> bool {
+        self.depth() <= Self::MAX_DEPTH && self.count_subtodos() <= Self::MAX_SUBTODOS
+    }
+
+    /// Complete or uncomplete this todo and cascade it to all the descendants
+    pub fn set_completed(&mut self, completed: bool) {
+        self.completed = completed;
+        for child in &mut self.children {
+            child.set_completed(completed)
+        }
+    }
+
+    /// Sanitize the contents of this todo and all the descendants
+    /// ( see `markdown::sanitize` )
+    pub fn sanitize(&mut self) {
+        self.content = markdown::sanitize(&self.content);
+        self.notes = self.notes.as_deref().map(markdown::sanitize).filter(|notes| !notes.is_empty());
+        for child in &mut self.children {
+            child.sanitize()
+        }
+    }
+
+    /// Recompute `completed` of every todo having non-empty children
+    /// as "all of them are completed", from the bottom up
+    pub fn sync_completion(&mut self) {
+        for child in &mut self.children {
+            child.sync_completion()
+        }
+
+        let mut subtasks = self.children.iter().filter(|c| !c.content.is_empty()).peekable();
+        if subtasks.peek().is_some() {
+            self.completed = subtasks.all(|c| c.completed)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct Attachment {
+    pub id:        ID,
+    pub name:      String,
+    pub mime_type: String,
+    /// bytes
+    pub size:      usize,
+    /// signed path to download the attachment without `Authorization`,
+    /// relative to the API origin and valid for a limited time
+    pub url:       String,
+}
+impl Attachment {
+    pub const MAX_SIZE: usize = 5 * 1024 * 1024;
+
+    /// characters, after control characters are stripped
+    pub const MAX_NAME_LEN: usize = 255;
+
+    pub const MIME_TYPES: [&'static str; 5] = [
+        "image/png",
+        "image/jpeg",
+        "image/gif",
+        "image/webp",
+        "application/pdf",
+    ];
+
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SignupResponse {
+    #[cfg_attr(feature = "openapi", schemars(with = "String"))]
+    pub token: JWTToken,
+}
+
+/// Code to log in to the account from another device, like `todo login --code`,
+/// only once and in `expires_in` seconds
+#[derive(Serialize, Deserialize)]
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PairingCodeResponse {
+    pub code:       String,
+    pub expires_in: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PairRequest {
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CreateCardRequest {
+    /// UUID chosen by the client to use the card before the response,
+    /// or a new one is given by the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id:    Option<ID>,
+    pub title: String,
+    pub todos: [String; Card::N_TODOS],
+}
+impl CreateCardRequest {
+    pub fn empty() -> Self {
+        Self {
+            id:    None,
+            title: String::new(),
+            todos: std::array::from_fn(|_| String::new()),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.title.is_empty() &&
+        self.todos.iter().all(String::is_empty)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CreateCardResponse {
+    pub id: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UpdateCard {
+    pub title: String,
+    pub todos: [Todo; Card::N_TODOS],
+}
+
+/// The title and the contents of the top-level todos of a card, to create
+/// new cards by ( without the completions, the notes, the sub-todos and the attachments )
+#[derive(Serialize, Deserialize)]
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct Template {
+    pub id:    ID,
+    pub title: String,
+    /// Contents of the top-level todos
+    pub todos: [String; Card::N_TODOS],
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CreateTemplateRequest {
+    pub card_id: ID,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CreateTemplateResponse {
+    pub id: ID,
+}
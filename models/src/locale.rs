@@ -0,0 +1,53 @@
+//! Languages of the messages of the front and the server
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Locale {
+    #[default]
+    En,
+    Ja,
+}
+
+impl Locale {
+    pub const ALL: [Self; 2] = [Self::En, Self::Ja];
+
+    /// BCP 47 tag, like `lang` of HTML
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Ja => "ja",
+        }
+    }
+
+    /// Name in the language itself, for the users to find theirs in the language switcher
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::En => "English",
+            Self::Ja => "日本語",
+        }
+    }
+
+    /// The supported one of a tag like `ja-JP`, by its primary language ignoring case
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next()?;
+        Self::ALL.into_iter().find(|locale| locale.tag().eq_ignore_ascii_case(primary))
+    }
+
+    /// The supported one most preferred in `Accept-Language` like `ja,en-US;q=0.8`,
+    /// or the default when none is
+    pub fn negotiate(accept_language: &str) -> Self {
+        let mut best: Option<(Self, f32)> = None;
+        for range in accept_language.split(',') {
+            let mut params = range.split(';');
+            let Some(locale) = params.next().and_then(Self::from_tag) else {continue};
+            let q = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.), |q| q.trim().parse::<f32>().ok())
+                .unwrap_or(0.);
+            if q > 0. && !matches!(best, Some((_, b)) if b >= q) {
+                best = Some((locale, q))
+            }
+        }
+        best.map(|(locale, _)| locale).unwrap_or_default()
+    }
+}
@@ -0,0 +1,237 @@
+//! A safe subset of Markdown for todo contents:
+//!
+//! - `[text](https://...)` : link ( only of `http`, `https` or `mailto` )
+//! - `**text**` : bold
+//! - `*text*` : italic
+//! - `` `text` `` : inline code
+//!
+//! and `\` escapes the following character.
+
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum Inline {
+    Text(String),
+    Bold(Vec<Inline>),
+    Italic(Vec<Inline>),
+    Code(String),
+    Link { text: Vec<Inline>, url: String },
+}
+
+pub fn is_safe_url(url: &str) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+    ["http://", "https://", "mailto:"].iter().any(|scheme| url.starts_with(scheme))
+}
+
+pub fn parse(src: &str) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    let mut text    = String::new();
+
+    let mut rest = src;
+    while let Some(c) = rest.chars().next() {
+        let parsed = match c {
+            '\\' => {
+                let mut chars = rest.chars().skip(1);
+                if let Some(escaped) = chars.next() {
+                    text.push(escaped);
+                    rest = &rest[1 + escaped.len_utf8()..];
+                    continue
+                }
+                None
+            }
+            '`' => enclosed(rest, "`").map(|(code, rest)|
+                (Inline::Code(code.into()), rest)
+            ),
+            '*' if rest.starts_with("**") => enclosed(rest, "**").map(|(inner, rest)|
+                (Inline::Bold(parse(inner)), rest)
+            ),
+            '*' => enclosed(rest, "*").map(|(inner, rest)|
+                (Inline::Italic(parse(inner)), rest)
+            ),
+            '[' => link(rest).map(|(text, url, rest)|
+                (Inline::Link { text: parse(text), url: url.into() }, rest)
+            ),
+            _ => None
+        };
+
+        match parsed {
+            Some((inline, remaining)) => {
+                if !text.is_empty() {
+                    inlines.push(Inline::Text(std::mem::take(&mut text)))
+                }
+                inlines.push(inline);
+                rest = remaining;
+            }
+            None => {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !text.is_empty() {
+        inlines.push(Inline::Text(text))
+    }
+
+    inlines
+}
+
+/// Escape what looks like an HTML tag by `\`, and unlink the links to unsafe URLs
+/// ( keeping their texts ), leaving the code spans as they are
+///
+/// The result is rendered by `parse` as the same text as `src`, except for the unlinked ones.
+pub fn sanitize(src: &str) -> String {
+    let mut sanitized = String::with_capacity(src.len());
+
+    let mut rest = src;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '\\' => if let Some(escaped) = rest[1..].chars().next() {
+                let len = 1 + escaped.len_utf8();
+                sanitized.push_str(&rest[..len]);
+                rest = &rest[len..];
+                continue
+            }
+            '`' => if let Some((_, remaining)) = enclosed(rest, "`") {
+                sanitized.push_str(&rest[..rest.len() - remaining.len()]);
+                rest = remaining;
+                continue
+            }
+            '<' => if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?')) {
+                sanitized.push('\\');
+            }
+            '[' => if let Some((text, url, remaining)) = link_any(rest) {
+                if is_safe_url(url) {
+                    sanitized.push('[');
+                    sanitized.push_str(&sanitize(text));
+                    sanitized.push_str("](");
+                    sanitized.push_str(url);
+                    sanitized.push(')');
+                } else {
+                    sanitized.push_str(&sanitize(text));
+                }
+                rest = remaining;
+                continue
+            }
+            _ => ()
+        }
+        sanitized.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    if sanitized.contains('<') && sanitized != src {
+        /* unlinking may have joined pieces into a new tag */
+        sanitize(&sanitized)
+    } else {
+        sanitized
+    }
+}
+
+/// `src` is expected to start with `delimiter`
+fn enclosed<'s>(src: &'s str, delimiter: &str) -> Option<(&'s str, &'s str)> {
+    let src = &src[delimiter.len()..];
+    let end = src.find(delimiter)?;
+    (end > 0).then(|| (&src[..end], &src[end + delimiter.len()..]))
+}
+
+/// `src` is expected to start with `[`
+///
+/// The URL ends at the `)` balancing the `(` before it, like `https://en.wikipedia.org/wiki/Rust_(programming_language)`.
+fn link_any(src: &str) -> Option<(&str, &str, &str)> {
+    let close = src.find("](")?;
+    let text  = &src[1..close];
+    let after = &src[close + 2..];
+
+    let mut depth = 0_usize;
+    let end = after.char_indices().find_map(|(i, c)| match c {
+        '(' => {depth += 1; None}
+        ')' if depth == 0 => Some(i),
+        ')' => {depth -= 1; None}
+        _ => None
+    })?;
+    (!text.is_empty()).then(|| (text, &after[..end], &after[end + 1..]))
+}
+
+fn link(src: &str) -> Option<(&str, &str, &str)> {
+    link_any(src).filter(|(_, url, _)| is_safe_url(url))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, sanitize, Inline::*};
+
+    fn text(s: &str) -> super::Inline {
+        Text(s.into())
+    }
+
+    #[test]
+    fn parse_inlines() {
+        assert_eq!(parse("plain"), [text("plain")]);
+        assert_eq!(parse("a **b** *c* `d`"), [
+            text("a "), Bold(vec![text("b")]), text(" "), Italic(vec![text("c")]), text(" "), Code("d".into()),
+        ]);
+        assert_eq!(parse(r"\*not italic\*"), [text("*not italic*")]);
+        assert_eq!(parse("unclosed **bold and `code"), [text("unclosed **bold and `code")]);
+    }
+
+    #[test]
+    fn parse_nesting() {
+        assert_eq!(parse("**bold *italic* `code`**"), [
+            Bold(vec![text("bold "), Italic(vec![text("italic")]), text(" "), Code("code".into())]),
+        ]);
+        assert_eq!(parse("[**shop**](https://example.com) now"), [
+            Link { text: vec![Bold(vec![text("shop")])], url: "https://example.com".into() }, text(" now"),
+        ]);
+        /* not parsed in code */
+        assert_eq!(parse("`**a** [b](https://example.com)`"), [Code("**a** [b](https://example.com)".into())]);
+    }
+
+    #[test]
+    fn parse_links() {
+        assert_eq!(parse("[Rust](https://en.wikipedia.org/wiki/Rust_(programming_language))!"), [
+            Link { text: vec![text("Rust")], url: "https://en.wikipedia.org/wiki/Rust_(programming_language)".into() }, text("!"),
+        ]);
+        assert_eq!(parse("[x](javascript:alert(1))"), [text("[x](javascript:alert(1))")]);
+        assert_eq!(parse("[](https://example.com)"), [text("[](https://example.com)")]);
+    }
+
+    #[test]
+    fn sanitize_keeps_code_spans() {
+        assert_eq!(sanitize("use `Vec<String>` here"), "use `Vec<String>` here");
+        assert_eq!(sanitize("`<script>alert(1)</script>`"), "`<script>alert(1)</script>`");
+        assert_eq!(sanitize("`[x](javascript:alert(1))`"), "`[x](javascript:alert(1))`");
+    }
+
+    #[test]
+    fn sanitize_escapes_tags_without_losing_text() {
+        assert_eq!(sanitize("if a<b and c>d"), r"if a\<b and c>d");
+        assert_eq!(sanitize("1 < 2 > 0"), "1 < 2 > 0");
+        assert_eq!(sanitize("<script>alert(1)</script>"), r"\<script>alert(1)\</script>");
+        assert_eq!(sanitize("<!-- comment -->"), r"\<!-- comment -->");
+
+        let src = "use `Vec<String>` if a<b and c>d";
+        assert_eq!(sanitize(src), r"use `Vec<String>` if a\<b and c>d");
+        assert_eq!(parse(&sanitize(src)), [text("use "), Code("Vec<String>".into()), text(" if a<b and c>d")]);
+    }
+
+    #[test]
+    fn sanitize_unlinks_rejected_schemes() {
+        assert_eq!(sanitize("[x](javascript:alert(1)) after"), "x after");
+        assert_eq!(sanitize("[x](JavaScript:void(0))"), "x");
+        assert_eq!(sanitize("[x](data:text/html,hi)"), "x");
+        assert_eq!(sanitize("[ok](https://example.com) [mail](mailto:a@example.com)"), "[ok](https://example.com) [mail](mailto:a@example.com)");
+        assert_eq!(sanitize("[*a* <b>](javascript:x)"), r"*a* \<b>");
+        /* the text of an unlinked one joined with the rest */
+        assert_eq!(sanitize("<[i](javascript:x)mg src=x>"), r"\<img src=x>");
+    }
+
+    #[test]
+    fn sanitize_is_idempotent() {
+        for src in [
+            "if a<b and c>d", "<b>bold</b>", r"\<b>", "`<b>`", "[x](javascript:alert(1))",
+            "<[i](javascript:x)mg>", "[a](https://example.com/(1))", r"trailing \",
+        ] {
+            let once = sanitize(src);
+            assert_eq!(sanitize(&once), once, "{src}");
+        }
+    }
+}
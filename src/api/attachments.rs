@@ -0,0 +1,285 @@
+use super::jwt::JWTPayload;
+use super::errors::ServerError;
+use super::Repo;
+use models::{Attachment, ID};
+use crate::repository::Repository;
+use ohkami::prelude::*;
+use ohkami::typed::status;
+use ohkami::utils::unix_timestamp;
+use ohkami::format::Query;
+use ohkami::{FromRequest, Memory};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+
+/// R2 bucket `ATTACHMENTS`
+/// ( `#[ohkami::bindings]` doesn't support R2 buckets )
+#[cfg(target_arch = "wasm32")]
+pub struct AttachmentsBucket(worker::Bucket);
+
+#[cfg(target_arch = "wasm32")]
+const _: () = {
+    unsafe impl Send for AttachmentsBucket {}
+    unsafe impl Sync for AttachmentsBucket {}
+
+    impl<'req> FromRequest<'req> for AttachmentsBucket {
+        type Error = Response;
+        fn from_request(req: &'req Request) -> Option<Result<Self, Self::Error>> {
+            Some(req.env().bucket("ATTACHMENTS").map(Self).map_err(|e| {
+                worker::console_error!("{e}");
+                Response::InternalServerError()
+            }))
+        }
+    }
+
+    impl AttachmentsBucket {
+        fn key(attachment_id: &str) -> String {
+            format!("attachments/{attachment_id}")
+        }
+
+        async fn put(&self, attachment_id: &str, mime_type: &str, bytes: &[u8]) -> Result<(), ServerError> {
+            self.0.put(Self::key(attachment_id), bytes.to_vec())
+                .http_metadata(worker::HttpMetadata {
+                    content_type: Some(mime_type.into()),
+                    ..Default::default()
+                })
+                .execute().await?;
+            Ok(())
+        }
+
+        async fn get(&self, attachment_id: &str) -> Result<Option<Vec<u8>>, ServerError> {
+            let Some(object) = self.0.get(Self::key(attachment_id)).execute().await? else {
+                return Ok(None)
+            };
+            let Some(body) = object.body() else {
+                return Ok(None)
+            };
+            Ok(Some(body.bytes().await?))
+        }
+
+        pub async fn delete_all(&self, attachment_ids: &[ID]) -> Result<(), ServerError> {
+            for id in attachment_ids {
+                self.0.delete(Self::key(id)).await?;
+            }
+            Ok(())
+        }
+    }
+};
+
+/// Local directory storing each attachment as a file named by its id
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+pub struct AttachmentsBucket(std::sync::Arc<std::path::PathBuf>);
+
+#[cfg(not(target_arch = "wasm32"))]
+const _: () = {
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    /// From `Memory` of the server ( see `crate::native` )
+    impl<'req> FromRequest<'req> for AttachmentsBucket {
+        type Error = Response;
+        fn from_request(req: &'req Request) -> Option<Result<Self, Self::Error>> {
+            Some(req.memorized::<Self>().cloned().ok_or_else(Response::InternalServerError))
+        }
+    }
+
+    impl AttachmentsBucket {
+        /// Use the directory at `dir`, creating it if not exists
+        pub fn open(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+            let dir = dir.into();
+            std::fs::create_dir_all(&dir)?;
+            Ok(Self(dir.into()))
+        }
+
+        fn path(&self, attachment_id: &str) -> PathBuf {
+            self.0.join(attachment_id)
+        }
+
+        async fn put(&self, attachment_id: &str, _mime_type: &str, bytes: &[u8]) -> Result<(), ServerError> {
+            Ok(tokio::fs::write(self.path(attachment_id), bytes).await?)
+        }
+
+        async fn get(&self, attachment_id: &str) -> Result<Option<Vec<u8>>, ServerError> {
+            match tokio::fs::read(self.path(attachment_id)).await {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        }
+
+        pub async fn delete_all(&self, attachment_ids: &[ID]) -> Result<(), ServerError> {
+            for id in attachment_ids {
+                match tokio::fs::remove_file(self.path(id)).await {
+                    Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+                    _ => ()
+                }
+            }
+            Ok(())
+        }
+    }
+};
+
+
+/// Raw request body with `?name=` query and `Content-Type`
+pub struct Upload<'req> {
+    name:      String,
+    mime_type: &'req str,
+    bytes:     &'req [u8],
+}
+
+impl<'req> FromRequest<'req> for Upload<'req> {
+    type Error = Response;
+    fn from_request(req: &'req Request) -> Option<Result<Self, Self::Error>> {
+        #[derive(Deserialize)] struct UploadQuery {
+            name: String,
+        }
+        let Ok(UploadQuery { name }) = req.query.as_ref()?.parse() else {
+            return Some(Err(Response::BadRequest().with_text("`name` query is required")))
+        };
+        /* no control characters ( e.g. CR LF ) to reach headers like `Content-Disposition` */
+        let name = name.chars().filter(|c| !c.is_control()).collect::<String>();
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > Attachment::MAX_NAME_LEN {
+            return Some(Err(Response::BadRequest().with_text(format!(
+                "`name` must be 1 to {} characters", Attachment::MAX_NAME_LEN
+            ))))
+        }
+        let name = name.to_string();
+
+        Some(Ok(Self {
+            name,
+            mime_type: req.headers.ContentType().unwrap_or_default(),
+            bytes:     req.payload().unwrap_or_default(),
+        }))
+    }
+}
+
+
+const SIGNED_URL_TTL_SECS: u64 = 60 * 60;
+
+fn signature_of(attachment_id: &str, expires: u64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(super::jwt::secret_key().as_bytes()).unwrap();
+    mac.update(format!("{attachment_id}:{expires}").as_bytes());
+    mac
+}
+
+/// Path to download the attachment without `Authorization`, valid for
+/// `SIGNED_URL_TTL_SECS` from now
+pub fn signed_url(attachment_id: &str) -> String {
+    let expires   = unix_timestamp() + SIGNED_URL_TTL_SECS;
+    let signature = signature_of(attachment_id, expires).finalize().into_bytes()
+        .iter().fold(String::new(), |mut hex, b| {hex.push_str(&format!("{b:02x}")); hex});
+    format!("/attachments/{attachment_id}?expires={expires}&signature={signature}")
+}
+
+fn verify(attachment_id: &str, expires: u64, signature: &str) -> bool {
+    let Some(signature) = (0..signature.len()).step_by(2)
+        .map(|i| signature.get(i..i+2).and_then(|hex| u8::from_str_radix(hex, 16).ok()))
+        .collect::<Option<Vec<_>>>()
+    else {return false};
+
+    expires >= unix_timestamp() &&
+    signature_of(attachment_id, expires).verify_slice(&signature).is_ok()
+}
+
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn upload_attachment(card_id: &str,
+    repo:   Repo,
+    auth:   Memory<'_, JWTPayload>,
+    bucket: AttachmentsBucket,
+    upload: Upload<'_>,
+) -> Result<status::Created<JSON<Attachment>>, ServerError> {
+    let Some(mime_type) = Attachment::MIME_TYPES.into_iter().find(|t| *t == upload.mime_type) else {
+        return Err(ServerError::UnsupportedAttachmentType { mime_type: upload.mime_type.into() })
+    };
+    if upload.bytes.len() > Attachment::MAX_SIZE {
+        return Err(ServerError::AttachmentTooLarge { size: upload.bytes.len() })
+    }
+
+    let id = repo.create_attachment(&auth.user_id, card_id, &upload.name, mime_type, upload.bytes.len()).await?;
+
+    if let Err(e) = bucket.put(&id, mime_type, upload.bytes).await {
+        repo.delete_attachment(&auth.user_id, card_id, &id).await?;
+        return Err(e)
+    }
+
+    Ok(status::Created(JSON(Attachment {
+        url:       signed_url(&id),
+        id,
+        name:      upload.name,
+        mime_type: mime_type.into(),
+        size:      upload.bytes.len(),
+    })))
+}
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn delete_attachment((card_id, id): (&str, &str),
+    repo:   Repo,
+    auth:   Memory<'_, JWTPayload>,
+    bucket: AttachmentsBucket,
+) -> Result<(), ServerError> {
+    /* the blob only after the row, not to delete the one of another card */
+    repo.delete_attachment(&auth.user_id, card_id, id).await?;
+    bucket.delete_all(&[id.into()]).await?;
+
+    Ok(())
+}
+
+/// `filename` of only printable ASCII for old clients, and the exact name in
+/// `filename*` ( RFC 5987 )
+fn content_disposition(disposition: &str, name: &str) -> String {
+    let fallback = name.chars()
+        .map(|c| if c.is_ascii_graphic() && !matches!(c, '"' | '\\' | '%') || c == ' ' {c} else {'_'})
+        .collect::<String>();
+    let encoded = name.bytes().fold(String::new(), |mut encoded, b| {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' |
+            b'!' | b'#' | b'$' | b'&' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~'
+            => encoded.push(b as char),
+            _ => encoded.push_str(&format!("%{b:02X}"))
+        }
+        encoded
+    });
+    format!("{disposition}; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
+
+#[derive(Deserialize)]
+pub struct DownloadQuery<'req> {
+    expires:   u64,
+    signature: &'req str,
+}
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn download_attachment(id: &str,
+    repo:   Repo,
+    bucket: AttachmentsBucket,
+    Query(q): Query<DownloadQuery<'_>>,
+) -> Result<Response, ServerError> {
+    if !verify(id, q.expires, q.signature) {
+        return Err(ServerError::InvalidSignature)
+    }
+
+    let Some(attachment) = repo.get_attachment(id).await? else {
+        return Err(ServerError::NotFound { resource: "attachment" })
+    };
+    /* only images and PDFs of the supported types are shown in the browser, and the others downloaded */
+    let (mime_type, disposition) = match Attachment::MIME_TYPES.into_iter().find(|t| *t == attachment.mime_type) {
+        Some(mime_type) if attachment.is_image() || mime_type == "application/pdf" => (mime_type, "inline"),
+        _ => ("application/octet-stream", "attachment"),
+    };
+
+    let Some(bytes) = bucket.get(id).await? else {
+        return Err(ServerError::NotFound { resource: "attachment" })
+    };
+
+    Ok(Response::OK()
+        .with_payload(mime_type, bytes)
+        .with_headers(|h| h
+            .ContentDisposition(content_disposition(disposition, &attachment.name))
+            .XContentTypeOptions("nosniff")
+            .CacheControl("private, max-age=3600")
+        )
+    )
+}
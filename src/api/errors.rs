@@ -1,22 +1,73 @@
+use super::i18n::Reason;
+use crate::repository::RepositoryError;
 use ohkami::prelude::*;
 
 
 #[derive(Debug, thiserror::Error)]
 pub enum ServerError {
+    #[cfg(target_arch = "wasm32")]
     #[error("Error in worker: {0}")]
     Worker(#[from] worker::Error),
 
-    #[error("User(id = {user_id}) requested operation on {resource} whitch is NOT of the user")]
-    NotOwner { user_id: String, resource: &'static str },
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+
+    #[error("Requested {resource} is not found")]
+    NotFound { resource: &'static str },
+
+    #[error("Attachment of {size} bytes is too large")]
+    AttachmentTooLarge { size: usize },
+
+    #[error("Attachment of type `{mime_type}` is not supported")]
+    UnsupportedAttachmentType { mime_type: String },
+
+    #[error("Invalid or expired signature for downloading attachment")]
+    InvalidSignature,
+
+    #[error("`{id}` is not a UUID")]
+    MalformedId { id: String },
+
+    #[error("Sub-todos must be at most {} levels and {} under a todo", models::Todo::MAX_DEPTH, models::Todo::MAX_SUBTODOS)]
+    TooManySubtodos,
+}
+
+impl ServerError {
+    pub fn log(&self) {
+        #[cfg(target_arch = "wasm32")]
+        worker::console_error!("{self}");
+        #[cfg(not(target_arch = "wasm32"))]
+        eprintln!("{self}");
+    }
 }
 
 impl IntoResponse for ServerError {
     fn into_response(self) -> Response {
-        worker::console_error!("{self}");
+        self.log();
 
         match self {
-            Self::Worker  {..} => Response::InternalServerError(),
-            Self::NotOwner{..} => Response::Forbidden(),
+            #[cfg(target_arch = "wasm32")]
+            Self::Worker                   {..} => Response::InternalServerError(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::IO                       {..} => Response::InternalServerError(),
+            Self::Repository(e) => match e {
+                RepositoryError::NotOwner      {..} => Response::Forbidden(),
+                RepositoryError::NotFound      {..} => Response::NotFound(),
+                RepositoryError::AlreadyExists {..} => Response::Conflict(),
+                #[cfg(target_arch = "wasm32")]
+                RepositoryError::D1            {..} => Response::InternalServerError(),
+                #[cfg(not(target_arch = "wasm32"))]
+                RepositoryError::SQLite        {..} => Response::InternalServerError(),
+            },
+            Self::NotFound                 {..} => Response::NotFound(),
+            Self::AttachmentTooLarge       {..} => Response::PayloadTooLarge(),
+            Self::UnsupportedAttachmentType{..} => Response::UnsupportedMediaType(),
+            Self::InvalidSignature              => Reason::InvalidSignature.tell(Response::Forbidden()),
+            Self::MalformedId              {..} => Response::BadRequest(),
+            Self::TooManySubtodos               => Response::BadRequest(),
         }
     }
 }
@@ -0,0 +1,119 @@
+//! Messages of the error responses, in the language of `Accept-Language`
+//!
+//! `ServerError` and the fangs respond errors by the status alone, and the
+//! details are only logged, so the body is written here by the status
+//! ( or by the `Reason` told by `ServerError` ).
+
+use models::Locale;
+use ohkami::prelude::*;
+use ohkami::header::append;
+use ohkami::{Fang, FangProc};
+use std::future::Future;
+
+
+/// What an error response tells more specifically than its status
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Reason {
+    InvalidSignature,
+}
+
+impl Reason {
+    /// Internal header from `ServerError` to `Localize`, not sent to the client
+    const HEADER: &'static str = "X-Ohkami-Yew-Todo-Reason";
+
+    fn key(self) -> &'static str {
+        match self {
+            Self::InvalidSignature => "invalid-signature",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        [Self::InvalidSignature].into_iter().find(|reason| reason.key() == key)
+    }
+
+    /// `res` to be given the message of this reason by `Localize`
+    pub fn tell(self, res: Response) -> Response {
+        res.with_headers(|h| h.custom(Self::HEADER, self.key()))
+    }
+}
+
+
+/// Fang to be the outermost one, to fill the empty body of every error response
+pub struct Localize;
+
+impl<I: FangProc> Fang<I> for Localize {
+    type Proc = LocalizeProc<I>;
+    fn chain(&self, inner: I) -> Self::Proc {
+        LocalizeProc { inner }
+    }
+}
+
+pub struct LocalizeProc<I: FangProc> {
+    inner: I,
+}
+
+impl<I: FangProc> FangProc for LocalizeProc<I> {
+    fn bite<'b>(&'b self, req: &'b mut Request) -> impl Future<Output = Response> + Send {
+        let locale = Locale::negotiate(req.headers.AcceptLanguage().unwrap_or_default());
+        async move {
+            let mut res = self.inner.bite(req).await;
+
+            let reason = res.headers.custom(Reason::HEADER).and_then(Reason::from_key);
+            if reason.is_some() {
+                res.headers.set().custom(Reason::HEADER, None);
+            }
+
+            let message = match reason {
+                Some(reason) => Some(reason_message(reason, locale)),
+                None => message(res.status, locale),
+            };
+            if let (None, Some(message)) = (res.payload(), message) {
+                res.set_text(message);
+                res.headers.set()
+                    .ContentLanguage(locale.tag())
+                    .Vary(append("Accept-Language"));
+            }
+            res
+        }
+    }
+}
+
+fn reason_message(reason: Reason, locale: Locale) -> &'static str {
+    match locale {
+        Locale::En => match reason {
+            Reason::InvalidSignature => "The link is invalid or has expired ( reload the page for a new one )",
+        },
+        Locale::Ja => match reason {
+            Reason::InvalidSignature => "リンクが無効か、期限切れです ( ページを再読み込みしてください )",
+        },
+    }
+}
+
+fn message(status: Status, locale: Locale) -> Option<&'static str> {
+    Some(match locale {
+        Locale::En => match status {
+            Status::BadRequest           => "The request is malformed",
+            Status::Unauthorized         => "The token is missing or invalid",
+            Status::Forbidden            => "It isn't yours, or has already been deleted",
+            Status::NotFound             => "Not found",
+            Status::Conflict             => "It already exists, or the same request is still in progress",
+            Status::PayloadTooLarge      => "The attachment is too large",
+            Status::UnsupportedMediaType => "Only images and PDFs can be attached",
+            Status::UnprocessableEntity  => "The Idempotency-Key has been used for another request",
+            Status::InternalServerError  => "The server failed",
+            _ => return None,
+        },
+        Locale::Ja => match status {
+            Status::BadRequest           => "リクエストの形式が正しくありません",
+            Status::Unauthorized         => "トークンがないか、無効です",
+            Status::Forbidden            => "あなたのものではないか、すでに削除されています",
+            Status::NotFound             => "見つかりません",
+            Status::Conflict             => "すでに存在するか、同じリクエストを処理中です",
+            Status::PayloadTooLarge      => "添付ファイルが大きすぎます",
+            Status::UnsupportedMediaType => "添付できるのは画像とPDFのみです",
+            Status::UnprocessableEntity  => "このIdempotency-Keyは別のリクエストに使われています",
+            Status::InternalServerError  => "サーバーでエラーが発生しました",
+            _ => return None,
+        },
+    })
+}
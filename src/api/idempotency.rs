@@ -0,0 +1,141 @@
+//! `Idempotency-Key` of the POST requests in `/api`
+//!
+//! The successful response to the first request with a key is recorded
+//! and replayed to the later ones with the same key, so that a retried
+//! or double-clicked action is done only once. A later one of another
+//! method, path or body is rejected. PUT and DELETE are idempotent by
+//! themselves, and their keys are ignored.
+
+use super::errors::ServerError;
+use super::jwt::JWTPayload;
+use crate::repository::{IdempotencyRecord, Repository};
+use ohkami::prelude::*;
+use ohkami::{Fang, FangProc};
+use std::future::Future;
+
+#[cfg(target_arch = "wasm32")]
+use {crate::repository::D1Repository, ohkami::FromRequest};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::repository::SqliteRepository;
+
+
+pub const HEADER: &str = "Idempotency-Key";
+/// Header set to `true` on the replayed responses
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// Keys are up to this length, like UUIDs or random hex strings
+const MAX_KEY_LEN: usize = 255;
+
+/// Fang to be after `jwt::fang` : keys are of each user
+///
+/// On native, the repository is given here rather than by `Memory`, since ohkami 0.20
+/// may run the outer fangs inside the ones of `/api` ( when compressing the router ).
+pub struct Idempotency {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub repo: SqliteRepository,
+}
+
+impl<I: FangProc> Fang<I> for Idempotency {
+    type Proc = IdempotencyProc<I>;
+    fn chain(&self, inner: I) -> Self::Proc {
+        IdempotencyProc {
+            #[cfg(not(target_arch = "wasm32"))]
+            repo: self.repo.clone(),
+            inner,
+        }
+    }
+}
+
+pub struct IdempotencyProc<I: FangProc> {
+    #[cfg(not(target_arch = "wasm32"))]
+    repo:  SqliteRepository,
+    inner: I,
+}
+
+impl<I: FangProc> FangProc for IdempotencyProc<I> {
+    fn bite<'b>(&'b self, req: &'b mut Request) -> impl Future<Output = Response> + Send {
+        #[cfg(target_arch = "wasm32")] {
+            worker::send::SendFuture::new(self.bite_idempotently(req))
+        }
+        #[cfg(not(target_arch = "wasm32"))] {
+            self.bite_idempotently(req)
+        }
+    }
+}
+
+/// `{method} {path} {hex SHA-256 of the body}` of `req`, to tell it from
+/// another request with the same key
+fn fingerprint(req: &Request) -> String {
+    use sha2::{Digest, Sha256};
+
+    let hash = Sha256::digest(req.payload().unwrap_or_default());
+    let hash = hash.iter().fold(String::new(), |mut hex, b| {hex.push_str(&format!("{b:02x}")); hex});
+    format!("{} {} {hash}", req.method, req.path.str())
+}
+
+impl<I: FangProc> IdempotencyProc<I> {
+    async fn bite_idempotently(&self, req: &mut Request) -> Response {
+        /* custom headers are looked up case-sensitively, and most clients send them in lowercase */
+        let key = req.headers.custom("idempotency-key").or_else(|| req.headers.custom(HEADER));
+        let (Some(key), Method::POST) = (key, req.method) else {
+            return self.inner.bite(req).await
+        };
+        if key.is_empty() || key.len() > MAX_KEY_LEN {
+            return Response::BadRequest()
+        }
+        let key = key.to_owned();
+
+        let Some(user_id) = req.memorized::<JWTPayload>().map(|p| p.user_id.clone()) else {
+            return self.inner.bite(req).await
+        };
+        #[cfg(target_arch = "wasm32")]
+        let repo = &match D1Repository::from_request(req) {
+            Some(Ok(repo)) => repo,
+            Some(Err(res)) => return res,
+            None => return Response::InternalServerError(),
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let repo = &self.repo;
+        let request = fingerprint(req);
+
+        match repo.claim_idempotency_key(&user_id, &key, &request).await {
+            Err(e) => ServerError::from(e).into_response(),
+
+            Ok(None) => {
+                let res = self.inner.bite(req).await;
+
+                let recorded = if (200..300).contains(&res.status.code()) {
+                    let body = res.payload().map(String::from_utf8_lossy);
+                    repo.save_idempotent_response(&user_id, &key, res.status.code(), body.as_deref()).await
+                } else {
+                    /* let the client retry it with the same key */
+                    repo.release_idempotency_key(&user_id, &key).await
+                };
+                if let Err(e) = recorded {
+                    ServerError::from(e).log()
+                }
+
+                res
+            }
+
+            Ok(Some(IdempotencyRecord { request: claimed_by, .. })) if claimed_by != request => {
+                Response::UnprocessableEntity()
+            }
+            Ok(Some(IdempotencyRecord { status: None, .. })) => {
+                /* `Retry-After` tells it from the `409` of the handlers, like for an existing card */
+                Response::Conflict().with_headers(|h| h.RetryAfter("1"))
+            }
+            Ok(Some(IdempotencyRecord { status: Some(status), body, .. })) => {
+                let mut res = match status {
+                    201 => Response::Created(),
+                    204 => Response::NoContent(),
+                    _   => Response::OK(),
+                };
+                if let Some(body) = body {
+                    res.set_payload("application/json", body.into_bytes());
+                }
+                res.with_headers(|h| h.custom(REPLAYED_HEADER, "true"))
+            }
+        }
+    }
+}
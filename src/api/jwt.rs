@@ -1,5 +1,4 @@
-use super::Bindings;
-use ohkami::serde::{Serialize, Deserialize};
+use ohkami::prelude::*;
 use ohkami::fang::{JWT, JWTToken};
 use ohkami::utils::unix_timestamp;
 
@@ -10,10 +9,43 @@ pub struct JWTPayload {
     iat: u64,
 }
 
-pub fn fang() -> JWT<JWTPayload> {
-    JWT::default(Bindings::JWT_SECRET_KEY)
+/// `JWT_SECRET_KEY` of wrangler.toml on Workers,
+/// or of the environment variable on native ( checked at startup by `crate::native::serve` )
+pub fn secret_key() -> &'static str {
+    #[cfg(target_arch = "wasm32")] {
+        crate::Bindings::JWT_SECRET_KEY
+    }
+    #[cfg(not(target_arch = "wasm32"))] {
+        static JWT_SECRET_KEY: std::sync::LazyLock<String> = std::sync::LazyLock::new(||
+            std::env::var("JWT_SECRET_KEY").expect("`JWT_SECRET_KEY` is not set")
+        );
+        &JWT_SECRET_KEY
+    }
+}
+
+/// `JWT` fang responding `401 Unauthorized` to any invalid token
+///
+/// `ohkami::fang::JWT` itself responds `400` or `500` to some malformed ones,
+/// like one not of `JWTPayload`, which are the client's fault all the same and
+/// are to be told as an invalid session ( not retried as a server error ).
+#[derive(Clone)]
+pub struct Auth(JWT<JWTPayload>);
+
+impl FangAction for Auth {
+    async fn fore<'a>(&'a self, req: &'a mut Request) -> Result<(), Response> {
+        let payload = self.0.verified(req).map_err(|res| match res.status {
+            Status::OK => res, /* preflight */
+            _          => Response::Unauthorized(),
+        })?;
+        req.memorize(payload);
+        Ok(())
+    }
+}
+
+pub fn fang() -> Auth {
+    Auth(JWT::default(secret_key()))
 }
 
 pub fn new_token_for(user_id: String) -> JWTToken {
-    self::fang().issue(JWTPayload { user_id, iat: unix_timestamp() })
+    JWT::default(secret_key()).issue(JWTPayload { user_id, iat: unix_timestamp() })
 }
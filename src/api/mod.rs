@@ -1,187 +1,148 @@
 pub mod errors;
 pub mod jwt;
-pub mod utils;
+pub mod idempotency;
+pub mod i18n;
+pub mod openapi;
+mod templates;
+mod attachments;
+mod shelves;
+
+pub use templates::{list_templates, create_template, delete_template, instantiate_template};
+pub use shelves::{list_archive, archive_card, unarchive_card, list_trash, restore_card, delete_card_forever};
+pub use attachments::AttachmentsBucket;
+pub use attachments::{upload_attachment, delete_attachment, download_attachment};
 
 use self::jwt::JWTPayload;
 use self::errors::ServerError;
-use crate::Bindings;
-use crate::models::{Card, CreateCardRequest, CreateCardResponse, SignupResponse, Todo, UpdateCard};
-use web_sys::{wasm_bindgen::JsCast, WorkerGlobalScope, js_sys};
+use models::{Card, CreateCardRequest, CreateCardResponse, SignupResponse, Todo, UpdateCard};
+use models::{PairRequest, PairingCodeResponse};
+use crate::repository::{Repository, Shelf, PAIRING_CODE_TTL};
 use ohkami::typed::status;
-use ohkami::serde::Deserialize;
-use ohkami::utils::unix_timestamp;
 use ohkami::Memory;
 use ohkami::format::JSON;
-use std::array;
 
+#[cfg(target_arch = "wasm32")]
+use crate::repository::D1Repository as Repo;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::repository::SqliteRepository as Repo;
 
-#[worker::send]
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
 pub async fn signup(
-    b: Bindings,
+    repo: Repo,
 ) -> Result<JSON<SignupResponse>, ServerError> {
-    let user_id = WorkerGlobalScope::unchecked_from_js(js_sys::global().into())
-        .crypto().unwrap().random_uuid();
+    let user_id = repo.create_user().await?;
+
+    Ok(JSON(SignupResponse {
+        token: jwt::new_token_for(user_id)
+    }))
+}
+
+/// Issue a code for another device to log in as the user by `pair`,
+/// not to show the token itself
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn create_pairing_code(
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
+) -> Result<status::Created<JSON<PairingCodeResponse>>, ServerError> {
+    let code = repo.create_pairing_code(&auth.user_id).await?;
+
+    Ok(status::Created(JSON(PairingCodeResponse {
+        code,
+        expires_in: PAIRING_CODE_TTL,
+    })))
+}
 
-    b.DB.prepare("INSERT INTO users (id) VALUES (?)")
-        .bind(&[(&user_id).into()])?
-        .run().await?;
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn pair(
+    repo: Repo,
+    JSON(req): JSON<PairRequest>,
+) -> Result<JSON<SignupResponse>, ServerError> {
+    /* as typed by the user */
+    let user_id = repo.consume_pairing_code(&req.code.trim().to_ascii_uppercase()).await?;
 
     Ok(JSON(SignupResponse {
         token: jwt::new_token_for(user_id)
     }))
 }
 
-#[worker::send]
+#[cfg_attr(target_arch = "wasm32", worker::send)]
 pub async fn create_card(
-    b:    Bindings,
+    repo: Repo,
     auth: Memory<'_, JWTPayload>,
     JSON(req): JSON<CreateCardRequest>
 ) -> Result<status::Created<JSON<CreateCardResponse>>, ServerError> {
-    let id = WorkerGlobalScope::unchecked_from_js(js_sys::global().into())
-        .crypto().unwrap().random_uuid();
-
-    b.DB.batch(vec![
-        b.DB.prepare("INSERT INTO cards (id, user_id, title, created_at) VALUES (?1, ?2, ?3, ?4)")
-            .bind(&[
-                (&id).into(),
-                (&auth.user_id).into(),
-                req.title.into(),
-                (unix_timestamp() as usize).into()
-            ])?,
-        b.DB.prepare(format!("INSERT INTO todos (card_id, content) VALUES {}", ["(?,?)"; Card::N_TODOS].join(",")))
-            .bind(array::from_fn::<_, {Card::N_TODOS * 2}, _>(|i|
-                if i%2==0 {(&id).into()} else {(&req.todos[i/2]).into()}
-            ).as_slice())?,
-        ]).await?;
+    if let Some(id) = req.id.as_deref().filter(|id| !models::is_uuid(id)) {
+        return Err(ServerError::MalformedId { id: id.to_owned() })
+    }
+
+    let id = repo.create_card(&auth.user_id, req.id.as_deref(), &req.title, req.todos.map(|content| {
+        let mut todo = Todo::new(content);
+        todo.sanitize();
+        todo
+    })).await?;
 
     Ok(status::Created(JSON(CreateCardResponse { id })))
 }
 
-#[worker::send]
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn duplicate_card(id: &str,
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
+) -> Result<status::Created<JSON<CreateCardResponse>>, ServerError> {
+    let Card { title, mut todos, .. } = repo.get_card(&auth.user_id, id).await?;
+    for todo in &mut todos {
+        todo.set_completed(false)
+    }
+    let id = repo.create_card(&auth.user_id, None, &title, todos).await?;
+
+    Ok(status::Created(JSON(CreateCardResponse { id })))
+}
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
 pub async fn list_cards(
-    b:    Bindings,
+    repo: Repo,
     auth: Memory<'_, JWTPayload>,
 ) -> Result<JSON<Vec<Card>>, ServerError> {
-    let card_records = {
-        #[derive(Deserialize)] struct Record {
-            id:    String,
-            title: String,
-        }
-        b.DB.prepare("SELECT id, title FROM cards WHERE user_id = ? ORDER BY created_at ASC")
-            .bind(&[(&auth.user_id).into()])?
-            .all().await?.results::<Record>()?
-    };
-
-    let mut todo_records = if card_records.is_empty() {vec![]} else {
-        #[derive(Deserialize)] struct Record {
-            content:      String,
-            completed_at: Option<u64>,
-        }
-        b.DB.prepare(format!(
-                "SELECT content, completed_at FROM todos
-                WHERE card_id IN ({})
-                ORDER BY id DESC", /*
-                    This automatically order by card index in `card_records` DESC
-                    (e.g. by cards.created_at DESC)
-                    due to `create_card`'s behavior
-                */ /*
-                    Here we get todos in reversed order to pop from one
-                    having smaller card_id and id in later proccess
-                */
-                vec!["?"; card_records.len()].join(",")
-            ))
-            .bind(&card_records.iter().map(|r| (&r.id).into()).collect::<Vec<_>>())?
-            .all().await?.results::<Record>()?
-    };
-
-    Ok(JSON(card_records.into_iter().map(|r| Card {
-        id:    r.id,
-        title: r.title,
-        todos: array::from_fn(|_| {
-            let next_todo = todo_records.pop().unwrap();
-            Todo {
-                content:   next_todo.content,
-                completed: next_todo.completed_at.is_some()
-            }
-        })
-    }).collect()))
+    Ok(JSON(signed(repo.list_cards(&auth.user_id).await?)))
 }
 
-#[worker::send]
+/// `cards` with the signed URLs of their attachments
+fn signed(mut cards: Vec<Card>) -> Vec<Card> {
+    for attachment in cards.iter_mut().flat_map(|card| &mut card.attachments) {
+        attachment.url = attachments::signed_url(&attachment.id)
+    }
+    cards
+}
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
 pub async fn update_card(id: &str,
-    b:    Bindings,
+    repo: Repo,
     auth: Memory<'_, JWTPayload>,
     JSON(req): JSON<UpdateCard>,
 ) -> Result<(), ServerError> {
-    b.assert_user_is_owner_of_card(&auth.user_id, id).await?;
-
-    let current_title = b.DB.prepare("SELECT title FROM cards WHERE id = ?")
-        .bind(&[id.into()])?.first::<String>(Some("title")).await?.unwrap();
-
-    let current_todos = {
-        #[derive(Deserialize)] struct Record {
-            id:           usize,
-            content:      String,
-            completed_at: Option<u64>,
-        }
-        impl PartialEq<Todo> for Record {
-            fn eq(&self, other: &Todo) -> bool {
-                self.content == other.content &&
-                self.completed_at.is_some() == other.completed
-            }
-        }
-        b.DB.prepare("SELECT id, content, completed_at FROM todos WHERE card_id = ?")
-            .bind(&[id.into()])?.all().await?.results::<Record>()?
-    };
-    
-    let updates = {
-        let mut updates = Vec::new();
-
-        if current_title != req.title {
-            updates.push(
-                b.DB.prepare("UPDATE cards SET title = ?1 WHERE id = ?2")
-                    .bind(&[req.title.into(), id.into()])?
-            )
-        }
-
-        let statement_update_todo = b.DB.prepare(
-            "UPDATE todos SET content = ?1, completed_at = ?2 WHERE id = ?3"
-        );
-        for (current, new) in current_todos.into_iter().zip(req.todos) {
-            use worker::D1Type::{Text, Integer, Null};
-            if current != new {
-                updates.push(statement_update_todo
-                    .bind_refs(&[
-                        Text(&new.content),
-                        if new.completed {Integer(unix_timestamp() as i32)} else {Null},
-                        Integer(current.id as _)
-                    ])?
-                )
-            }
-        }
-
-        updates
-    };
-    if !updates.is_empty() {
-        b.DB.batch(updates).await?;
+    if !req.todos.iter().all(Todo::is_within_limits) {
+        return Err(ServerError::TooManySubtodos)
+    }
+
+    let mut req = req;
+    for todo in &mut req.todos {
+        todo.sanitize();
+        todo.sync_completion();
     }
 
+    repo.update_card(&auth.user_id, id, req).await?;
+
     Ok(())
 }
 
-#[worker::send]
+/// Move the card to the trash ( see `shelves` )
+#[cfg_attr(target_arch = "wasm32", worker::send)]
 pub async fn delete_card(id: &str,
-    b:    Bindings,
-    auth: Memory<'_, JWTPayload>
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
 ) -> Result<(), ServerError> {
-    b.assert_user_is_owner_of_card(&auth.user_id, id).await?;
-
-    b.DB.batch(vec![
-        b.DB.prepare("DELETE FROM cards WHERE id = ?")
-            .bind(&[id.into()])?,
-        b.DB.prepare(format!("DELETE FROM todos WHERE card_id = ?"))
-            .bind(&[id.into()])?,
-    ]).await?;
+    repo.shelve_card(&auth.user_id, id, Shelf::Trash).await?;
 
     Ok(())
 }
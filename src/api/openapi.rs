@@ -0,0 +1,339 @@
+//! OpenAPI 3 document of the routes in `crate::ohkami`,
+//! with the schemas generated from `models`
+
+use models::{Attachment, Card, CreateCardRequest, CreateCardResponse, SignupResponse, Todo, UpdateCard};
+use models::{CreateTemplateRequest, CreateTemplateResponse, Template};
+use models::{PairRequest, PairingCodeResponse};
+use super::idempotency;
+use crate::repository::IDEMPOTENCY_KEY_TTL;
+use ohkami::format::JSON;
+use ohkami::serde::json::{json, Map, Value};
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use std::sync::LazyLock;
+
+
+pub async fn openapi() -> JSON<&'static Value> {
+    static DOCUMENT: LazyLock<Value> = LazyLock::new(document);
+    JSON(&DOCUMENT)
+}
+
+pub fn document() -> Value {
+    let mut schemas = SchemaGenerator::new(SchemaSettings::openapi3());
+
+    let id = json!({ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } });
+
+    let mut paths = json!({
+        "/signup": {
+            "post": {
+                "summary": "Sign up as a new user",
+                "security": [],
+                "responses": {
+                    "200": json_response("JWT for `/api`", schema::<SignupResponse>(&mut schemas)),
+                },
+            },
+        },
+        "/pair": {
+            "post": {
+                "summary": "Log in as the user of a pairing code, using it up",
+                "security": [],
+                "requestBody": json_body(schema::<PairRequest>(&mut schemas)),
+                "responses": {
+                    "200": json_response("JWT for `/api`", schema::<SignupResponse>(&mut schemas)),
+                    "400": response("Malformed request"),
+                    "404": response("Wrong, used or expired code"),
+                },
+            },
+        },
+        "/attachments/{id}": {
+            "get": {
+                "summary": "Download an attachment by the signed `url` of it",
+                "security": [],
+                "parameters": [
+                    id,
+                    { "name": "expires",   "in": "query", "required": true, "schema": { "type": "integer", "format": "uint64" } },
+                    { "name": "signature", "in": "query", "required": true, "schema": { "type": "string" } },
+                ],
+                "responses": {
+                    "200": {
+                        "description": "Content of the attachment",
+                        "content": binary_content(),
+                    },
+                    "403": response("Invalid or expired signature"),
+                    "404": response("No such attachment"),
+                },
+            },
+        },
+        "/api/cards": {
+            "get": {
+                "summary": "List the cards of the user on the board, neither archived nor in the trash",
+                "responses": {
+                    "200": json_response("Cards in created order", schema::<Vec<Card>>(&mut schemas)),
+                    "401": response("Missing or invalid JWT"),
+                },
+            },
+            "post": {
+                "summary": "Create a card",
+                "requestBody": json_body(schema::<CreateCardRequest>(&mut schemas)),
+                "responses": {
+                    "201": json_response("Created", schema::<CreateCardResponse>(&mut schemas)),
+                    "400": response("Malformed card, or `id` not a UUID"),
+                    "401": response("Missing or invalid JWT"),
+                    "409": response("`id` is taken by an existing card"),
+                },
+            },
+        },
+        "/api/cards/{id}": {
+            "parameters": [id],
+            "put": {
+                "summary": "Update the title and todos of a card",
+                "requestBody": json_body(schema::<UpdateCard>(&mut schemas)),
+                "responses": {
+                    "200": response("Updated"),
+                    "400": response(&format!("Malformed card, or sub-todos over {} levels or {} under a todo", Todo::MAX_DEPTH, Todo::MAX_SUBTODOS)),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a card of the user"),
+                },
+            },
+            "delete": {
+                "summary": "Move a card to the trash ( from the board or the archive )",
+                "responses": {
+                    "200": response("Moved to the trash"),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a card of the user"),
+                },
+            },
+        },
+        "/api/cards/{id}/duplicate": {
+            "parameters": [id],
+            "post": {
+                "summary": "Create a copy of a card with all the todos uncompleted",
+                "responses": {
+                    "201": json_response("Created", schema::<CreateCardResponse>(&mut schemas)),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a card of the user"),
+                },
+            },
+        },
+        "/api/cards/{id}/attachments": {
+            "parameters": [id],
+            "post": {
+                "summary": "Attach a file to a card",
+                "parameters": [
+                    {
+                        "name": "name", "in": "query", "required": true,
+                        "description": "file name, with control characters stripped",
+                        "schema": { "type": "string", "minLength": 1, "maxLength": Attachment::MAX_NAME_LEN },
+                    },
+                ],
+                "requestBody": {
+                    "required": true,
+                    "content": binary_content(),
+                },
+                "responses": {
+                    "201": json_response("Created", schema::<Attachment>(&mut schemas)),
+                    "400": response("Missing `name`"),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a card of the user"),
+                    "413": response(&format!("Larger than {} bytes", Attachment::MAX_SIZE)),
+                    "415": response("Unsupported type"),
+                },
+            },
+        },
+        "/api/cards/{id}/attachments/{attachment_id}": {
+            "parameters": [
+                id,
+                { "name": "attachment_id", "in": "path", "required": true, "schema": { "type": "string" } },
+            ],
+            "delete": {
+                "summary": "Delete an attachment of a card",
+                "responses": {
+                    "200": response("Deleted"),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a card of the user"),
+                    "404": response("No attachment of the id in the card"),
+                },
+            },
+        },
+        "/api/archive": {
+            "get": {
+                "summary": "List the archived cards of the user",
+                "responses": {
+                    "200": json_response("Cards, the latest archived first", schema::<Vec<Card>>(&mut schemas)),
+                    "401": response("Missing or invalid JWT"),
+                },
+            },
+        },
+        "/api/archive/{id}": {
+            "parameters": [id],
+            "put": {
+                "summary": "Archive a card",
+                "responses": {
+                    "200": response("Archived"),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a card of the user"),
+                },
+            },
+            "delete": {
+                "summary": "Put an archived card back on the board",
+                "responses": {
+                    "200": response("Unarchived"),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a card of the user"),
+                },
+            },
+        },
+        "/api/trash": {
+            "get": {
+                "summary": "List the cards of the user in the trash, kept until deleted from there",
+                "responses": {
+                    "200": json_response("Cards, the latest moved first", schema::<Vec<Card>>(&mut schemas)),
+                    "401": response("Missing or invalid JWT"),
+                },
+            },
+        },
+        "/api/trash/{id}": {
+            "parameters": [id],
+            "delete": {
+                "summary": "Delete a card in the trash for good, with its attachments",
+                "responses": {
+                    "200": response("Deleted"),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a card of the user"),
+                    "404": response("The card is not in the trash"),
+                },
+            },
+        },
+        "/api/trash/{id}/restore": {
+            "parameters": [id],
+            "post": {
+                "summary": "Put a card in the trash back on the board",
+                "responses": {
+                    "200": response("Restored"),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a card of the user"),
+                },
+            },
+        },
+        "/api/templates": {
+            "get": {
+                "summary": "List the templates of the user",
+                "responses": {
+                    "200": json_response("Templates in created order", schema::<Vec<Template>>(&mut schemas)),
+                    "401": response("Missing or invalid JWT"),
+                },
+            },
+            "post": {
+                "summary": "Save the title and todo contents of a card as a template",
+                "description": "Only the contents of the top-level todos are saved: not their completions, notes and sub-todos, nor the attachments.",
+                "requestBody": json_body(schema::<CreateTemplateRequest>(&mut schemas)),
+                "responses": {
+                    "201": json_response("Created", schema::<CreateTemplateResponse>(&mut schemas)),
+                    "400": response("Malformed request"),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a card of the user"),
+                },
+            },
+        },
+        "/api/templates/{id}": {
+            "parameters": [id],
+            "delete": {
+                "summary": "Delete a template",
+                "responses": {
+                    "200": response("Deleted"),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a template of the user"),
+                },
+            },
+        },
+        "/api/templates/{id}/instantiate": {
+            "parameters": [id],
+            "post": {
+                "summary": "Create a card from a template",
+                "responses": {
+                    "201": json_response("Created", schema::<CreateCardResponse>(&mut schemas)),
+                    "401": response("Missing or invalid JWT"),
+                    "403": response("Not a template of the user"),
+                },
+            },
+        },
+        "/api/pairing-codes": {
+            "post": {
+                "summary": "Issue a code to log in as the user from another device by `/pair`",
+                "responses": {
+                    "201": json_response("Created", schema::<PairingCodeResponse>(&mut schemas)),
+                    "401": response("Missing or invalid JWT"),
+                },
+            },
+        },
+    });
+
+    /* every POST in `/api` accepts `Idempotency-Key` ( see `api::idempotency` ) */
+    for (_, item) in paths.as_object_mut().unwrap().iter_mut().filter(|(path, _)| path.starts_with("/api/")) {
+        for (_, operation) in item.as_object_mut().unwrap().iter_mut().filter(|(method, _)| *method == "post") {
+            operation.as_object_mut().unwrap()
+                .entry("parameters").or_insert_with(|| json!([]))
+                .as_array_mut().unwrap()
+                .push(json!({
+                    "name": idempotency::HEADER, "in": "header", "required": false, "schema": { "type": "string", "maxLength": 255 },
+                    "description": format!("Replay the successful response to the first request with the same key in {} hours", IDEMPOTENCY_KEY_TTL / 60 / 60),
+                }));
+            let responses = operation["responses"].as_object_mut().unwrap();
+            responses.entry("400").or_insert_with(|| response("Malformed request"));
+            let in_progress = "The first request with the `Idempotency-Key` is in progress ( with `Retry-After` )";
+            match responses.get_mut("409") {
+                Some(conflict) => conflict["description"] = json!(format!("{}; {in_progress}", conflict["description"].as_str().unwrap())),
+                None => {responses.insert("409".into(), response(in_progress));}
+            }
+            responses.insert("422".into(), response("The `Idempotency-Key` is used for another request, of another method, path or body"));
+        }
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title":   env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Errors have a plain text message in the language of `Accept-Language` ( English or Japanese )",
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas.take_definitions(),
+            "securitySchemes": {
+                "jwt": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" },
+            },
+        },
+        "security": [{ "jwt": [] }],
+    })
+}
+
+/// Schema of `T` referring to `#/components/schemas` for the struct types,
+/// which are collected into `schemas`
+fn schema<T: JsonSchema>(schemas: &mut SchemaGenerator) -> Value {
+    json!(schemas.subschema_for::<T>())
+}
+
+fn json_body(schema: Value) -> Value {
+    json!({
+        "required": true,
+        "content": { "application/json": { "schema": schema } },
+    })
+}
+
+fn json_response(description: &str, schema: Value) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": schema } },
+    })
+}
+
+/// Response without body ( or with a plain text one at most, for errors )
+fn response(description: &str) -> Value {
+    json!({ "description": description })
+}
+
+fn binary_content() -> Map<String, Value> {
+    Attachment::MIME_TYPES.into_iter()
+        .map(|mime_type| (mime_type.into(), json!({ "schema": { "type": "string", "format": "binary" } })))
+        .collect()
+}
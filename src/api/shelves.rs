@@ -0,0 +1,69 @@
+use super::jwt::JWTPayload;
+use super::errors::ServerError;
+use super::{AttachmentsBucket, signed, Repo};
+use models::Card;
+use crate::repository::{Repository, Shelf};
+use ohkami::Memory;
+use ohkami::format::JSON;
+
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn list_archive(
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
+) -> Result<JSON<Vec<Card>>, ServerError> {
+    Ok(JSON(signed(repo.list_shelved_cards(&auth.user_id, Shelf::Archive).await?)))
+}
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn archive_card(id: &str,
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
+) -> Result<(), ServerError> {
+    repo.shelve_card(&auth.user_id, id, Shelf::Archive).await?;
+
+    Ok(())
+}
+
+/// Put the archived card back on the board
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn unarchive_card(id: &str,
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
+) -> Result<(), ServerError> {
+    repo.unshelve_card(&auth.user_id, id).await?;
+
+    Ok(())
+}
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn list_trash(
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
+) -> Result<JSON<Vec<Card>>, ServerError> {
+    Ok(JSON(signed(repo.list_shelved_cards(&auth.user_id, Shelf::Trash).await?)))
+}
+
+/// Put the card in the trash back on the board
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn restore_card(id: &str,
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
+) -> Result<(), ServerError> {
+    repo.unshelve_card(&auth.user_id, id).await?;
+
+    Ok(())
+}
+
+/// Delete the card in the trash for good, with its attachments
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn delete_card_forever(id: &str,
+    repo:   Repo,
+    auth:   Memory<'_, JWTPayload>,
+    bucket: AttachmentsBucket,
+) -> Result<(), ServerError> {
+    let attachment_ids = repo.delete_card(&auth.user_id, id).await?;
+    bucket.delete_all(&attachment_ids).await?;
+
+    Ok(())
+}
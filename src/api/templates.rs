@@ -0,0 +1,52 @@
+use super::jwt::JWTPayload;
+use super::errors::ServerError;
+use super::Repo;
+use models::{Card, CreateCardResponse, CreateTemplateRequest, CreateTemplateResponse, Template, Todo};
+use crate::repository::Repository;
+use ohkami::typed::status;
+use ohkami::Memory;
+use ohkami::format::JSON;
+
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn list_templates(
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
+) -> Result<JSON<Vec<Template>>, ServerError> {
+    Ok(JSON(repo.list_templates(&auth.user_id).await?))
+}
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn create_template(
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
+    JSON(req): JSON<CreateTemplateRequest>,
+) -> Result<status::Created<JSON<CreateTemplateResponse>>, ServerError> {
+    let Card { title, todos, .. } = repo.get_card(&auth.user_id, &req.card_id).await?;
+
+    let id = repo.create_template(&auth.user_id, &title, todos.map(|todo| todo.content)).await?;
+
+    Ok(status::Created(JSON(CreateTemplateResponse { id })))
+}
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn instantiate_template(id: &str,
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
+) -> Result<status::Created<JSON<CreateCardResponse>>, ServerError> {
+    let Template { title, todos, .. } = repo.get_template(&auth.user_id, id).await?;
+
+    let id = repo.create_card(&auth.user_id, None, &title, todos.map(Todo::new)).await?;
+
+    Ok(status::Created(JSON(CreateCardResponse { id })))
+}
+
+#[cfg_attr(target_arch = "wasm32", worker::send)]
+pub async fn delete_template(id: &str,
+    repo: Repo,
+    auth: Memory<'_, JWTPayload>,
+) -> Result<(), ServerError> {
+    repo.delete_template(&auth.user_id, id).await?;
+
+    Ok(())
+}
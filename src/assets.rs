@@ -0,0 +1,35 @@
+//! `index.html` for the paths of the views of the front ( `ui::routes::Route` )
+//!
+//! The files in `./dist` are served before the Worker is asked, so only
+//! the other paths like `/cards/:id` reach here to be routed by the front.
+
+use crate::api::errors::ServerError;
+use ohkami::prelude::*;
+use ohkami::FromRequest;
+
+
+/// `ASSETS` binding of `./dist` ( `assets` in wrangler.toml )
+pub struct Assets(worker::Fetcher);
+
+unsafe impl Send for Assets {}
+unsafe impl Sync for Assets {}
+
+impl<'req> FromRequest<'req> for Assets {
+    type Error = Response;
+    fn from_request(req: &'req Request) -> Option<Result<Self, Self::Error>> {
+        Some(req.env().service("ASSETS").map(Self).map_err(|e| {
+            worker::console_error!("{e}");
+            Response::InternalServerError()
+        }))
+    }
+}
+
+#[worker::send]
+pub async fn index_html(
+    Assets(assets): Assets,
+) -> Result<Response, ServerError> {
+    /* only the path matters for the assets */
+    let html = assets.fetch("https://assets/index.html", None).await?.text().await?;
+
+    Ok(Response::OK().with_html(html))
+}
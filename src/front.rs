@@ -1,5 +1,4 @@
 mod ui;
-mod models;
 
 
 fn main() {
@@ -0,0 +1,9 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::main]
+async fn main() {
+    use ohkami_yew_todo::native;
+
+    native::serve(native::Config::from_env()).await
+}
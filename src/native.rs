@@ -0,0 +1,73 @@
+//! Self-hosted server: the same routes as the Worker on tokio,
+//! with a local SQLite file and a local directory for attachments
+
+pub use crate::api::AttachmentsBucket;
+
+use crate::api::{i18n, idempotency, jwt};
+use crate::repository::SqliteRepository;
+use ohkami::{Memory, Ohkami};
+
+
+/// Configuration by environment variables
+/// ( `JWT_SECRET_KEY` is also required, read by `jwt::secret_key` )
+pub struct Config {
+    /// `ADDRESS`, default: `0.0.0.0:8787`
+    pub address:         String,
+    /// `DATABASE_PATH`, default: `./ohkami-yew-todo.db`
+    pub database_path:   String,
+    /// `ATTACHMENTS_DIR`, default: `./attachments`
+    pub attachments_dir: String,
+}
+impl Config {
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: &str| std::env::var(name).unwrap_or_else(|_| default.into());
+        Self {
+            address:         var("ADDRESS", "0.0.0.0:8787"),
+            database_path:   var("DATABASE_PATH", "./ohkami-yew-todo.db"),
+            attachments_dir: var("ATTACHMENTS_DIR", "./attachments"),
+        }
+    }
+}
+
+pub fn ohkami(repo: SqliteRepository, bucket: AttachmentsBucket) -> Ohkami {
+    let idempotency = idempotency::Idempotency { repo: repo.clone() };
+    super::ohkami((
+        i18n::Localize,
+        Memory::new(repo),
+        Memory::new(bucket),
+        #[cfg(debug_assertions)]
+        ohkami::fang::CORS::new("http://127.0.0.1:8080"),
+    ), idempotency)
+}
+
+/// Spawn `ohkami(repo, bucket)` at a free local port on a new thread ( `Ohkami` is
+/// not `Send` ), and return its origin once it accepts connections
+/// ( the harness of `tests/` and `cli/tests/` )
+pub fn spawn(repo: SqliteRepository, bucket: AttachmentsBucket) -> String {
+    use std::net::{TcpListener, TcpStream};
+
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build().unwrap()
+            .block_on(ohkami(repo, bucket).howl(("127.0.0.1", port)))
+    });
+
+    while TcpStream::connect(("127.0.0.1", port)).is_err() {
+        std::thread::sleep(std::time::Duration::from_millis(10))
+    }
+    format!("http://127.0.0.1:{port}")
+}
+
+pub async fn serve(config: Config) {
+    /* fail fast without `JWT_SECRET_KEY` */
+    jwt::secret_key();
+
+    let repo = SqliteRepository::open(&config.database_path)
+        .unwrap_or_else(|e| panic!("Failed to open `{}`: {e}", config.database_path));
+    let bucket = AttachmentsBucket::open(&config.attachments_dir)
+        .unwrap_or_else(|e| panic!("Failed to open `{}`: {e}", config.attachments_dir));
+
+    ohkami(repo, bucket).howl(config.address).await
+}
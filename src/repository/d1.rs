@@ -0,0 +1,573 @@
+use super::{assert_owner, pairing_code, IdempotencyRecord, Repository, RepositoryError, Shelf, TodoChanges, TodoNode, TodoRecord, IDEMPOTENCY_CLAIM_LEASE, IDEMPOTENCY_KEY_TTL, PAIRING_CODE_TTL};
+use crate::Bindings;
+use models::{Attachment, Card, Template, Todo, UpdateCard, ID};
+use ohkami::{FromRequest, Request, Response};
+use ohkami::serde::Deserialize;
+use ohkami::utils::unix_timestamp;
+use web_sys::{wasm_bindgen::JsCast, WorkerGlobalScope, js_sys};
+use worker::{D1Database, D1PreparedStatement};
+use std::collections::HashMap;
+use std::array;
+
+
+fn new_id() -> ID {
+    WorkerGlobalScope::unchecked_from_js(js_sys::global().into())
+        .crypto().unwrap().random_uuid()
+}
+
+
+/// `Repository` on the D1 database `DB`
+pub struct D1Repository(D1Database);
+
+unsafe impl Send for D1Repository {}
+unsafe impl Sync for D1Repository {}
+
+impl<'req> FromRequest<'req> for D1Repository {
+    type Error = Response;
+    fn from_request(req: &'req Request) -> Option<Result<Self, Self::Error>> {
+        Bindings::from_request(req).map(|b| b.map(|b| Self(b.DB)))
+    }
+}
+
+impl D1Repository {
+    async fn assert_owner_of_card(&self, user_id: &str, card_id: &str) -> Result<(), RepositoryError> {
+        let owner_id = self.0.prepare("SELECT user_id FROM cards WHERE id = ?")
+            .bind(&[card_id.into()])?.first::<String>(Some("user_id")).await?;
+        assert_owner(owner_id, user_id, "todo card")
+    }
+
+    async fn assert_owner_of_template(&self, user_id: &str, template_id: &str) -> Result<(), RepositoryError> {
+        let owner_id = self.0.prepare("SELECT user_id FROM templates WHERE id = ?")
+            .bind(&[template_id.into()])?.first::<String>(Some("user_id")).await?;
+        assert_owner(owner_id, user_id, "template")
+    }
+
+    /// Get the cards of the user on `shelf` or, for `None`, on the board
+    async fn list_cards_on(&self, user_id: &str, shelf: Option<Shelf>) -> Result<Vec<Card>, RepositoryError> {
+        let card_records = {
+            #[derive(Deserialize)] struct Record {
+                id:    String,
+                title: String,
+            }
+            self.0.prepare(format!("SELECT id, title FROM cards WHERE user_id = ? {}", Shelf::filter(shelf)))
+                .bind(&[user_id.into()])?
+                .all().await?.results::<Record>()?
+        };
+
+        let card_ids = card_records.iter().map(|r| &*r.id).collect::<Vec<_>>();
+        let todo_trees  = self.get_todo_trees(&card_ids).await?;
+        let attachments = self.get_attachments(&card_ids).await?;
+
+        Ok(card_records.into_iter().zip(todo_trees).zip(attachments).map(|((r, todos), attachments)| Card {
+            id:    r.id,
+            title: r.title,
+            todos: todos.map(Todo::from),
+            attachments,
+        }).collect())
+    }
+
+    /// Get the todo trees of the cards, in the same order as `card_ids`
+    /// (the caller must check the ownership)
+    async fn get_todo_trees(&self,
+        card_ids: &[&str],
+    ) -> Result<Vec<[TodoNode; Card::N_TODOS]>, RepositoryError> {
+        if card_ids.is_empty() {
+            return Ok(vec![])
+        }
+
+        let records = self.0.prepare(format!(
+                "SELECT id, card_id, parent_id, content, notes, completed_at FROM todos
+                WHERE card_id IN ({})
+                ORDER BY id ASC",
+                vec!["?"; card_ids.len()].join(",")
+            ))
+            .bind(&card_ids.iter().map(|id| (*id).into()).collect::<Vec<_>>())?
+            .all().await?.results::<TodoRecord>()?;
+
+        Ok(TodoNode::build_trees(card_ids, records))
+    }
+
+    /// Get the attachments of the cards, in the same order as `card_ids`
+    /// (the caller must check the ownership)
+    async fn get_attachments(&self,
+        card_ids: &[&str],
+    ) -> Result<Vec<Vec<Attachment>>, RepositoryError> {
+        if card_ids.is_empty() {
+            return Ok(vec![])
+        }
+
+        #[derive(Deserialize)] struct Record {
+            id:        String,
+            card_id:   String,
+            name:      String,
+            mime_type: String,
+            size:      usize,
+        }
+        let records = self.0.prepare(format!(
+                "SELECT id, card_id, name, mime_type, size FROM attachments
+                WHERE card_id IN ({})
+                ORDER BY created_at ASC, rowid ASC",
+                vec!["?"; card_ids.len()].join(",")
+            ))
+            .bind(&card_ids.iter().map(|id| (*id).into()).collect::<Vec<_>>())?
+            .all().await?.results::<Record>()?;
+
+        let mut attachments_of = HashMap::<String, Vec<_>>::new();
+        for r in records {
+            attachments_of.entry(r.card_id).or_default().push(Attachment {
+                id:        r.id,
+                name:      r.name,
+                mime_type: r.mime_type,
+                size:      r.size,
+                url:       String::new(),
+            })
+        }
+
+        Ok(card_ids.iter().map(|card_id| attachments_of.remove(*card_id).unwrap_or_default()).collect())
+    }
+
+    /// Statements inserting `children` of each parent with all their descendants, to be
+    /// in the same batch after the ones inserting `inserted` todos ( for `Parent::Inserted` )
+    /// by `INSERT_TODO`
+    ///
+    /// As the batch is run in a transaction and every todo is inserted with the id of
+    /// the max id + 1, the todo inserted `n` todos ago has the max id - `n`, so the
+    /// parents are given by that.
+    fn insert_todo_children(&self,
+        card_id:     &str,
+        inserted:    usize,
+        children_of: Vec<(Parent, Vec<Todo>)>,
+    ) -> Result<Vec<D1PreparedStatement>, RepositoryError> {
+        let statement_insert_under_existing = self.0.prepare(INSERT_TODO.replace("?parent", "?2"));
+        let statement_insert_under_inserted = self.0.prepare(INSERT_TODO.replace("?parent", "(SELECT MAX(id) FROM todos) - ?2"));
+
+        /* depth-first, for each todo to be inserted after its parent and before its next sibling */
+        let mut stack = children_of.into_iter().rev()
+            .flat_map(|(parent, children)| children.into_iter().rev().map(move |child| (parent, child)))
+            .collect::<Vec<_>>();
+
+        let mut statements = Vec::new();
+        let mut inserted = inserted;
+        while let Some((parent, child)) = stack.pop() {
+            use worker::D1Type::{Text, Integer, Null};
+            let (statement, parent) = match parent {
+                Parent::Existing(id) => (&statement_insert_under_existing, id),
+                Parent::Inserted(n)  => (&statement_insert_under_inserted, inserted - 1 - n),
+            };
+            statements.push(statement.bind_refs(&[
+                Text(card_id),
+                Integer(parent as _),
+                Text(&child.content),
+                match &child.notes {Some(notes) => Text(notes), None => Null},
+                if child.completed {Integer(unix_timestamp() as i32)} else {Null},
+            ])?);
+
+            stack.extend(child.children.into_iter().rev().map(|grandchild| (Parent::Inserted(inserted), grandchild)));
+            inserted += 1;
+        }
+
+        Ok(statements)
+    }
+}
+
+/// Insert the todo `(?1 card_id, ?parent, ?3 content, ?4 notes, ?5 completed_at)` with
+/// the id of the max id + 1, not to depend on how SQLite allocates a new rowid
+const INSERT_TODO: &str = "INSERT INTO todos (id, card_id, parent_id, content, notes, completed_at)
+    VALUES ((SELECT IFNULL(MAX(id), 0) + 1 FROM todos), ?1, ?parent, ?3, ?4, ?5)";
+
+/// Parent of the todos to be inserted by `insert_todo_children`
+#[derive(Clone, Copy)]
+enum Parent {
+    /// the existing todo of the id
+    Existing(usize),
+    /// the `n`-th todo inserted by the batch, from 0
+    Inserted(usize),
+}
+
+impl Repository for D1Repository {
+    async fn create_user(&self) -> Result<ID, RepositoryError> {
+        let id = new_id();
+
+        self.0.prepare("INSERT INTO users (id) VALUES (?)")
+            .bind(&[(&id).into()])?
+            .run().await?;
+
+        Ok(id)
+    }
+
+    async fn list_cards(&self, user_id: &str) -> Result<Vec<Card>, RepositoryError> {
+        self.list_cards_on(user_id, None).await
+    }
+
+    async fn list_shelved_cards(&self, user_id: &str, shelf: Shelf) -> Result<Vec<Card>, RepositoryError> {
+        self.list_cards_on(user_id, Some(shelf)).await
+    }
+
+    async fn get_card(&self, user_id: &str, card_id: &str) -> Result<Card, RepositoryError> {
+        self.assert_owner_of_card(user_id, card_id).await?;
+
+        let title = self.0.prepare("SELECT title FROM cards WHERE id = ?")
+            .bind(&[card_id.into()])?.first::<String>(Some("title")).await?.unwrap();
+
+        let [todos]       = <[_; 1]>::try_from(self.get_todo_trees(&[card_id]).await?).ok().unwrap();
+        let [attachments] = <[_; 1]>::try_from(self.get_attachments(&[card_id]).await?).ok().unwrap();
+
+        Ok(Card {
+            id:    card_id.into(),
+            title,
+            todos: todos.map(Todo::from),
+            attachments,
+        })
+    }
+
+    async fn create_card(&self, user_id: &str, id: Option<&str>, title: &str, todos: [Todo; Card::N_TODOS]) -> Result<ID, RepositoryError> {
+        let id = id.map_or_else(new_id, String::from);
+
+        if self.0.prepare("SELECT user_id FROM cards WHERE id = ?")
+            .bind(&[(&id).into()])?.first::<String>(Some("user_id")).await?.is_some() {
+            return Err(RepositoryError::AlreadyExists { resource: "todo card" })
+        }
+
+        let mut statements = vec![
+            self.0.prepare("INSERT INTO cards (id, user_id, title, created_at) VALUES (?1, ?2, ?3, ?4)")
+                .bind(&[
+                    (&id).into(),
+                    user_id.into(),
+                    title.into(),
+                    (unix_timestamp() as usize).into()
+                ])?,
+        ];
+        let statement_insert_top_level = self.0.prepare(INSERT_TODO.replace("?parent", "?2"));
+        for todo in &todos {
+            use worker::D1Type::{Text, Integer, Null};
+            statements.push(statement_insert_top_level.bind_refs(&[
+                Text(&id),
+                Null,
+                Text(&todo.content),
+                match &todo.notes {Some(notes) => Text(notes), None => Null},
+                if todo.completed {Integer(unix_timestamp() as i32)} else {Null},
+            ])?);
+        }
+        /* the top-level todos are the first `Card::N_TODOS` inserted */
+        statements.extend(self.insert_todo_children(&id, Card::N_TODOS, todos.into_iter().enumerate()
+            .map(|(i, todo)| (Parent::Inserted(i), todo.children))
+            .collect()
+        )?);
+        /* by another request with the same id after the check above */
+        self.0.batch(statements).await.map_err(|e| match e.to_string().contains("UNIQUE constraint failed: cards.id") {
+            true  => RepositoryError::AlreadyExists { resource: "todo card" },
+            false => e.into(),
+        })?;
+
+        Ok(id)
+    }
+
+    async fn update_card(&self, user_id: &str, card_id: &str, update: UpdateCard) -> Result<(), RepositoryError> {
+        self.assert_owner_of_card(user_id, card_id).await?;
+
+        let current_title = self.0.prepare("SELECT title FROM cards WHERE id = ?")
+            .bind(&[card_id.into()])?.first::<String>(Some("title")).await?.unwrap();
+
+        let [current_todos] = <[_; 1]>::try_from(self.get_todo_trees(&[card_id]).await?).ok().unwrap();
+
+        let changes = TodoChanges::between(current_todos, update.todos);
+
+        let statements = {
+            let mut statements = Vec::new();
+
+            if current_title != update.title {
+                statements.push(
+                    self.0.prepare("UPDATE cards SET title = ?1 WHERE id = ?2")
+                        .bind(&[update.title.into(), card_id.into()])?
+                )
+            }
+
+            let statement_update_todo = self.0.prepare(
+                "UPDATE todos SET content = ?1, notes = ?2, completed_at = ?3 WHERE id = ?4"
+            );
+            for (id, todo) in &changes.updates {
+                use worker::D1Type::{Text, Integer, Null};
+                statements.push(statement_update_todo
+                    .bind_refs(&[
+                        Text(&todo.content),
+                        match &todo.notes {Some(notes) => Text(notes), None => Null},
+                        if todo.completed {Integer(unix_timestamp() as i32)} else {Null},
+                        Integer(*id as _)
+                    ])?
+                )
+            }
+
+            let statement_delete_todo = self.0.prepare(
+                "DELETE FROM todos WHERE id = ?1"
+            );
+            for id in &changes.deletes {
+                statements.push(statement_delete_todo
+                    .bind_refs(&[worker::D1Type::Integer(*id as _)])?
+                )
+            }
+
+            statements.extend(self.insert_todo_children(card_id, 0, changes.children_of.into_iter()
+                .map(|(id, children)| (Parent::Existing(id), children))
+                .collect()
+            )?);
+
+            statements
+        };
+        /* all at once in a transaction, not to leave the card half updated */
+        if !statements.is_empty() {
+            self.0.batch(statements).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn shelve_card(&self, user_id: &str, card_id: &str, shelf: Shelf) -> Result<(), RepositoryError> {
+        self.assert_owner_of_card(user_id, card_id).await?;
+
+        self.0.prepare(shelf.update())
+            .bind(&[card_id.into(), (unix_timestamp() as usize).into()])?
+            .run().await?;
+
+        Ok(())
+    }
+
+    async fn unshelve_card(&self, user_id: &str, card_id: &str) -> Result<(), RepositoryError> {
+        self.assert_owner_of_card(user_id, card_id).await?;
+
+        self.0.prepare("UPDATE cards SET archived_at = NULL, deleted_at = NULL WHERE id = ?")
+            .bind(&[card_id.into()])?
+            .run().await?;
+
+        Ok(())
+    }
+
+    async fn delete_card(&self, user_id: &str, card_id: &str) -> Result<Vec<ID>, RepositoryError> {
+        self.assert_owner_of_card(user_id, card_id).await?;
+        if self.0.prepare("SELECT id FROM cards WHERE id = ? AND deleted_at IS NOT NULL")
+            .bind(&[card_id.into()])?.first::<String>(Some("id")).await?.is_none() {
+            return Err(RepositoryError::NotFound { resource: "todo card in the trash" })
+        }
+
+        let attachment_ids = self.get_attachments(&[card_id]).await?.concat()
+            .into_iter().map(|a| a.id).collect::<Vec<_>>();
+
+        self.0.batch(vec![
+            self.0.prepare("DELETE FROM cards WHERE id = ?")
+                .bind(&[card_id.into()])?,
+            self.0.prepare("DELETE FROM todos WHERE card_id = ?")
+                .bind(&[card_id.into()])?,
+            self.0.prepare("DELETE FROM attachments WHERE card_id = ?")
+                .bind(&[card_id.into()])?,
+        ]).await?;
+
+        Ok(attachment_ids)
+    }
+
+    async fn list_templates(&self, user_id: &str) -> Result<Vec<Template>, RepositoryError> {
+        let template_records = {
+            #[derive(Deserialize)] struct Record {
+                id:    String,
+                title: String,
+            }
+            self.0.prepare("SELECT id, title FROM templates WHERE user_id = ? ORDER BY created_at ASC, rowid ASC")
+                .bind(&[user_id.into()])?
+                .all().await?.results::<Record>()?
+        };
+        if template_records.is_empty() {
+            return Ok(vec![])
+        }
+
+        #[derive(Deserialize)] struct Record {
+            template_id: String,
+            content:     String,
+        }
+        let todo_records = self.0.prepare(format!(
+                "SELECT template_id, content FROM template_todos
+                WHERE template_id IN ({})
+                ORDER BY id ASC",
+                vec!["?"; template_records.len()].join(",")
+            ))
+            .bind(&template_records.iter().map(|r| (&r.id).into()).collect::<Vec<_>>())?
+            .all().await?.results::<Record>()?;
+
+        let mut todos_of = HashMap::<String, Vec<_>>::new();
+        for r in todo_records {
+            todos_of.entry(r.template_id).or_default().push(r.content)
+        }
+
+        Ok(template_records.into_iter().map(|r| {
+            let mut todos = todos_of.remove(&r.id).unwrap_or_default().into_iter();
+            Template {
+                id:    r.id,
+                title: r.title,
+                todos: array::from_fn(|_| todos.next().unwrap_or_default()),
+            }
+        }).collect())
+    }
+
+    async fn get_template(&self, user_id: &str, template_id: &str) -> Result<Template, RepositoryError> {
+        self.assert_owner_of_template(user_id, template_id).await?;
+
+        let title = self.0.prepare("SELECT title FROM templates WHERE id = ?")
+            .bind(&[template_id.into()])?.first::<String>(Some("title")).await?.unwrap();
+
+        let todos = {
+            #[derive(Deserialize)] struct Record {
+                content: String,
+            }
+            self.0.prepare("SELECT content FROM template_todos WHERE template_id = ? ORDER BY id ASC")
+                .bind(&[template_id.into()])?
+                .all().await?.results::<Record>()?
+        };
+        let mut todos = todos.into_iter();
+
+        Ok(Template {
+            id:    template_id.into(),
+            title,
+            todos: array::from_fn(|_| todos.next().map(|r| r.content).unwrap_or_default()),
+        })
+    }
+
+    async fn create_template(&self, user_id: &str, title: &str, todos: [String; Card::N_TODOS]) -> Result<ID, RepositoryError> {
+        let id = new_id();
+
+        self.0.batch(vec![
+            self.0.prepare("INSERT INTO templates (id, user_id, title, created_at) VALUES (?1, ?2, ?3, ?4)")
+                .bind(&[
+                    (&id).into(),
+                    user_id.into(),
+                    title.into(),
+                    (unix_timestamp() as usize).into()
+                ])?,
+            self.0.prepare(format!("INSERT INTO template_todos (template_id, content) VALUES {}", ["(?,?)"; Card::N_TODOS].join(",")))
+                .bind(array::from_fn::<_, {Card::N_TODOS * 2}, _>(|i|
+                    if i%2==0 {(&id).into()} else {(&todos[i/2]).into()}
+                ).as_slice())?,
+        ]).await?;
+
+        Ok(id)
+    }
+
+    async fn delete_template(&self, user_id: &str, template_id: &str) -> Result<(), RepositoryError> {
+        self.assert_owner_of_template(user_id, template_id).await?;
+
+        self.0.batch(vec![
+            self.0.prepare("DELETE FROM templates WHERE id = ?")
+                .bind(&[template_id.into()])?,
+            self.0.prepare("DELETE FROM template_todos WHERE template_id = ?")
+                .bind(&[template_id.into()])?,
+        ]).await?;
+
+        Ok(())
+    }
+
+    async fn create_attachment(&self, user_id: &str, card_id: &str, name: &str, mime_type: &str, size: usize) -> Result<ID, RepositoryError> {
+        self.assert_owner_of_card(user_id, card_id).await?;
+
+        let id = new_id();
+
+        self.0.prepare("INSERT INTO attachments (id, card_id, name, mime_type, size, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
+            .bind(&[
+                (&id).into(),
+                card_id.into(),
+                name.into(),
+                mime_type.into(),
+                size.into(),
+                (unix_timestamp() as usize).into()
+            ])?
+            .run().await?;
+
+        Ok(id)
+    }
+
+    async fn get_attachment(&self, attachment_id: &str) -> Result<Option<Attachment>, RepositoryError> {
+        #[derive(Deserialize)] struct Record {
+            name:      String,
+            mime_type: String,
+            size:      usize,
+        }
+        let record = self.0.prepare("SELECT name, mime_type, size FROM attachments WHERE id = ?")
+            .bind(&[attachment_id.into()])?.first::<Record>(None).await?;
+
+        Ok(record.map(|r| Attachment {
+            id:        attachment_id.into(),
+            name:      r.name,
+            mime_type: r.mime_type,
+            size:      r.size,
+            url:       String::new(),
+        }))
+    }
+
+    async fn delete_attachment(&self, user_id: &str, card_id: &str, attachment_id: &str) -> Result<(), RepositoryError> {
+        self.assert_owner_of_card(user_id, card_id).await?;
+
+        let deleted = self.0.prepare("DELETE FROM attachments WHERE id = ?1 AND card_id = ?2 RETURNING id")
+            .bind(&[attachment_id.into(), card_id.into()])?
+            .first::<String>(Some("id")).await?;
+        if deleted.is_none() {
+            return Err(RepositoryError::NotFound { resource: "attachment" })
+        }
+
+        Ok(())
+    }
+
+    async fn claim_idempotency_key(&self, user_id: &str, key: &str, request: &str) -> Result<Option<IdempotencyRecord>, RepositoryError> {
+        let now = unix_timestamp();
+        self.0.prepare("DELETE FROM idempotency_keys WHERE created_at < ?")
+            .bind(&[((now - IDEMPOTENCY_KEY_TTL) as usize).into()])?
+            .run().await?;
+
+        let claimed = self.0.prepare(
+                "INSERT INTO idempotency_keys (user_id, key, request, created_at, claimed_at) VALUES (?1, ?2, ?3, ?4, ?4)
+                ON CONFLICT (user_id, key) DO UPDATE SET claimed_at = excluded.claimed_at
+                    WHERE status IS NULL AND request = excluded.request AND claimed_at < ?5
+                RETURNING key"
+            )
+            .bind(&[user_id.into(), key.into(), request.into(), (now as usize).into(), ((now - IDEMPOTENCY_CLAIM_LEASE) as usize).into()])?
+            .first::<String>(Some("key")).await?;
+        if claimed.is_some() {
+            return Ok(None)
+        }
+
+        Ok(self.0.prepare("SELECT request, status, body FROM idempotency_keys WHERE user_id = ?1 AND key = ?2")
+            .bind(&[user_id.into(), key.into()])?
+            .first::<IdempotencyRecord>(None).await?)
+    }
+
+    async fn save_idempotent_response(&self, user_id: &str, key: &str, status: u16, body: Option<&str>) -> Result<(), RepositoryError> {
+        self.0.prepare("UPDATE idempotency_keys SET status = ?1, body = ?2 WHERE user_id = ?3 AND key = ?4")
+            .bind(&[status.into(), body.into(), user_id.into(), key.into()])?
+            .run().await?;
+
+        Ok(())
+    }
+
+    async fn release_idempotency_key(&self, user_id: &str, key: &str) -> Result<(), RepositoryError> {
+        self.0.prepare("DELETE FROM idempotency_keys WHERE user_id = ?1 AND key = ?2")
+            .bind(&[user_id.into(), key.into()])?
+            .run().await?;
+
+        Ok(())
+    }
+
+    async fn create_pairing_code(&self, user_id: &str) -> Result<String, RepositoryError> {
+        let now = unix_timestamp();
+        self.0.prepare("DELETE FROM pairing_codes WHERE created_at < ?")
+            .bind(&[((now - PAIRING_CODE_TTL) as usize).into()])?
+            .run().await?;
+
+        let code = pairing_code(new_id());
+        self.0.prepare("INSERT INTO pairing_codes (code, user_id, created_at) VALUES (?1, ?2, ?3)")
+            .bind(&[code.clone().into(), user_id.into(), (now as usize).into()])?
+            .run().await?;
+
+        Ok(code)
+    }
+
+    async fn consume_pairing_code(&self, code: &str) -> Result<ID, RepositoryError> {
+        self.0.prepare("DELETE FROM pairing_codes WHERE code = ?1 AND created_at >= ?2 RETURNING user_id")
+            .bind(&[code.into(), ((unix_timestamp() - PAIRING_CODE_TTL) as usize).into()])?
+            .first::<String>(Some("user_id")).await?
+            .ok_or(RepositoryError::NotFound { resource: "pairing code" })
+    }
+}
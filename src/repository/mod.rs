@@ -0,0 +1,255 @@
+#[cfg(target_arch = "wasm32")]
+mod d1;
+#[cfg(target_arch = "wasm32")]
+pub use d1::D1Repository;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod sqlite;
+#[cfg(not(target_arch = "wasm32"))]
+pub use sqlite::SqliteRepository;
+
+use models::{Attachment, Card, Template, Todo, UpdateCard, ID};
+use ohkami::serde::Deserialize;
+use std::collections::HashMap;
+use std::array;
+
+
+#[derive(Debug, thiserror::Error)]
+pub enum RepositoryError {
+    #[cfg(target_arch = "wasm32")]
+    #[error("Error in D1: {0}")]
+    D1(#[from] worker::Error),
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("Error in SQLite: {0}")]
+    SQLite(#[from] rusqlite::Error),
+
+    #[error("User(id = {user_id}) requested operation on {resource} whitch is NOT of the user")]
+    NotOwner { user_id: String, resource: &'static str },
+
+    #[error("Requested {resource} is not found")]
+    NotFound { resource: &'static str },
+
+    #[error("Requested {resource} already exists")]
+    AlreadyExists { resource: &'static str },
+}
+
+/// Persistence of users, cards ( with their todos and attachments ) and templates.
+///
+/// Every operation on an existing card or template takes the id of the requesting user
+/// and fails with `RepositoryError::NotOwner` when it's not of the user ( or doesn't exist ).
+///
+/// `url`s of the returned `Attachment`s are left empty for the caller to sign.
+#[allow(async_fn_in_trait)]
+pub trait Repository {
+    /// Create a new user and return its id
+    async fn create_user(&self) -> Result<ID, RepositoryError>;
+
+    /// The cards on the board : neither archived nor in the trash
+    async fn list_cards(&self, user_id: &str) -> Result<Vec<Card>, RepositoryError>;
+    /// The cards on `shelf`, the latest put there first
+    async fn list_shelved_cards(&self, user_id: &str, shelf: Shelf) -> Result<Vec<Card>, RepositoryError>;
+    async fn get_card(&self, user_id: &str, card_id: &str) -> Result<Card, RepositoryError>;
+    /// Insert a new card of the user with the title and todos, and return its id :
+    /// `id` if given ( failing with `RepositoryError::AlreadyExists` when it's taken ) or a new one
+    async fn create_card(&self, user_id: &str, id: Option<&str>, title: &str, todos: [Todo; Card::N_TODOS]) -> Result<ID, RepositoryError>;
+    async fn update_card(&self, user_id: &str, card_id: &str, update: UpdateCard) -> Result<(), RepositoryError>;
+    /// Put the card away on `shelf`, from the board or the other shelf
+    async fn shelve_card(&self, user_id: &str, card_id: &str, shelf: Shelf) -> Result<(), RepositoryError>;
+    /// Put the card back on the board
+    async fn unshelve_card(&self, user_id: &str, card_id: &str) -> Result<(), RepositoryError>;
+    /// Delete the card in the trash with all its todos and attachments, and return
+    /// the ids of the deleted attachments ( `NotFound` when it's not in the trash )
+    async fn delete_card(&self, user_id: &str, card_id: &str) -> Result<Vec<ID>, RepositoryError>;
+
+    async fn list_templates(&self, user_id: &str) -> Result<Vec<Template>, RepositoryError>;
+    async fn get_template(&self, user_id: &str, template_id: &str) -> Result<Template, RepositoryError>;
+    /// Insert a new template of the user with the title and the contents of the
+    /// top-level todos ( see `Template` ), and return its id
+    async fn create_template(&self, user_id: &str, title: &str, todos: [String; Card::N_TODOS]) -> Result<ID, RepositoryError>;
+    async fn delete_template(&self, user_id: &str, template_id: &str) -> Result<(), RepositoryError>;
+
+    /// Record a new attachment of the card and return its id
+    async fn create_attachment(&self, user_id: &str, card_id: &str, name: &str, mime_type: &str, size: usize) -> Result<ID, RepositoryError>;
+    /// Get the attachment of the id regardless of the owner
+    /// ( for downloading with a signed URL )
+    async fn get_attachment(&self, attachment_id: &str) -> Result<Option<Attachment>, RepositoryError>;
+    /// `NotFound` when the card has no attachment of the id
+    async fn delete_attachment(&self, user_id: &str, card_id: &str, attachment_id: &str) -> Result<(), RepositoryError>;
+
+    /// Claim the `Idempotency-Key` of the user for `request` ( like `POST /cards {hash of the body}` ) and return `None`,
+    /// or return the record of the key when it's already claimed in `IDEMPOTENCY_KEY_TTL`
+    ///
+    /// A claim still in progress after `IDEMPOTENCY_CLAIM_LEASE` is taken over by the same request,
+    /// as the one that claimed it may have died without releasing it.
+    async fn claim_idempotency_key(&self, user_id: &str, key: &str, request: &str) -> Result<Option<IdempotencyRecord>, RepositoryError>;
+    /// Record the response to the request of the claimed key
+    async fn save_idempotent_response(&self, user_id: &str, key: &str, status: u16, body: Option<&str>) -> Result<(), RepositoryError>;
+    /// Unclaim the key for the request to be retried
+    async fn release_idempotency_key(&self, user_id: &str, key: &str) -> Result<(), RepositoryError>;
+
+    /// Issue a new code to log in as the user in `PAIRING_CODE_TTL`, and return it
+    async fn create_pairing_code(&self, user_id: &str) -> Result<String, RepositoryError>;
+    /// Use up the code and return the id of its user, or `NotFound` when it's wrong, used or expired
+    async fn consume_pairing_code(&self, code: &str) -> Result<ID, RepositoryError>;
+}
+
+/// Seconds for which an `Idempotency-Key` is remembered
+pub const IDEMPOTENCY_KEY_TTL: u64 = 24 * 60 * 60;
+
+/// Seconds for which a claimed `Idempotency-Key` is kept in progress
+pub const IDEMPOTENCY_CLAIM_LEASE: u64 = 60;
+
+/// Seconds for which a pairing code is valid
+pub const PAIRING_CODE_TTL: u64 = 10 * 60;
+
+/// 10 random hex digits of a new UUID v4 in uppercase, like `3F2A9C01B7`
+fn pairing_code(uuid: ID) -> String {
+    uuid.replace('-', "")[..10].to_ascii_uppercase()
+}
+
+/// Where a card is put away from the board
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Shelf {
+    Archive,
+    /// kept until deleted from there
+    Trash,
+}
+impl Shelf {
+    /// After `WHERE user_id = ?`, the condition and the order of the cards on
+    /// `shelf` or, for `None`, on the board
+    fn filter(shelf: Option<Self>) -> &'static str {
+        match shelf {
+            None                => "AND archived_at IS NULL AND deleted_at IS NULL ORDER BY created_at ASC, rowid ASC",
+            Some(Self::Archive) => "AND archived_at IS NOT NULL AND deleted_at IS NULL ORDER BY archived_at DESC, rowid ASC",
+            Some(Self::Trash)   => "AND deleted_at IS NOT NULL ORDER BY deleted_at DESC, rowid ASC",
+        }
+    }
+
+    /// `UPDATE cards` putting the card `?1` on this shelf at the time `?2`
+    fn update(self) -> &'static str {
+        match self {
+            Self::Archive => "UPDATE cards SET archived_at = ?2, deleted_at = NULL WHERE id = ?1",
+            Self::Trash   => "UPDATE cards SET archived_at = NULL, deleted_at = ?2 WHERE id = ?1",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct IdempotencyRecord {
+    /// `{method} {path}` and the hash of the body of the request that claimed the key
+    pub request: String,
+    /// `None` while the request is in progress
+    pub status:  Option<u16>,
+    pub body:    Option<String>,
+}
+
+
+fn assert_owner(
+    owner_id: Option<String>,
+    user_id:  &str,
+    resource: &'static str,
+) -> Result<(), RepositoryError> {
+    (owner_id.as_deref() == Some(user_id)).then_some(())
+        .ok_or_else(|| RepositoryError::NotOwner {
+            user_id: user_id.to_string(), resource
+        })
+}
+
+#[derive(Deserialize)]
+struct TodoRecord {
+    id:           usize,
+    card_id:      String,
+    parent_id:    Option<usize>,
+    content:      String,
+    notes:        Option<String>,
+    completed_at: Option<u64>,
+}
+
+/// A todo record with its sub-todo records
+struct TodoNode {
+    record:   TodoRecord,
+    children: Vec<TodoNode>,
+}
+impl TodoNode {
+    fn build(record: TodoRecord, children_of: &mut HashMap<usize, Vec<TodoRecord>>) -> Self {
+        let children = children_of.remove(&record.id).unwrap_or_default()
+            .into_iter().map(|child| Self::build(child, children_of)).collect();
+        Self { record, children }
+    }
+
+    /// Build the todo trees of the cards from all their todo records ordered by id,
+    /// in the same order as `card_ids`
+    fn build_trees(card_ids: &[&str], records: Vec<TodoRecord>) -> Vec<[Self; Card::N_TODOS]> {
+        let (mut top_levels_of, mut children_of) = (HashMap::<String, Vec<_>>::new(), HashMap::<usize, Vec<_>>::new());
+        for r in records {
+            match r.parent_id {
+                None            => top_levels_of.entry(r.card_id.clone()).or_default().push(r),
+                Some(parent_id) => children_of.entry(parent_id).or_default().push(r),
+            }
+        }
+
+        card_ids.iter().map(|card_id| {
+            let mut top_levels = top_levels_of.remove(*card_id).unwrap_or_default().into_iter();
+            array::from_fn(|_| Self::build(top_levels.next().unwrap(), &mut children_of))
+        }).collect()
+    }
+
+    fn descendant_ids(&self) -> Vec<usize> {
+        self.children.iter()
+            .flat_map(|child| [child.record.id].into_iter().chain(child.descendant_ids()))
+            .collect()
+    }
+}
+impl From<TodoNode> for Todo {
+    fn from(node: TodoNode) -> Self {
+        Todo {
+            content:   node.record.content,
+            notes:     node.record.notes,
+            completed: node.record.completed_at.is_some(),
+            children:  node.children.into_iter().map(Todo::from).collect(),
+        }
+    }
+}
+impl PartialEq<Todo> for TodoNode {
+    fn eq(&self, other: &Todo) -> bool {
+        self.record.content == other.content &&
+        self.record.notes == other.notes &&
+        self.record.completed_at.is_some() == other.completed &&
+        self.children == other.children
+    }
+}
+
+/// What to write to make the stored todos of a card the same as `new`
+struct TodoChanges {
+    /// top-level todos whose content, notes or completion has changed,
+    /// with the ids of their records ( `children` are left empty )
+    updates:     Vec<(usize, Todo)>,
+    /// ids of the sub-todos to be deleted
+    deletes:     Vec<usize>,
+    /// new children of the todos of the ids, to be inserted
+    children_of: Vec<(usize, Vec<Todo>)>,
+}
+impl TodoChanges {
+    fn between(current: [TodoNode; Card::N_TODOS], new: [Todo; Card::N_TODOS]) -> Self {
+        let mut changes = Self { updates: vec![], deletes: vec![], children_of: vec![] };
+
+        for (current, mut new) in current.into_iter().zip(new) {
+            if current.children != new.children {
+                /* replacing all the sub-todos: they have no identity other than their position */
+                changes.deletes.extend(current.descendant_ids());
+                changes.children_of.push((current.record.id, std::mem::take(&mut new.children)))
+            } else {
+                new.children.clear()
+            }
+
+            if (current.record.content != new.content)
+            || (current.record.notes != new.notes)
+            || (current.record.completed_at.is_some() != new.completed) {
+                changes.updates.push((current.record.id, new))
+            }
+        }
+
+        changes
+    }
+}
@@ -0,0 +1,480 @@
+use super::{assert_owner, pairing_code, IdempotencyRecord, Repository, RepositoryError, Shelf, TodoChanges, TodoNode, TodoRecord, IDEMPOTENCY_CLAIM_LEASE, IDEMPOTENCY_KEY_TTL, PAIRING_CODE_TTL};
+use models::{Attachment, Card, Template, Todo, UpdateCard, ID};
+use ohkami::utils::unix_timestamp;
+use ohkami::{FromRequest, Request, Response};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::path::Path;
+use std::array;
+
+
+fn new_id() -> ID {
+    uuid::Uuid::new_v4().to_string()
+}
+
+
+/// `Repository` on a SQLite database of `migrations/`
+///
+/// Cloning shares the same connection.
+#[derive(Clone)]
+pub struct SqliteRepository(Arc<Mutex<Connection>>);
+
+/// From `Memory` of the server ( see `crate::native` )
+impl<'req> FromRequest<'req> for SqliteRepository {
+    type Error = Response;
+    fn from_request(req: &'req Request) -> Option<Result<Self, Self::Error>> {
+        Some(req.memorized::<Self>().cloned().ok_or_else(Response::InternalServerError))
+    }
+}
+
+impl SqliteRepository {
+    /// `migrations/` in order, recorded in `d1_migrations` as `wrangler d1 migrations apply` does
+    const MIGRATIONS: &'static [(&'static str, &'static str)] = &[
+        ("0000_schema.sql",          include_str!("../../migrations/0000_schema.sql")),
+        ("0001_todos_parent_id.sql", include_str!("../../migrations/0001_todos_parent_id.sql")),
+        ("0002_todos_notes.sql",     include_str!("../../migrations/0002_todos_notes.sql")),
+        ("0003_idempotency_keys_claimed_at.sql", include_str!("../../migrations/0003_idempotency_keys_claimed_at.sql")),
+        ("0004_cards_archived_at_deleted_at.sql", include_str!("../../migrations/0004_cards_archived_at_deleted_at.sql")),
+    ];
+
+    /// Open the database file at `path`, creating it if not exists, and
+    /// apply the migrations not applied yet
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RepositoryError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, RepositoryError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(mut connection: Connection) -> Result<Self, RepositoryError> {
+        connection.execute_batch("
+            CREATE TABLE IF NOT EXISTS d1_migrations (
+                id         INTEGER PRIMARY KEY AUTOINCREMENT,
+                name       TEXT UNIQUE,
+                applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+            );
+        ")?;
+        for (name, migration) in Self::MIGRATIONS {
+            let tx = connection.transaction()?;
+            let applied = tx.query_row(
+                "SELECT EXISTS (SELECT 1 FROM d1_migrations WHERE name = ?1)",
+                [name], |row| row.get::<_, bool>(0)
+            )?;
+            if !applied {
+                tx.execute_batch(migration)?;
+                tx.execute("INSERT INTO d1_migrations (name) VALUES (?1)", [name])?;
+            }
+            tx.commit()?;
+        }
+        Ok(Self(Arc::new(Mutex::new(connection))))
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn assert_owner_of_card(c: &Connection, user_id: &str, card_id: &str) -> Result<(), RepositoryError> {
+    let owner_id = c.query_row("SELECT user_id FROM cards WHERE id = ?", [card_id], |row| row.get(0)).optional()?;
+    assert_owner(owner_id, user_id, "todo card")
+}
+
+fn assert_owner_of_template(c: &Connection, user_id: &str, template_id: &str) -> Result<(), RepositoryError> {
+    let owner_id = c.query_row("SELECT user_id FROM templates WHERE id = ?", [template_id], |row| row.get(0)).optional()?;
+    assert_owner(owner_id, user_id, "template")
+}
+
+fn completed_at(completed: bool) -> Option<u64> {
+    completed.then(unix_timestamp)
+}
+
+/// Get the cards of the user on `shelf` or, for `None`, on the board
+fn list_cards(c: &Connection, user_id: &str, shelf: Option<Shelf>) -> Result<Vec<Card>, RepositoryError> {
+    let card_ids = c.prepare(&format!("SELECT id FROM cards WHERE user_id = ? {}", Shelf::filter(shelf)))?
+        .query_map([user_id], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    card_ids.iter().map(|id| get_card(c, id)).collect()
+}
+
+/// Get the todo trees of the cards, in the same order as `card_ids`
+/// (the caller must check the ownership)
+fn get_todo_trees(c: &Connection, card_ids: &[&str]) -> Result<Vec<[TodoNode; Card::N_TODOS]>, RepositoryError> {
+    if card_ids.is_empty() {
+        return Ok(vec![])
+    }
+
+    let records = c.prepare(&format!(
+            "SELECT id, card_id, parent_id, content, notes, completed_at FROM todos
+            WHERE card_id IN ({})
+            ORDER BY id ASC",
+            vec!["?"; card_ids.len()].join(",")
+        ))?
+        .query_map(params_from_iter(card_ids), |row| Ok(TodoRecord {
+            id:           row.get(0)?,
+            card_id:      row.get(1)?,
+            parent_id:    row.get(2)?,
+            content:      row.get(3)?,
+            notes:        row.get(4)?,
+            completed_at: row.get(5)?,
+        }))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(TodoNode::build_trees(card_ids, records))
+}
+
+/// Get the attachments of the cards, in the same order as `card_ids`
+/// (the caller must check the ownership)
+fn get_attachments(c: &Connection, card_ids: &[&str]) -> Result<Vec<Vec<Attachment>>, RepositoryError> {
+    if card_ids.is_empty() {
+        return Ok(vec![])
+    }
+
+    let records = c.prepare(&format!(
+            "SELECT card_id, id, name, mime_type, size FROM attachments
+            WHERE card_id IN ({})
+            ORDER BY created_at ASC, rowid ASC",
+            vec!["?"; card_ids.len()].join(",")
+        ))?
+        .query_map(params_from_iter(card_ids), |row| Ok((row.get::<_, String>(0)?, Attachment {
+            id:        row.get(1)?,
+            name:      row.get(2)?,
+            mime_type: row.get(3)?,
+            size:      row.get(4)?,
+            url:       String::new(),
+        })))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut attachments_of = HashMap::<String, Vec<_>>::new();
+    for (card_id, attachment) in records {
+        attachments_of.entry(card_id).or_default().push(attachment)
+    }
+
+    Ok(card_ids.iter().map(|card_id| attachments_of.remove(*card_id).unwrap_or_default()).collect())
+}
+
+/// Insert `children` of each todo of the id, with all their descendants
+fn insert_todo_children(c: &Connection, card_id: &str, children_of: Vec<(usize, Vec<Todo>)>) -> Result<(), RepositoryError> {
+    let mut statement_insert_todo = c.prepare(
+        "INSERT INTO todos (card_id, parent_id, content, notes, completed_at) VALUES (?1, ?2, ?3, ?4, ?5)"
+    )?;
+
+    let mut stack = children_of;
+    while let Some((parent_id, children)) = stack.pop() {
+        for child in children {
+            statement_insert_todo.execute(params![
+                card_id, parent_id, child.content, child.notes, completed_at(child.completed)
+            ])?;
+            if !child.children.is_empty() {
+                stack.push((c.last_insert_rowid() as usize, child.children))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn get_card(c: &Connection, card_id: &str) -> Result<Card, RepositoryError> {
+    let title = c.query_row("SELECT title FROM cards WHERE id = ?", [card_id], |row| row.get(0))?;
+
+    let [todos]       = <[_; 1]>::try_from(get_todo_trees(c, &[card_id])?).ok().unwrap();
+    let [attachments] = <[_; 1]>::try_from(get_attachments(c, &[card_id])?).ok().unwrap();
+
+    Ok(Card {
+        id:    card_id.into(),
+        title,
+        todos: todos.map(Todo::from),
+        attachments,
+    })
+}
+
+fn get_template(c: &Connection, template_id: &str) -> Result<Template, RepositoryError> {
+    let title = c.query_row("SELECT title FROM templates WHERE id = ?", [template_id], |row| row.get(0))?;
+
+    let mut todos = c.prepare("SELECT content FROM template_todos WHERE template_id = ? ORDER BY id ASC")?
+        .query_map([template_id], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter();
+
+    Ok(Template {
+        id:    template_id.into(),
+        title,
+        todos: array::from_fn(|_| todos.next().unwrap_or_default()),
+    })
+}
+
+impl Repository for SqliteRepository {
+    async fn create_user(&self) -> Result<ID, RepositoryError> {
+        let id = new_id();
+
+        self.connection().execute("INSERT INTO users (id) VALUES (?)", [&id])?;
+
+        Ok(id)
+    }
+
+    async fn list_cards(&self, user_id: &str) -> Result<Vec<Card>, RepositoryError> {
+        list_cards(&self.connection(), user_id, None)
+    }
+
+    async fn list_shelved_cards(&self, user_id: &str, shelf: Shelf) -> Result<Vec<Card>, RepositoryError> {
+        list_cards(&self.connection(), user_id, Some(shelf))
+    }
+
+    async fn get_card(&self, user_id: &str, card_id: &str) -> Result<Card, RepositoryError> {
+        let c = self.connection();
+
+        assert_owner_of_card(&c, user_id, card_id)?;
+        get_card(&c, card_id)
+    }
+
+    async fn create_card(&self, user_id: &str, id: Option<&str>, title: &str, todos: [Todo; Card::N_TODOS]) -> Result<ID, RepositoryError> {
+        let mut c = self.connection();
+        let id = id.map_or_else(new_id, String::from);
+
+        let t = c.transaction()?;
+        t.execute(
+            "INSERT INTO cards (id, user_id, title, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![id, user_id, title, unix_timestamp()]
+        ).map_err(|e| match e.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::ConstraintViolation) => RepositoryError::AlreadyExists { resource: "todo card" },
+            _ => e.into(),
+        })?;
+        let mut children_of = Vec::new();
+        for todo in todos {
+            t.execute(
+                "INSERT INTO todos (card_id, content, notes, completed_at) VALUES (?1, ?2, ?3, ?4)",
+                params![id, todo.content, todo.notes, completed_at(todo.completed)]
+            )?;
+            if !todo.children.is_empty() {
+                children_of.push((t.last_insert_rowid() as usize, todo.children))
+            }
+        }
+        insert_todo_children(&t, &id, children_of)?;
+        t.commit()?;
+
+        Ok(id)
+    }
+
+    async fn update_card(&self, user_id: &str, card_id: &str, update: UpdateCard) -> Result<(), RepositoryError> {
+        let mut c = self.connection();
+
+        assert_owner_of_card(&c, user_id, card_id)?;
+
+        let [current_todos] = <[_; 1]>::try_from(get_todo_trees(&c, &[card_id])?).ok().unwrap();
+        let changes = TodoChanges::between(current_todos, update.todos);
+
+        let t = c.transaction()?;
+        t.execute("UPDATE cards SET title = ?1 WHERE id = ?2", params![update.title, card_id])?;
+        for (id, todo) in changes.updates {
+            t.execute(
+                "UPDATE todos SET content = ?1, notes = ?2, completed_at = ?3 WHERE id = ?4",
+                params![todo.content, todo.notes, completed_at(todo.completed), id]
+            )?;
+        }
+        for id in changes.deletes {
+            t.execute("DELETE FROM todos WHERE id = ?1", [id])?;
+        }
+        insert_todo_children(&t, card_id, changes.children_of)?;
+        t.commit()?;
+
+        Ok(())
+    }
+
+    async fn shelve_card(&self, user_id: &str, card_id: &str, shelf: Shelf) -> Result<(), RepositoryError> {
+        let c = self.connection();
+
+        assert_owner_of_card(&c, user_id, card_id)?;
+        c.execute(shelf.update(), params![card_id, unix_timestamp()])?;
+
+        Ok(())
+    }
+
+    async fn unshelve_card(&self, user_id: &str, card_id: &str) -> Result<(), RepositoryError> {
+        let c = self.connection();
+
+        assert_owner_of_card(&c, user_id, card_id)?;
+        c.execute("UPDATE cards SET archived_at = NULL, deleted_at = NULL WHERE id = ?", [card_id])?;
+
+        Ok(())
+    }
+
+    async fn delete_card(&self, user_id: &str, card_id: &str) -> Result<Vec<ID>, RepositoryError> {
+        let mut c = self.connection();
+
+        assert_owner_of_card(&c, user_id, card_id)?;
+        if !c.query_row("SELECT deleted_at IS NOT NULL FROM cards WHERE id = ?", [card_id], |row| row.get::<_, bool>(0))? {
+            return Err(RepositoryError::NotFound { resource: "todo card in the trash" })
+        }
+
+        let attachment_ids = get_attachments(&c, &[card_id])?.concat()
+            .into_iter().map(|a| a.id).collect::<Vec<_>>();
+
+        let t = c.transaction()?;
+        t.execute("DELETE FROM cards WHERE id = ?", [card_id])?;
+        t.execute("DELETE FROM todos WHERE card_id = ?", [card_id])?;
+        t.execute("DELETE FROM attachments WHERE card_id = ?", [card_id])?;
+        t.commit()?;
+
+        Ok(attachment_ids)
+    }
+
+    async fn list_templates(&self, user_id: &str) -> Result<Vec<Template>, RepositoryError> {
+        let c = self.connection();
+
+        let template_ids = c.prepare("SELECT id FROM templates WHERE user_id = ? ORDER BY created_at ASC, rowid ASC")?
+            .query_map([user_id], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        template_ids.iter().map(|id| get_template(&c, id)).collect()
+    }
+
+    async fn get_template(&self, user_id: &str, template_id: &str) -> Result<Template, RepositoryError> {
+        let c = self.connection();
+
+        assert_owner_of_template(&c, user_id, template_id)?;
+        get_template(&c, template_id)
+    }
+
+    async fn create_template(&self, user_id: &str, title: &str, todos: [String; Card::N_TODOS]) -> Result<ID, RepositoryError> {
+        let mut c = self.connection();
+        let id = new_id();
+
+        let t = c.transaction()?;
+        t.execute(
+            "INSERT INTO templates (id, user_id, title, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![id, user_id, title, unix_timestamp()]
+        )?;
+        for content in todos {
+            t.execute(
+                "INSERT INTO template_todos (template_id, content) VALUES (?1, ?2)",
+                params![id, content]
+            )?;
+        }
+        t.commit()?;
+
+        Ok(id)
+    }
+
+    async fn delete_template(&self, user_id: &str, template_id: &str) -> Result<(), RepositoryError> {
+        let mut c = self.connection();
+
+        assert_owner_of_template(&c, user_id, template_id)?;
+
+        let t = c.transaction()?;
+        t.execute("DELETE FROM templates WHERE id = ?", [template_id])?;
+        t.execute("DELETE FROM template_todos WHERE template_id = ?", [template_id])?;
+        t.commit()?;
+
+        Ok(())
+    }
+
+    async fn create_attachment(&self, user_id: &str, card_id: &str, name: &str, mime_type: &str, size: usize) -> Result<ID, RepositoryError> {
+        let c = self.connection();
+
+        assert_owner_of_card(&c, user_id, card_id)?;
+
+        let id = new_id();
+        c.execute(
+            "INSERT INTO attachments (id, card_id, name, mime_type, size, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![id, card_id, name, mime_type, size, unix_timestamp()]
+        )?;
+
+        Ok(id)
+    }
+
+    async fn get_attachment(&self, attachment_id: &str) -> Result<Option<Attachment>, RepositoryError> {
+        Ok(self.connection().query_row(
+            "SELECT name, mime_type, size FROM attachments WHERE id = ?",
+            [attachment_id],
+            |row| Ok(Attachment {
+                id:        attachment_id.into(),
+                name:      row.get(0)?,
+                mime_type: row.get(1)?,
+                size:      row.get(2)?,
+                url:       String::new(),
+            })
+        ).optional()?)
+    }
+
+    async fn delete_attachment(&self, user_id: &str, card_id: &str, attachment_id: &str) -> Result<(), RepositoryError> {
+        let c = self.connection();
+
+        assert_owner_of_card(&c, user_id, card_id)?;
+
+        let deleted = c.execute("DELETE FROM attachments WHERE id = ?1 AND card_id = ?2", [attachment_id, card_id])?;
+        if deleted == 0 {
+            return Err(RepositoryError::NotFound { resource: "attachment" })
+        }
+
+        Ok(())
+    }
+
+    async fn claim_idempotency_key(&self, user_id: &str, key: &str, request: &str) -> Result<Option<IdempotencyRecord>, RepositoryError> {
+        let c = self.connection();
+
+        let now = unix_timestamp();
+        c.execute("DELETE FROM idempotency_keys WHERE created_at < ?", [now - IDEMPOTENCY_KEY_TTL])?;
+
+        let claimed = c.query_row(
+            "INSERT INTO idempotency_keys (user_id, key, request, created_at, claimed_at) VALUES (?1, ?2, ?3, ?4, ?4)
+            ON CONFLICT (user_id, key) DO UPDATE SET claimed_at = excluded.claimed_at
+                WHERE status IS NULL AND request = excluded.request AND claimed_at < ?5
+            RETURNING key",
+            params![user_id, key, request, now, now - IDEMPOTENCY_CLAIM_LEASE],
+            |_| Ok(())
+        ).optional()?;
+        if claimed.is_some() {
+            return Ok(None)
+        }
+
+        Ok(Some(c.query_row(
+            "SELECT request, status, body FROM idempotency_keys WHERE user_id = ?1 AND key = ?2",
+            [user_id, key],
+            |row| Ok(IdempotencyRecord {
+                request: row.get(0)?,
+                status:  row.get(1)?,
+                body:    row.get(2)?,
+            })
+        )?))
+    }
+
+    async fn save_idempotent_response(&self, user_id: &str, key: &str, status: u16, body: Option<&str>) -> Result<(), RepositoryError> {
+        self.connection().execute(
+            "UPDATE idempotency_keys SET status = ?1, body = ?2 WHERE user_id = ?3 AND key = ?4",
+            params![status, body, user_id, key]
+        )?;
+
+        Ok(())
+    }
+
+    async fn release_idempotency_key(&self, user_id: &str, key: &str) -> Result<(), RepositoryError> {
+        self.connection().execute("DELETE FROM idempotency_keys WHERE user_id = ?1 AND key = ?2", [user_id, key])?;
+
+        Ok(())
+    }
+
+    async fn create_pairing_code(&self, user_id: &str) -> Result<String, RepositoryError> {
+        let c = self.connection();
+
+        let now = unix_timestamp();
+        c.execute("DELETE FROM pairing_codes WHERE created_at < ?", [now - PAIRING_CODE_TTL])?;
+
+        let code = pairing_code(new_id());
+        c.execute(
+            "INSERT INTO pairing_codes (code, user_id, created_at) VALUES (?1, ?2, ?3)",
+            params![code, user_id, now]
+        )?;
+
+        Ok(code)
+    }
+
+    async fn consume_pairing_code(&self, code: &str) -> Result<ID, RepositoryError> {
+        self.connection().query_row(
+            "DELETE FROM pairing_codes WHERE code = ?1 AND created_at >= ?2 RETURNING user_id",
+            params![code, unix_timestamp() - PAIRING_CODE_TTL],
+            |row| row.get(0)
+        ).optional()?.ok_or(RepositoryError::NotFound { resource: "pairing code" })
+    }
+}
@@ -1,36 +1,140 @@
+#![cfg(feature = "server")]
+
 mod api;
-mod models;
+pub mod repository;
+#[cfg(target_arch = "wasm32")]
+mod assets;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native;
 
-use api::{signup, list_cards, create_card, update_card, delete_card};
-use api::jwt;
+use api::{signup, pair, create_pairing_code, list_cards, create_card, update_card, delete_card, duplicate_card};
+use api::{list_templates, create_template, delete_template, instantiate_template};
+use api::{upload_attachment, delete_attachment, download_attachment};
+use api::{list_archive, archive_card, unarchive_card, list_trash, restore_card, delete_card_forever};
+use api::{jwt, idempotency, openapi};
 use ohkami::prelude::*;
+use ohkami::fang::Fangs;
 
 
+#[cfg(target_arch = "wasm32")]
 #[ohkami::bindings]
 struct Bindings;
 
+#[cfg(target_arch = "wasm32")]
 #[ohkami::worker]
 async fn my_worker() -> Ohkami {
     console_error_panic_hook::set_once();
 
-    let fangs = {
+    let fangs = (
+        api::i18n::Localize,
         #[cfg(debug_assertions)]
-        ohkami::fang::CORS::new("http://127.0.0.1:8080")
-    };
+        ohkami::fang::CORS::new("http://127.0.0.1:8080"),
+    );
+
+    ohkami(fangs, idempotency::Idempotency {})
+}
 
-    Ohkami::with(fangs, (
-        /* in production, `./dist` is served by `--assets dist` of `deploy` script in package.json */
+/// All the routes, shared by the Worker and the native server
+fn ohkami(fangs: impl Fangs + 'static, idempotency: idempotency::Idempotency) -> Ohkami {
+    routes!(router, fangs, (jwt::fang(), idempotency))
+}
+
+/// `(method, path)` of every route of the API, like `("POST", "/api/cards")`
+///
+/// `api::openapi::document` must document all of them ( checked by tests/openapi.rs ).
+pub const ROUTES: &[(&str, &str)] = routes!(list);
 
+/// The routes of the API, declared once for both `ohkami` and `ROUTES` :
+/// passed to `$then` with `$args`, the ones under `/api` after `;`
+macro_rules! routes {
+    ($then:ident $(, $args:expr)*) => {$then!(($($args),*)
         "/signup"
             .POST(signup),
+        "/pair"
+            .POST(pair),
+        "/attachments/:id"
+            .GET(download_attachment);
+
+        "/cards"
+            .GET(list_cards)
+            .POST(create_card),
+        "/cards/:id"
+            .PUT(update_card)
+            .DELETE(delete_card),
+        "/cards/:id/duplicate"
+            .POST(duplicate_card),
+        "/cards/:id/attachments"
+            .POST(upload_attachment),
+        "/cards/:id/attachments/:attachment_id"
+            .DELETE(delete_attachment),
+        "/archive"
+            .GET(list_archive),
+        "/archive/:id"
+            .PUT(archive_card)
+            .DELETE(unarchive_card),
+        "/trash"
+            .GET(list_trash),
+        "/trash/:id"
+            .DELETE(delete_card_forever),
+        "/trash/:id/restore"
+            .POST(restore_card),
+        "/templates"
+            .GET(list_templates)
+            .POST(create_template),
+        "/templates/:id"
+            .DELETE(delete_template),
+        "/templates/:id/instantiate"
+            .POST(instantiate_template),
+        "/pairing-codes"
+            .POST(create_pairing_code),
+    )};
+}
+use routes;
 
-        "/api".By(Ohkami::with(jwt::fang(), (
-            "/cards"
-                .GET(list_cards)
-                .POST(create_card),
-            "/cards/:id"
-                .PUT(update_card)
-                .DELETE(delete_card),
-        ))),
-    ))
+macro_rules! router {
+    (($fangs:expr, $api_fangs:expr)
+        $($path:literal $(.$method:ident($handler:expr))+),*;
+        $($api_path:literal $(.$api_method:ident($api_handler:expr))+),* $(,)?
+    ) => {
+        Ohkami::with($fangs, (
+            /* in production, `./dist` is served by `assets` in wrangler.toml
+               ( or by your proxy in front of the native server ) */
+
+            /* the views of the front, keep them the same as `ui::routes::Route` */
+            #[cfg(target_arch = "wasm32")]
+            "/cards/:id".GET(assets::index_html),
+            #[cfg(target_arch = "wasm32")]
+            "/search".GET(assets::index_html),
+            #[cfg(target_arch = "wasm32")]
+            "/archive".GET(assets::index_html),
+            #[cfg(target_arch = "wasm32")]
+            "/trash".GET(assets::index_html),
+            #[cfg(target_arch = "wasm32")]
+            "/settings".GET(assets::index_html),
+
+            /* the document itself */
+            "/openapi.json"
+                .GET(openapi::openapi),
+
+            $($path $(.$method($handler))+,)*
+
+            "/api".By(Ohkami::with($api_fangs, (
+                $($api_path $(.$api_method($api_handler))+,)*
+            ))),
+        ))
+    };
+}
+use router;
+
+macro_rules! list {
+    (()
+        $($path:literal $(.$method:ident($handler:expr))+),*;
+        $($api_path:literal $(.$api_method:ident($api_handler:expr))+),* $(,)?
+    ) => {
+        &[
+            $($((stringify!($method), $path),)+)*
+            $($((stringify!($api_method), concat!("/api", $api_path)),)+)*
+        ]
+    };
 }
+use list;
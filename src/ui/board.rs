@@ -0,0 +1,337 @@
+//! State of the cards and the templates, changed only by `BoardAction`s
+
+use super::i18n::Messages;
+use models::{Attachment, Card, Template, Todo, ID};
+use yew::Reducible;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+
+#[derive(Clone, Default, PartialEq)]
+pub struct Board {
+    /// `Rc` to clone only the changed card for each action
+    pub cards:     Vec<Rc<Card>>,
+    /// The latest archived first
+    pub archived:  Vec<Rc<Card>>,
+    /// The latest moved to the trash first
+    pub trashed:   Vec<Rc<Card>>,
+    pub templates: Vec<Template>,
+    /// Cards being created, not to be edited until the server has them
+    pending:       HashSet<ID>,
+    saves:         HashMap<ID, Rc<CardSaves>>,
+}
+
+impl Board {
+    pub fn is_pending(&self, card_id: &str) -> bool {
+        self.pending.contains(card_id)
+    }
+
+    pub fn saves(&self, card_id: &str) -> Rc<CardSaves> {
+        self.saves.get(card_id).cloned().unwrap_or_default()
+    }
+
+    pub fn card(&self, id: &str) -> Option<&Rc<Card>> {
+        self.cards.iter().find(|c| c.id == id)
+    }
+
+    /// The card of the id on the board or on either shelf
+    pub fn any_card(&self, id: &str) -> Option<&Rc<Card>> {
+        self.cards.iter().chain(&self.archived).chain(&self.trashed).find(|c| c.id == id)
+    }
+
+    pub fn shelf(&self, shelf: Shelf) -> &[Rc<Card>] {
+        match shelf {
+            Shelf::Archive => &self.archived,
+            Shelf::Trash   => &self.trashed,
+        }
+    }
+
+    pub fn position(&self, card_id: &str) -> Option<usize> {
+        self.cards.iter().position(|c| c.id == card_id)
+    }
+
+    pub fn template(&self, id: &str) -> Option<&Template> {
+        self.templates.iter().find(|t| t.id == id)
+    }
+
+    /// Cards having `query` in the title, or the contents or notes of any todo, ignoring case
+    pub fn search<'b>(&'b self, query: &str) -> impl Iterator<Item = &'b Rc<Card>> {
+        fn todo_matches(todo: &Todo, query: &str) -> bool {
+            todo.content.to_lowercase().contains(query) ||
+            todo.notes.as_deref().is_some_and(|notes| notes.to_lowercase().contains(query)) ||
+            todo.children.iter().any(|child| todo_matches(child, query))
+        }
+
+        let query = query.trim().to_lowercase();
+        self.cards.iter().filter(move |card| !query.is_empty() && (
+            card.title.to_lowercase().contains(&query) ||
+            card.todos.iter().any(|todo| todo_matches(todo, &query))
+        ))
+    }
+
+    fn card_mut(&mut self, id: &str) -> Option<&mut Card> {
+        self.cards.iter_mut().find(|c| c.id == id).map(Rc::make_mut)
+    }
+
+    /// Take the card of the id out of the board and the shelves
+    fn take(&mut self, id: &str) -> Option<Rc<Card>> {
+        self.pending.remove(id);
+        self.saves.remove(id);
+        [&mut self.cards, &mut self.archived, &mut self.trashed].into_iter().find_map(|cards| {
+            let at = cards.iter().position(|c| c.id == id)?;
+            Some(cards.remove(at))
+        })
+    }
+}
+
+/// Where the cards off the board are
+#[derive(Clone, Copy, PartialEq)]
+pub enum Shelf {
+    Archive,
+    Trash,
+}
+
+pub enum BoardAction {
+    Load { cards: Vec<Card>, archived: Vec<Card>, trashed: Vec<Card>, templates: Vec<Template> },
+
+    AddCard(Card),
+    /// Show the card being created by the id of the client, at `at`
+    AddPendingCard { at: usize, card: Card },
+    /// The server has created the pending card `pending_id` as `id`
+    /// ( the same one unless the server chose another )
+    PersistCard { pending_id: ID, id: ID },
+    /// Remove the card from the board or the shelf it's on
+    RemoveCard(ID),
+    /// Put the card on the top of `shelf`, from the board or the other shelf
+    ShelveCard { card_id: ID, shelf: Shelf },
+    /// Put `card` back on the board at `at`, from the shelf it's on if any
+    UnshelveCard { at: usize, card: Rc<Card> },
+    ChangeCard { card_id: ID, change: CardChange },
+    /// Put the title and the todos back to `Card`'s, if it's still on the board
+    /// ( attachments are saved separately and kept )
+    RevertCard(Card),
+
+    AddAttachment { card_id: ID, attachment: Attachment },
+    RemoveAttachment { card_id: ID, attachment_id: ID },
+
+    AddTemplate(Template),
+    RemoveTemplate(ID),
+
+    /// `status` of the save of `field` by the edit `edit`, unless a later edit is saving it
+    SaveField { card_id: ID, field: Field, edit: usize, status: SaveStatus },
+    /// Stop showing that `field` has been saved by `edit`
+    ClearSaved { card_id: ID, field: Field, edit: usize },
+}
+
+impl Reducible for Board {
+    type Action = BoardAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut board = Rc::unwrap_or_clone(self);
+        match action {
+            BoardAction::Load { cards, archived, trashed, templates } => {
+                board.cards = cards.into_iter().map(Rc::new).collect();
+                board.archived = archived.into_iter().map(Rc::new).collect();
+                board.trashed = trashed.into_iter().map(Rc::new).collect();
+                board.templates = templates;
+                board.saves.clear();
+            }
+
+            BoardAction::AddCard(card) => {
+                /* not twice for a replayed creation */
+                if board.card(&card.id).is_none() {
+                    board.cards.push(Rc::new(card))
+                }
+            }
+            BoardAction::AddPendingCard { at, card } => {
+                if board.card(&card.id).is_none() {
+                    board.pending.insert(card.id.clone());
+                    board.cards.insert(at.min(board.cards.len()), Rc::new(card))
+                }
+            }
+            BoardAction::PersistCard { pending_id, id } => {
+                board.pending.remove(&pending_id);
+                if let Some(card) = board.card_mut(&pending_id) {
+                    card.id = id
+                }
+            }
+            BoardAction::RemoveCard(id) => {
+                board.take(&id);
+            }
+            BoardAction::ShelveCard { card_id, shelf } => {
+                if let Some(card) = board.take(&card_id) {
+                    match shelf {
+                        Shelf::Archive => board.archived.insert(0, card),
+                        Shelf::Trash   => board.trashed.insert(0, card),
+                    }
+                }
+            }
+            BoardAction::UnshelveCard { at, card } => {
+                board.take(&card.id);
+                board.cards.insert(at.min(board.cards.len()), card)
+            }
+            BoardAction::ChangeCard { card_id, change } => {
+                if let Some(card) = board.card_mut(&card_id) {
+                    change.apply(card)
+                }
+            }
+            BoardAction::RevertCard(Card { id, title, todos, attachments:_ }) => {
+                if let Some(card) = board.card_mut(&id) {
+                    card.title = title;
+                    card.todos = todos;
+                }
+            }
+
+            BoardAction::AddAttachment { card_id, attachment } => {
+                if let Some(card) = board.card_mut(&card_id) {
+                    card.attachments.push(attachment)
+                }
+            }
+            BoardAction::RemoveAttachment { card_id, attachment_id } => {
+                if let Some(card) = board.card_mut(&card_id) {
+                    card.attachments.retain(|a| a.id != attachment_id)
+                }
+            }
+
+            BoardAction::AddTemplate(template) => {
+                board.templates.push(template)
+            }
+            BoardAction::RemoveTemplate(id) => {
+                board.templates.retain(|t| t.id != id)
+            }
+
+            BoardAction::SaveField { card_id, field, edit, status } => {
+                let saves = Rc::make_mut(board.saves.entry(card_id).or_default());
+                if !matches!(saves.0.get(&field), Some((e, _)) if *e > edit) {
+                    saves.0.insert(field, (edit, status));
+                }
+            }
+            BoardAction::ClearSaved { card_id, field, edit } => {
+                if let Some(saves) = board.saves.get_mut(&card_id) {
+                    if saves.0.get(&field) == Some(&(edit, SaveStatus::Saved)) {
+                        Rc::make_mut(saves).0.remove(&field);
+                    }
+                }
+            }
+        }
+        Rc::new(board)
+    }
+}
+
+
+/// A todo in a card : `index`-th of the todos and then `path` in its descendants
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TodoId {
+    pub index: usize,
+    pub path:  Vec<usize>,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum CardChange {
+    SetTitle(String),
+    EditTodo { todo: TodoId, edit: TodoEdit },
+    /// Put the title and the todos back, by undo or redo
+    Restore { title: String, todos: [Todo; Card::N_TODOS] },
+}
+
+#[derive(Clone, PartialEq)]
+pub enum TodoEdit {
+    Toggle,
+    /// A sub-todo is removed when its content is cleared
+    SetContent(String),
+    SetNotes(String),
+    AddSubtodo,
+}
+
+impl CardChange {
+    pub fn apply(&self, card: &mut Card) {
+        match self {
+            Self::SetTitle(title) => card.title = title.clone(),
+
+            Self::Restore { title, todos } => {
+                card.title = title.clone();
+                card.todos = todos.clone();
+            }
+
+            Self::EditTodo { todo: TodoId { index, path }, edit } => {
+                let Some(root) = card.todos.get_mut(*index) else {return};
+                match edit {
+                    TodoEdit::Toggle => if let Some(target) = root.get_mut(path) {
+                        target.set_completed(!target.completed)
+                    }
+                    TodoEdit::SetContent(content) => match path.split_last() {
+                        Some((last, parent_path)) if content.is_empty() => {
+                            if let Some(parent) = root.get_mut(parent_path) {
+                                if *last < parent.children.len() {
+                                    parent.children.remove(*last);
+                                }
+                            }
+                        }
+                        _ => if let Some(target) = root.get_mut(path) {
+                            target.content = content.clone()
+                        }
+                    }
+                    TodoEdit::SetNotes(notes) => if let Some(target) = root.get_mut(path) {
+                        target.notes = (!notes.is_empty()).then(|| notes.clone())
+                    }
+                    TodoEdit::AddSubtodo => if let Some(target) = root.get_mut(path) {
+                        target.children.push(Todo::new(""))
+                    }
+                }
+                root.sync_completion()
+            }
+        }
+    }
+
+    /// Whether the server is to be told this change : an added sub-todo
+    /// is not saved until its content is edited
+    pub fn is_saved(&self) -> bool {
+        !matches!(self, Self::EditTodo { edit: TodoEdit::AddSubtodo, .. })
+    }
+
+    /// The field of the text edited by this change, to show the status of saving it
+    pub fn field(&self) -> Option<Field> {
+        match self {
+            Self::SetTitle(_)                                      => Some(Field::Title),
+            Self::EditTodo { todo, edit: TodoEdit::SetContent(_) } => Some(Field::Content(todo.clone())),
+            Self::EditTodo { todo, edit: TodoEdit::SetNotes(_) }   => Some(Field::Notes(todo.clone())),
+            Self::EditTodo { .. } | Self::Restore { .. }           => None,
+        }
+    }
+
+    /// Message of the toast when saving this change fails
+    pub fn failure(&self, t: &Messages) -> &'static str {
+        match self {
+            Self::SetTitle(_)                                  => t.update_title_failed,
+            Self::Restore { .. }                               => t.restore_failed,
+            Self::EditTodo { edit: TodoEdit::SetNotes(_), .. } => t.update_notes_failed,
+            Self::EditTodo { .. }                              => t.update_todo_failed,
+        }
+    }
+}
+
+
+/// A text field of a card
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Field {
+    Title,
+    Content(TodoId),
+    Notes(TodoId),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SaveStatus {
+    Saving,
+    Saved,
+    Failed,
+}
+
+/// The latest saves of the fields of a card, with the ids of the edits
+#[derive(Clone, Default, PartialEq)]
+pub struct CardSaves(HashMap<Field, (usize, SaveStatus)>);
+
+impl CardSaves {
+    pub fn status(&self, field: &Field) -> Option<SaveStatus> {
+        self.0.get(field).map(|(_, status)| *status)
+    }
+}
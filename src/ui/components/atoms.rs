@@ -1,11 +1,25 @@
 use yew::prelude::*;
+use yew::platform::{spawn_local, time::sleep};
+use models::{markdown, Attachment};
+use crate::ui::{board::SaveStatus, fetch, i18n::use_messages};
+use std::{cell::RefCell, rc::Rc, time::Duration};
 
 
+/// Classes of the buttons showing only an icon
+const ICON_BUTTON_CLASS: &str = "
+    block w-full h-full p-0 m-0
+    border-0 rounded-sm bg-transparent
+    cursor-pointer disabled:cursor-default
+    outline-none focus-visible:ring-2 focus-visible:ring-sky-400
+";
+
 #[derive(Properties, PartialEq)]
 pub struct ButtonProps {
     #[prop_or(None)]
     children: Option<Html>,
 
+    /// What the button does, for screen readers and the tooltip
+    pub label:    AttrValue,
     #[prop_or(None)]
     pub on_click: Option<Callback<()>>,
     #[prop_or("")]
@@ -16,15 +30,16 @@ pub struct ButtonProps {
 fn Button(props: &ButtonProps) -> Html {
     html! (
         <div class={props.class}>
-            <div class={props.on_click.is_some().then_some("cursor-pointer")}>
-                <a
-                    tabindex="0"
-                    class={props.on_click.is_none().then_some("pointer-events-none")}
-                    onclick={props.on_click.as_ref().map(|h| h.reform(|_| ()))}
-                >
-                    {props.children.clone()}
-                </a>
-            </div>
+            <button
+                type="button"
+                class={ICON_BUTTON_CLASS}
+                aria-label={props.label.clone()}
+                title={props.label.clone()}
+                disabled={props.on_click.is_none()}
+                onclick={props.on_click.as_ref().map(|h| h.reform(|_| ()))}
+            >
+                {props.children.clone()}
+            </button>
         </div>
     )
 }
@@ -33,12 +48,12 @@ fn Button(props: &ButtonProps) -> Html {
 #[function_component]
 pub fn DeleteButton(props: &ButtonProps) -> Html {
     html!(
-        <Button on_click={props.on_click.clone()} class={props.class}>
-            <img src={
+        <Button label={props.label.clone()} on_click={props.on_click.clone()} class={props.class}>
+            <img alt="" src={
                 if props.on_click.is_none() {
-                    "assets/icons/delete_disabled.svg"
+                    "/assets/icons/delete_disabled.svg"
                 } else {
-                    "assets/icons/delete.svg"
+                    "/assets/icons/delete.svg"
                 }
             }/>
         </Button>
@@ -48,22 +63,125 @@ pub fn DeleteButton(props: &ButtonProps) -> Html {
 #[function_component]
 pub fn UploadButton(props: &ButtonProps) -> Html {
     html!(
-        <Button on_click={props.on_click.clone()} class={props.class}>
-            <img src={
+        <Button label={props.label.clone()} on_click={props.on_click.clone()} class={props.class}>
+            <img alt="" src={
                 if props.on_click.is_none() {
-                    "assets/icons/upload_disabled.svg"
+                    "/assets/icons/upload_disabled.svg"
                 } else {
-                    "assets/icons/upload.svg"
+                    "/assets/icons/upload.svg"
                 }
             }/>
         </Button>
     )
 }
 
+#[function_component]
+pub fn DuplicateButton(props: &ButtonProps) -> Html {
+    html!(
+        <Button label={props.label.clone()} on_click={props.on_click.clone()} class={props.class}>
+            <img alt="" src="/assets/icons/content_copy.svg"/>
+        </Button>
+    )
+}
+
+#[function_component]
+pub fn SaveAsTemplateButton(props: &ButtonProps) -> Html {
+    html!(
+        <Button label={props.label.clone()} on_click={props.on_click.clone()} class={props.class}>
+            <img alt="" src="/assets/icons/bookmark_add.svg"/>
+        </Button>
+    )
+}
+
+#[function_component]
+pub fn ArchiveButton(props: &ButtonProps) -> Html {
+    html!(
+        <Button label={props.label.clone()} on_click={props.on_click.clone()} class={props.class}>
+            <img alt="" src="/assets/icons/archive.svg"/>
+        </Button>
+    )
+}
+
+#[function_component]
+pub fn OpenButton(props: &ButtonProps) -> Html {
+    html!(
+        <Button label={props.label.clone()} on_click={props.on_click.clone()} class={props.class}>
+            <img alt="" src="/assets/icons/open_in_full.svg"/>
+        </Button>
+    )
+}
+
+#[derive(Properties, PartialEq)]
+pub struct AttachmentChipProps {
+    pub attachment: Attachment,
+
+    #[prop_or(None)]
+    pub on_click_delete: Option<Callback<()>>,
+}
+
+#[function_component]
+pub fn AttachmentChip(AttachmentChipProps {
+    attachment,
+    on_click_delete,
+}: &AttachmentChipProps) -> Html {
+    let (url, t) = (fetch::url(&attachment.url), use_messages());
+
+    html!(
+        <div class="h-6 max-w-full pl-1 pr-1 rounded-md bg-neutral-200 flex items-center space-x-1">
+            <a
+                class="min-w-0 flex items-center space-x-1 no-underline text-neutral-800 rounded-sm outline-none focus-visible:ring-2 focus-visible:ring-sky-400"
+                href={url.clone()}
+                rel="noopener noreferrer"
+                target="_blank"
+            >
+                if attachment.is_image() {
+                    <img class="h-5 w-5 object-cover rounded-sm" src={url} alt={attachment.name.clone()}/>
+                } else {
+                    <span class="text-xs text-red-700">{"PDF"}</span>
+                }
+                <span class="text-xs truncate">{&attachment.name}</span>
+            </a>
+            if let Some(on_click_delete) = on_click_delete {
+                <button
+                    type="button"
+                    class="p-0 border-0 rounded-sm bg-transparent cursor-pointer text-xs text-neutral-500 outline-none focus-visible:ring-2 focus-visible:ring-sky-400"
+                    aria-label={(t.remove_attachment)(&attachment.name)}
+                    onclick={on_click_delete.reform(|_| ())}
+                >
+                    {"×"}
+                </button>
+            }
+        </div>
+    )
+}
+
+#[derive(Properties, PartialEq)]
+pub struct RetryBannerProps {
+    pub state: Option<fetch::RetryState>,
+}
+
+/// Notice while a failed request is waiting for the retry
+#[function_component]
+pub fn RetryBanner(RetryBannerProps { state }: &RetryBannerProps) -> Html {
+    let t = use_messages();
+
+    let Some(fetch::RetryState::Waiting { attempt, max_retries, delay, .. }) = state else {
+        return html!()
+    };
+
+    html!(
+        <p class="fixed top-2 inset-x-0 mx-auto w-fit px-3 py-1 rounded-md bg-amber-100 text-sm text-amber-900 shadow" role="status">
+            {(t.retrying)(*attempt, *max_retries, delay.as_secs_f32())}
+        </p>
+    )
+}
+
 #[derive(Properties, PartialEq)]
 pub struct CheckBoxButtonProps {
     pub checked:  bool,
-    
+    /// What is checked, for screen readers
+    pub label:    AttrValue,
+
     #[prop_or("")]
     pub class:    &'static str,
     #[prop_or(None)]
@@ -73,19 +191,30 @@ pub struct CheckBoxButtonProps {
 #[function_component]
 pub fn CheckBoxButton(CheckBoxButtonProps {
     checked,
+    label,
     class,
     on_click,
 }: &CheckBoxButtonProps) -> Html {
     html! (
-        <Button {on_click} class={class}>
-            <img src={
-                if *checked {
-                    "assets/icons/check_box.svg"
-                } else {
-                    "assets/icons/check_box_outline_blank.svg"
-                }
-            }/>
-        </Button>
+        <div class={*class}>
+            <button
+                type="button"
+                role="checkbox"
+                class={ICON_BUTTON_CLASS}
+                aria-checked={if *checked {"true"} else {"false"}}
+                aria-label={label.clone()}
+                disabled={on_click.is_none()}
+                onclick={on_click.as_ref().map(|h| h.reform(|_| ()))}
+            >
+                <img alt="" src={
+                    if *checked {
+                        "/assets/icons/check_box.svg"
+                    } else {
+                        "/assets/icons/check_box_outline_blank.svg"
+                    }
+                }/>
+            </button>
+        </div>
     )
 }
 
@@ -93,48 +222,293 @@ pub fn CheckBoxButton(CheckBoxButtonProps {
 #[derive(Properties, PartialEq)]
 pub struct TextInputProps {
     pub value:    String,
+    /// What the text is, for screen readers
+    pub label:    AttrValue,
 
     #[prop_or(false)]
     pub is_title: bool,
+    /// render `value` as Markdown while not editing
+    #[prop_or(false)]
+    pub markdown: bool,
     #[prop_or("")]
     pub class: &'static str,
+    /// called when the text is committed, and after a pause in typing
     #[prop_or(None)]
     pub on_change: Option<Callback<String>>,
     #[prop_or(None)]
-    pub on_input:  Option<Callback<String>>,
+    pub status:    Option<SaveStatus>,
 }
 
 #[function_component]
 pub fn TextInput(TextInputProps {
     value,
+    label,
     class,
     is_title,
+    markdown,
     on_change,
-    on_input,
+    status,
 }: &TextInputProps) -> Html {
     use web_sys::{HtmlInputElement, wasm_bindgen::JsCast};
 
-    let disabled = on_change.is_none() && on_input.is_none();
+    let disabled = on_change.is_none();
+
+    let editing  = use_state(|| false);
+    let input    = use_node_ref();
+    let autosave = use_autosave(value, on_change);
+
+    use_effect_with(*editing, {
+        let input = input.clone();
+        move |editing| if *editing {
+            if let Some(input) = input.cast::<HtmlInputElement>() {
+                let _ = input.focus();
+            }
+        }
+    });
+
+    let text_class = match (*is_title, disabled) {
+        (true,  _    ) => "text-lg   + text-neutral-800 + resize-none border-none w-full h-full outline-none bg-inherit rounded-sm focus-visible:ring-2 focus-visible:ring-sky-400",
+        (false, true ) => "text-base + text-neutral-400 + resize-none border-none w-full h-full outline-none bg-inherit rounded-sm focus-visible:ring-2 focus-visible:ring-sky-400",
+        (false, false) => "text-base + text-neutral-800 + resize-none border-none w-full h-full outline-none bg-inherit rounded-sm focus-visible:ring-2 focus-visible:ring-sky-400",
+    };
+    let status_class = "right-0 top-1/2 -translate-y-1/2 bg-neutral-100";
+
+    if *markdown && !*editing && !value.is_empty() {
+        return html!(
+            <div class={classes!(*class, "relative")}>
+                <p
+                    class={classes!(text_class, "m-0", "truncate", (!disabled).then_some("cursor-text"))}
+                    tabindex={(!disabled).then_some("0")}
+                    role={(!disabled).then_some("textbox")}
+                    aria-label={(!disabled).then(|| label.clone())}
+                    onfocus={(!disabled).then(|| {let editing = editing.clone(); move |_| editing.set(true)})}
+                >
+                    {render_markdown(&markdown::parse(value))}
+                </p>
+                <SaveIndicator status={*status} class={status_class}/>
+            </div>
+        )
+    }
 
     html!(
-        <div class={*class}>
+        <div class={classes!(*class, "relative")}>
             <input
-                class={match (*is_title, disabled) {
-                    (true,  _    ) => "text-lg   + text-neutral-800 + resize-none border-none w-full h-full outline-none bg-inherit",
-                    (false, true ) => "text-base + text-neutral-400 + resize-none border-none w-full h-full outline-none bg-inherit",
-                    (false, false) => "text-base + text-neutral-800 + resize-none border-none w-full h-full outline-none bg-inherit",
-                }}
+                ref={input}
+                class={text_class}
+                aria-label={label.clone()}
                 autocomplete="off"
                 spellcheck="false"
                 disabled={disabled}
                 value={value.clone()}
-                onchange={on_change.as_ref().map(|h| h.reform(|e: Event| {
-                    e.target().unwrap().dyn_into::<HtmlInputElement>().unwrap().value()
-                }))}
-                oninput={on_input.as_ref().map(|h| h.reform(|e: InputEvent| {
-                    e.target().unwrap().dyn_into::<HtmlInputElement>().unwrap().value()
-                }))}
+                onfocus={{let autosave = autosave.clone(); move |e: FocusEvent| {
+                    autosave.begin(e.target().unwrap().dyn_into::<HtmlInputElement>().unwrap().value())
+                }}}
+                onchange={{let autosave = autosave.clone(); move |e: Event| {
+                    autosave.commit(e.target().unwrap().dyn_into::<HtmlInputElement>().unwrap().value())
+                }}}
+                oninput={{let autosave = autosave.clone(); move |e: InputEvent| {
+                    autosave.input(e.target().unwrap().dyn_into::<HtmlInputElement>().unwrap().value())
+                }}}
+                onkeydown={{let autosave = autosave.clone(); move |e: KeyboardEvent| {
+                    let input = e.target().unwrap().dyn_into::<HtmlInputElement>().unwrap();
+                    match &*e.key() {
+                        /* committed by `change` on the blur */
+                        "Enter"  => {let _ = input.blur();}
+                        "Escape" => {input.set_value(&autosave.cancel()); let _ = input.blur();}
+                        _ => ()
+                    }
+                }}}
+                onblur={{let editing = editing.clone(); move |_| editing.set(false)}}
             />
+            <SaveIndicator status={*status} class={status_class}/>
         </div>
     )
 }
+
+#[derive(Properties, PartialEq)]
+pub struct TextAreaProps {
+    pub value: String,
+    /// What the text is, for screen readers
+    pub label: AttrValue,
+
+    #[prop_or("")]
+    pub class: &'static str,
+    #[prop_or("")]
+    pub placeholder: &'static str,
+    /// called when the text is committed, and after a pause in typing
+    #[prop_or(None)]
+    pub on_change: Option<Callback<String>>,
+    #[prop_or(None)]
+    pub status:    Option<SaveStatus>,
+}
+
+#[function_component]
+pub fn TextArea(TextAreaProps {
+    value,
+    label,
+    class,
+    placeholder,
+    on_change,
+    status,
+}: &TextAreaProps) -> Html {
+    use web_sys::{HtmlTextAreaElement, wasm_bindgen::JsCast};
+
+    let autosave = use_autosave(value, on_change);
+
+    html!(
+        <div class={classes!(*class, "relative")}>
+            <textarea
+                class={if on_change.is_none() {
+                    "text-sm + text-neutral-400 + resize-none border-none w-full h-full outline-none rounded-md bg-neutral-200 p-1 focus-visible:ring-2 focus-visible:ring-sky-400"
+                } else {
+                    "text-sm + text-neutral-800 + resize-none border-none w-full h-full outline-none rounded-md bg-neutral-200 p-1 focus-visible:ring-2 focus-visible:ring-sky-400"
+                }}
+                aria-label={label.clone()}
+                rows="3"
+                spellcheck="false"
+                placeholder={*placeholder}
+                disabled={on_change.is_none()}
+                value={value.clone()}
+                onfocus={{let autosave = autosave.clone(); move |e: FocusEvent| {
+                    autosave.begin(e.target().unwrap().dyn_into::<HtmlTextAreaElement>().unwrap().value())
+                }}}
+                onchange={{let autosave = autosave.clone(); move |e: Event| {
+                    autosave.commit(e.target().unwrap().dyn_into::<HtmlTextAreaElement>().unwrap().value())
+                }}}
+                oninput={{let autosave = autosave.clone(); move |e: InputEvent| {
+                    autosave.input(e.target().unwrap().dyn_into::<HtmlTextAreaElement>().unwrap().value())
+                }}}
+                onkeydown={{let autosave = autosave.clone(); move |e: KeyboardEvent| if e.key() == "Escape" {
+                    let textarea = e.target().unwrap().dyn_into::<HtmlTextAreaElement>().unwrap();
+                    textarea.set_value(&autosave.cancel());
+                    let _ = textarea.blur();
+                }}}
+            />
+            <SaveIndicator status={*status} class="right-1 bottom-1 bg-neutral-200"/>
+        </div>
+    )
+}
+
+#[derive(Properties, PartialEq)]
+struct SaveIndicatorProps {
+    status: Option<SaveStatus>,
+    /// position in the field
+    class:  &'static str,
+}
+
+#[function_component]
+fn SaveIndicator(SaveIndicatorProps { status, class }: &SaveIndicatorProps) -> Html {
+    let t = use_messages();
+    let Some(status) = status else {return html!()};
+
+    let (mark, label, color) = match status {
+        SaveStatus::Saving => ("…", t.saving,      "text-neutral-400"),
+        SaveStatus::Saved  => ("✓", t.saved,       "text-emerald-600"),
+        SaveStatus::Failed => ("!", t.save_failed, "text-red-600"),
+    };
+    html!(
+        <span
+            class={classes!("absolute", "px-1", "text-xs", color, *class)}
+            role="status"
+            title={label}
+            aria-label={label}
+        >
+            {mark}
+        </span>
+    )
+}
+
+
+/// Saving the text of a field by `on_change` when it's committed by `change`,
+/// and also after a pause in typing, not to lose it by closing the page
+#[derive(Clone)]
+struct Autosave {
+    on_change: Option<Callback<String>>,
+    /// `value` of the latest render, to save only a changed text
+    shown:     Rc<RefCell<String>>,
+    /// the text before editing, put back by Esc
+    original:  Rc<RefCell<String>>,
+    /// inputs so far, for a delayed save to be done only after the last one
+    inputs:    Rc<RefCell<usize>>,
+}
+
+#[hook]
+fn use_autosave(value: &str, on_change: &Option<Callback<String>>) -> Autosave {
+    let autosave = Autosave {
+        on_change: on_change.clone(),
+        shown:     use_mut_ref(String::new),
+        original:  use_mut_ref(String::new),
+        inputs:    use_mut_ref(|| 0),
+    };
+    *autosave.shown.borrow_mut() = value.to_owned();
+    autosave
+}
+
+impl Autosave {
+    const DELAY: Duration = Duration::from_millis(800);
+
+    fn begin(&self, text: String) {
+        *self.original.borrow_mut() = text
+    }
+
+    fn input(&self, text: String) {
+        let input = {
+            let mut inputs = self.inputs.borrow_mut();
+            *inputs += 1;
+            *inputs
+        };
+        /* not to remove a sub-todo cleared to be retyped */
+        if text.is_empty() {
+            return
+        }
+        let this = self.clone();
+        spawn_local(async move {
+            sleep(Self::DELAY).await;
+            if *this.inputs.borrow() == input {
+                this.save(text)
+            }
+        })
+    }
+
+    /// Save `text` now, instead of the delayed one
+    fn commit(&self, text: String) {
+        *self.inputs.borrow_mut() += 1;
+        self.save(text)
+    }
+
+    /// The text before editing, saved again if any of the edit has been saved
+    fn cancel(&self) -> String {
+        let original = self.original.borrow().clone();
+        self.commit(original.clone());
+        original
+    }
+
+    fn save(&self, text: String) {
+        if *self.shown.borrow() != text {
+            if let Some(on_change) = &self.on_change {
+                on_change.emit(text)
+            }
+        }
+    }
+}
+
+fn render_markdown(inlines: &[markdown::Inline]) -> Html {
+    use markdown::Inline;
+
+    inlines.iter().map(|inline| match inline {
+        Inline::Text(text)    => html!({text}),
+        Inline::Bold(inner)   => html!(<strong>{render_markdown(inner)}</strong>),
+        Inline::Italic(inner) => html!(<em>{render_markdown(inner)}</em>),
+        Inline::Code(code)    => html!(<code class="px-1 rounded bg-neutral-200 text-sm">{code}</code>),
+        Inline::Link { text, url } => html!(
+            <a
+                href={url.clone()}
+                rel="noopener noreferrer"
+                target="_blank"
+                onclick={|e: MouseEvent| e.stop_propagation()}
+            >
+                {render_markdown(text)}
+            </a>
+        ),
+    }).collect()
+}
@@ -1,42 +1,138 @@
 use yew::prelude::*;
-use super::atoms::{TextInput, DeleteButton};
+use super::atoms::{TextInput, AttachmentChip, ArchiveButton, DeleteButton, DuplicateButton, OpenButton, SaveAsTemplateButton, UploadButton};
 use super::layouts::{CardLayout, TodoLayout};
-use crate::models::Card;
+use crate::ui::board::{CardSaves, Field};
+use crate::ui::i18n::use_messages;
+use models::{Attachment, Card, Template, ID};
+use std::rc::Rc;
 
 
 #[derive(Properties, PartialEq)]
 pub struct TodoCardProps {
-    pub bind: Card,
+    pub bind: Rc<Card>,
+    /// Being created and not yet editable
+    #[prop_or_default]
+    pub pending: bool,
+    /// Status of saving each field
+    #[prop_or_default]
+    pub saves:   Rc<CardSaves>,
 
-    pub on_click_delete:  Callback<()>,
-    pub on_edit_title:    Callback<String>,
-    pub on_check_todo_by: [Callback<()>; Card::N_TODOS],
-    pub on_edit_todo_by:  [Callback<String>; Card::N_TODOS],
+    /// Open the card alone, not shown when it's already
+    #[prop_or_default]
+    pub on_click_open:             Option<Callback<()>>,
+
+    pub on_click_archive:          Callback<()>,
+    pub on_click_delete:           Callback<()>,
+    pub on_click_duplicate:        Callback<()>,
+    pub on_click_save_as_template: Callback<()>,
+    pub on_upload_attachment:      Callback<web_sys::File>,
+    pub on_delete_attachment:      Callback<ID>,
+    pub on_edit_title:             Callback<String>,
+    pub on_check_todo_by:          [Callback<Vec<usize>>; Card::N_TODOS],
+    pub on_edit_todo_by:           [Callback<(Vec<usize>, String)>; Card::N_TODOS],
+    pub on_edit_notes_by:          [Callback<(Vec<usize>, String)>; Card::N_TODOS],
+    pub on_add_subtodo_by:         [Callback<Vec<usize>>; Card::N_TODOS],
 }
 
 #[function_component]
 pub fn TodoCard(props: &TodoCardProps) -> Html {
+    use web_sys::{HtmlInputElement, wasm_bindgen::JsCast};
+
+    let (file_input, t) = (use_node_ref(), use_messages());
+
+    /* for screen readers */
+    let name = if props.bind.title.is_empty() {String::from(t.untitled_card)} else {(t.card_named)(&props.bind.title)};
+
     html!(
         <CardLayout
+            label={name.clone()}
+            inert={props.pending}
             title={html!(
                 <TextInput
+                    label={t.title_of_card}
                     is_title={true}
                     value={props.bind.title.clone()}
                     on_change={props.on_edit_title.clone()}
+                    status={props.saves.status(&Field::Title)}
                 />
             )}
-            toolbox={html!(
+            toolbox={html!(<>
+                if let Some(on_click_open) = &props.on_click_open {
+                    <OpenButton
+                        class="basis-4"
+                        label={(t.open_card)(&name)}
+                        on_click={on_click_open.clone()}
+                    />
+                }
+                <DuplicateButton
+                    class="basis-4"
+                    label={(t.duplicate_card)(&name)}
+                    on_click={props.on_click_duplicate.clone()}
+                />
+                <SaveAsTemplateButton
+                    class="basis-4"
+                    label={(t.save_card_as_template)(&name)}
+                    on_click={props.on_click_save_as_template.clone()}
+                />
+                <UploadButton
+                    class="basis-4"
+                    label={(t.attach_to_card)(&name)}
+                    on_click={Callback::from({
+                        let file_input = file_input.clone();
+                        move |_| if let Some(input) = file_input.cast::<HtmlInputElement>() {
+                            input.click()
+                        }
+                    })}
+                />
+                <input
+                    ref={file_input}
+                    class="hidden"
+                    aria-hidden="true"
+                    tabindex="-1"
+                    type="file"
+                    accept={Attachment::MIME_TYPES.join(",")}
+                    onchange={Callback::from({
+                        let on_upload = props.on_upload_attachment.clone();
+                        move |e: Event| {
+                            let input = e.target().unwrap().dyn_into::<HtmlInputElement>().unwrap();
+                            if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                                on_upload.emit(file)
+                            }
+                            input.set_value("");
+                        }
+                    })}
+                />
+                <ArchiveButton
+                    class="basis-4"
+                    label={(t.archive_card)(&name)}
+                    on_click={props.on_click_archive.clone()}
+                />
                 <DeleteButton
+                    class="basis-4"
+                    label={(t.delete_card)(&name)}
                     on_click={props.on_click_delete.clone()}
                 />
-            )}
-            contents={html!(
+            </>)}
+            contents={html!(<>
                 <TodoLayout
                     todos={props.bind.todos.clone()}
+                    saves={props.saves.clone()}
                     on_check_todo={props.on_check_todo_by.clone()}
                     on_edit_todo={props.on_edit_todo_by.clone()}
+                    on_edit_notes={props.on_edit_notes_by.clone()}
+                    on_add_subtodo={props.on_add_subtodo_by.clone()}
                 />
-            )}
+                if !props.bind.attachments.is_empty() {
+                    <div class="mt-4 flex flex-wrap gap-1">
+                        {for props.bind.attachments.iter().map(|a| html!(
+                            <AttachmentChip
+                                attachment={a.clone()}
+                                on_click_delete={props.on_delete_attachment.reform({let id = a.id.clone(); move |_| id.clone()})}
+                            />
+                        ))}
+                    </div>
+                }
+            </>)}
         />
     )
 }
@@ -44,21 +140,24 @@ pub fn TodoCard(props: &TodoCardProps) -> Html {
 
 #[function_component]
 pub fn FrontCoverCard() -> Html {
+    let t = use_messages();
+
     html!(
         <CardLayout
+            label={t.note}
             title={html!(
                 <TextInput
+                    label={t.title_of_note}
                     is_title={true}
-                    value={String::from("Note")}
+                    value={String::from(t.note)}
                 />
             )}
             toolbox={/* empty */}
             contents={html!(
                 <ul class="m-0">
-                    <li>{"自動的に永久トークンを発行してlocalStorageに保存し、それをもってユーザーを識別しています。"}</li>
-                    <li>{"念のため、知られてはいけない情報は入力しないことをおすすめします。"}</li>
+                    {for t.cover_notes.into_iter().map(|note| html!(<li>{note}</li>))}
                     <li>
-                        {"repository: "}
+                        {t.repository}{": "}
                         <a
                             href="https://github.com/kana-rus/ohkami-yew-todo"
                             rel="noopener noreferrer"
@@ -76,25 +175,154 @@ pub fn FrontCoverCard() -> Html {
 
 #[derive(Properties, PartialEq)]
 pub struct PlusCardProps {
-    pub on_click: Callback<()>,
+    pub templates: Vec<Template>,
+
+    pub on_click_blank:     Callback<()>,
+    pub on_click_template:  Callback<ID>,
+    pub on_delete_template: Callback<ID>,
 }
 
 #[function_component]
 pub fn PlusCard(props: &PlusCardProps) -> Html {
+    let (choosing, t) = (use_state(|| false), use_messages());
+
+    if !*choosing {
+        return html!(
+            <button
+                type="button"
+                class="
+                    bg-slate-100
+                    cursor-pointer
+                    border-0
+                    rounded-xl rounded-tr-none
+                    w-72 min-w-72
+                    flex
+                    outline-none focus-visible:ring-2 focus-visible:ring-sky-400
+                "
+                aria-label={t.new_card}
+                aria-expanded="false"
+                onclick={{let choosing = choosing.clone(); move |_| choosing.set(true)}}
+            >
+                <span class="m-auto text-2xl text-neutral-800" aria-hidden="true">
+                    {"＋"}
+                </span>
+            </button>
+        )
+    }
+
+    let choice = |label: String, on_click: Callback<()>| html!(
+        <button
+            type="button"
+            class="w-full text-left text-base border-0 bg-transparent cursor-pointer rounded-md px-2 py-1 text-neutral-800 hover:bg-slate-200 truncate outline-none focus-visible:ring-2 focus-visible:ring-sky-400"
+            onclick={{let choosing = choosing.clone(); move |_| {
+                choosing.set(false);
+                on_click.emit(());
+            }}}
+        >
+            {label}
+        </button>
+    );
+
     html!(
-        <div
+        <section
             class="
                 bg-slate-100
-                cursor-pointer
                 rounded-xl rounded-tr-none
                 w-72 min-w-72
-                flex
+                p-4
+                flex flex-col
             "
-            onclick={props.on_click.reform(|_| ())}
+            aria-labelledby="new-card-title"
+        >
+            <header class="h-7 flex items-center">
+                <h2 id="new-card-title" class="grow m-0 text-lg font-normal text-neutral-800">{t.new_card}</h2>
+                <button
+                    type="button"
+                    class="p-0 border-0 rounded-sm bg-transparent cursor-pointer text-base text-neutral-500 outline-none focus-visible:ring-2 focus-visible:ring-sky-400"
+                    aria-label={t.close}
+                    onclick={{let choosing = choosing.clone(); move |_| choosing.set(false)}}
+                >
+                    {"×"}
+                </button>
+            </header>
+
+            <hr class="w-full border-neutral-400 my-4" />
+
+            <ul class="m-0 p-0 space-y-1 overflow-y-scroll">
+                <li class="list-none">
+                    {choice(String::from(t.blank), props.on_click_blank.clone())}
+                </li>
+                if !props.templates.is_empty() {
+                    <li class="list-none px-2 pt-2 text-sm text-neutral-500">{t.from_template}</li>
+                }
+                {for props.templates.iter().map(|template| {
+                    let title = if template.title.is_empty() {String::from(t.untitled_template)} else {template.title.clone()};
+                    html!(
+                        <li class="list-none flex items-center">
+                            <div class="grow min-w-0">
+                                {choice(
+                                    title.clone(),
+                                    props.on_click_template.reform({let id = template.id.clone(); move |_| id.clone()})
+                                )}
+                            </div>
+                            <DeleteButton
+                                class="basis-4 h-6"
+                                label={(t.delete_template)(&title)}
+                                on_click={props.on_delete_template.reform({let id = template.id.clone(); move |_| id.clone()})}
+                            />
+                        </li>
+                    )
+                })}
+            </ul>
+        </section>
+    )
+}
+
+
+#[derive(Properties, PartialEq)]
+pub struct ShelvedCardProps {
+    pub bind:     Rc<Card>,
+    /// In the trash, or else archived
+    pub in_trash: bool,
+
+    /// Put it back on the board
+    pub on_click_restore: Callback<()>,
+    /// Move it to the trash, or delete it for good in the trash
+    pub on_click_delete:  Callback<()>,
+}
+
+/// A row of the archive or the trash
+#[function_component]
+pub fn ShelvedCard(props: &ShelvedCardProps) -> Html {
+    let t = use_messages();
+
+    let name = if props.bind.title.is_empty() {String::from(t.untitled_card)} else {(t.card_named)(&props.bind.title)};
+    let (restore, restore_label, delete, delete_label) = match props.in_trash {
+        false => (t.unarchive, (t.unarchive_card)(&name), t.delete, (t.delete_card)(&name)),
+        true  => (t.restore, (t.restore_card)(&name), t.delete_forever, (t.delete_card_forever)(&name)),
+    };
+    let completed = props.bind.todos.iter().filter(|todo| !todo.content.is_empty() && todo.completed).count();
+    let written   = props.bind.todos.iter().filter(|todo| !todo.content.is_empty()).count();
+
+    let button = |text: &'static str, label: String, on_click: &Callback<()>| html!(
+        <button
+            type="button"
+            class="px-2 py-1 rounded-md border border-solid border-neutral-300 bg-white cursor-pointer text-sm outline-none focus-visible:ring-2 focus-visible:ring-sky-400"
+            aria-label={label}
+            onclick={on_click.reform(|_| ())}
         >
-            <p class="m-auto text-2xl text-neutral-800">
-                {"＋"}
-            </p>
-        </div>
+            {text}
+        </button>
+    );
+
+    html!(
+        <li class="list-none px-4 py-2 rounded-xl bg-slate-100 flex items-center space-x-2">
+            <span class="grow min-w-0 truncate text-neutral-800">
+                {if props.bind.title.is_empty() {t.untitled_card} else {&props.bind.title}}
+            </span>
+            <span class="text-sm text-neutral-500">{format!("{completed}/{written}")}</span>
+            {button(restore, restore_label, &props.on_click_restore)}
+            {button(delete, delete_label, &props.on_click_delete)}
+        </li>
     )
 }
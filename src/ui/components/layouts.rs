@@ -1,6 +1,10 @@
 use yew::prelude::*;
-use crate::models::{Card, Todo};
-use super::atoms::{TextInput, CheckBoxButton};
+use models::{Card, Todo};
+use super::atoms::{TextInput, TextArea, CheckBoxButton};
+use crate::ui::board::{CardSaves, Field, TodoId};
+use crate::ui::i18n::use_messages;
+use crate::ui::shortcuts::focus_todo_next_to;
+use std::rc::Rc;
 
 
 #[derive(Properties, PartialEq)]
@@ -8,34 +12,49 @@ pub struct CardLayoutProps {
     pub title:    Html,
     pub toolbox:  Html,
     pub contents: Html,
+
+    /// Name of the card for screen readers
+    pub label: AttrValue,
+
+    /// Shown but not operable, like while being saved
+    #[prop_or_default]
+    pub inert: bool,
 }
 
 #[function_component]
 pub fn CardLayout(props: &CardLayoutProps) -> Html {
+    let class = "
+        bg-neutral-100
+        rounded-xl rounded-tr-none
+        border border-solid border-neutral-300
+        shadow-lg shadow-neutral-300
+        w-72 min-w-72 h-[374px]
+        p-4
+        flex flex-col
+    ";
+
     html!(
-        <div
-            class="
-                bg-neutral-100
-                rounded-xl rounded-tr-none
-                border border-solid border-neutral-300
-                shadow-lg shadow-neutral-300
-                w-72 min-w-72 h-[374px]
-                p-4
-            "
+        <article
+            class={classes!(class, props.inert.then_some("opacity-60 pointer-events-none"))}
+            aria-label={props.label.clone()}
+            inert={props.inert.then_some(AttrValue::Static(""))}
+            aria-busy={props.inert.then_some(AttrValue::Static("true"))}
         >
             <header class="h-7 space-x-2 flex items-center">
                 <div class="grow h-7">
                     {props.title.clone()}
                 </div>
-                <div class="basis-4 h-6">
+                <div class="h-6 flex space-x-1">
                     {props.toolbox.clone()}
                 </div>
             </header>
 
             <hr class="border-neutral-400 my-4" />
 
-            {props.contents.clone()}
-        </div>
+            <div class="grow min-h-0 overflow-y-scroll">
+                {props.contents.clone()}
+            </div>
+        </article>
     )
 }
 
@@ -44,35 +63,218 @@ pub fn CardLayout(props: &CardLayoutProps) -> Html {
 pub struct TodoLayoutProps {
     pub todos: [Todo; Card::N_TODOS],
 
+    #[prop_or_default]
+    pub saves:          Rc<CardSaves>,
     #[prop_or(true)]
-    pub checkable:     bool,
+    pub checkable:      bool,
     #[prop_or_else(|| std::array::from_fn(|_| Callback::noop()))]
-    pub on_check_todo: [Callback<()>; Card::N_TODOS],
+    pub on_check_todo:  [Callback<Vec<usize>>; Card::N_TODOS],
     #[prop_or_else(|| std::array::from_fn(|_| Callback::noop()))]
-    pub on_edit_todo:  [Callback<String>; Card::N_TODOS],
+    pub on_edit_todo:   [Callback<(Vec<usize>, String)>; Card::N_TODOS],
+    #[prop_or_else(|| std::array::from_fn(|_| Callback::noop()))]
+    pub on_edit_notes:  [Callback<(Vec<usize>, String)>; Card::N_TODOS],
+    #[prop_or_else(|| std::array::from_fn(|_| Callback::noop()))]
+    pub on_add_subtodo: [Callback<Vec<usize>>; Card::N_TODOS],
 }
 
 #[function_component]
 pub fn TodoLayout(props: &TodoLayoutProps) -> Html {
+    let t = use_messages();
+
     html!(
-        <ul class="m-0 p-0 space-y-2">
+        <ul class="m-0 p-0 space-y-2" aria-label={t.todos}>
             {for props.todos.iter().enumerate().map(|(i, todo)| html!(
-                <li class="list-none flex items-center space-x-2">
-                    <CheckBoxButton
-                        class="basis-4 h-6"
-                        checked={todo.completed}
-                        on_click={(
-                            props.checkable &&
-                            (!todo.content.is_empty())
-                        ).then(|| props.on_check_todo[i].clone())}
-                    />
-                    <TextInput
-                        class="grow h-6 m-0 p-0"
-                        value={todo.content.clone()}
-                        on_change={(!todo.completed).then(|| props.on_edit_todo[i].clone())}
-                    />
-                </li>
+                <TodoItem
+                    todo={todo.clone()}
+                    index={i}
+                    path={vec![]}
+                    saves={props.saves.clone()}
+                    checkable={props.checkable}
+                    on_check={props.on_check_todo[i].clone()}
+                    on_edit={props.on_edit_todo[i].clone()}
+                    on_edit_notes={props.on_edit_notes[i].clone()}
+                    on_add_subtodo={props.on_add_subtodo[i].clone()}
+                    full={todo.count_subtodos() >= Todo::MAX_SUBTODOS}
+                />
             ))}
         </ul>
     )
 }
+
+
+/// Classes of the small buttons of a todo, showing a character
+const SMALL_BUTTON_CLASS: &str = "
+    basis-3 p-0 border-0 rounded-sm bg-transparent
+    cursor-pointer
+    outline-none focus-visible:ring-2 focus-visible:ring-sky-400
+";
+
+#[derive(Properties, PartialEq)]
+struct TodoItemProps {
+    todo:  Todo,
+    /// index of the top-level todo of this todo
+    index: usize,
+    /// path from the top-level todo to this todo
+    path:  Vec<usize>,
+    saves: Rc<CardSaves>,
+
+    checkable:      bool,
+    on_check:       Callback<Vec<usize>>,
+    on_edit:        Callback<(Vec<usize>, String)>,
+    on_edit_notes:  Callback<(Vec<usize>, String)>,
+    on_add_subtodo: Callback<Vec<usize>>,
+    /// The top-level todo has `Todo::MAX_SUBTODOS` sub-todos
+    full:           bool,
+}
+
+#[function_component]
+fn TodoItem(props: &TodoItemProps) -> Html {
+    use web_sys::{HtmlElement, HtmlInputElement, wasm_bindgen::JsCast};
+
+    let collapsed  = use_state(|| false);
+    let notes_open = use_state(|| false);
+    let t          = use_messages();
+
+    let todo = &props.todo;
+    let path = &props.path;
+    let id   = TodoId { index: props.index, path: path.clone() };
+
+    let child_path = |i: usize| {
+        let mut path = path.clone();
+        path.push(i);
+        path
+    };
+
+    let checkable = props.checkable && !todo.content.is_empty();
+
+    /* for screen readers, like "Todo 1.2" or its content */
+    let number = std::iter::once(props.index).chain(path.iter().copied())
+        .map(|i| (i + 1).to_string())
+        .collect::<Vec<_>>().join(".");
+    let label = if todo.content.is_empty() {(t.todo_number)(&number)} else {todo.content.clone()};
+
+    /* `x` and `Enter` on the row itself, and `Enter` and `Esc` in its input
+       ( after the input has committed or reset the value and blurred ) */
+    let onkeydown = {
+        let (on_check, path) = (props.on_check.clone(), path.clone());
+        move |e: KeyboardEvent| {
+            let Some(row) = e.current_target().and_then(|t| t.dyn_into::<HtmlElement>().ok()) else {return};
+            let on_row   = e.target().is_some_and(|t| row.is_same_node(t.dyn_ref()));
+            let in_input = e.target().is_some_and(|t| t.has_type::<HtmlInputElement>());
+            match (&*e.key(), on_row, in_input) {
+                ("x", true, _) => if checkable {
+                    on_check.emit(path.clone())
+                }
+                ("Enter", true, _) => {
+                    e.prevent_default();
+                    if let Some(editor) = row.query_selector("input:not([disabled]), p[tabindex]").ok().flatten()
+                        .and_then(|editor| editor.dyn_into::<HtmlElement>().ok())
+                    {
+                        let _ = editor.focus();
+                    }
+                }
+                ("Enter", _, true) => focus_todo_next_to(Some(&row), true),
+                ("Escape", _, true) => {let _ = row.focus();}
+                _ => ()
+            }
+        }
+    };
+
+    html!(
+        <li class="list-none">
+            <div
+                class="flex items-center space-x-2 rounded-sm outline-none focus-visible:ring-2 focus-visible:ring-sky-400"
+                style={format!("padding-left: {}rem", path.len())}
+                tabindex="0"
+                role="group"
+                aria-label={(t.todo_number)(&number)}
+                data-todo=""
+                {onkeydown}
+            >
+                if todo.children.is_empty() {
+                    <span class="basis-3 invisible" aria-hidden="true">{"▾"}</span>
+                } else {
+                    <button
+                        type="button"
+                        class={classes!(SMALL_BUTTON_CLASS, "text-xs", "text-neutral-500")}
+                        aria-label={(t.sub_todos_of)(&label)}
+                        aria-expanded={if *collapsed {"false"} else {"true"}}
+                        onclick={{let collapsed = collapsed.clone(); move |_| collapsed.set(!*collapsed)}}
+                    >
+                        {if *collapsed {"▸"} else {"▾"}}
+                    </button>
+                }
+                <CheckBoxButton
+                    class="basis-4 h-6"
+                    checked={todo.completed}
+                    label={label.clone()}
+                    on_click={checkable.then(|| props.on_check.reform({let path = path.clone(); move |_| path.clone()}))}
+                />
+                <TextInput
+                    class="grow min-w-0 h-6 m-0 p-0"
+                    markdown={true}
+                    label={(t.todo_number)(&number)}
+                    value={todo.content.clone()}
+                    status={props.saves.status(&Field::Content(id.clone()))}
+                    on_change={(!todo.completed).then(|| props.on_edit.reform({let path = path.clone(); move |content| (path.clone(), content)}))}
+                />
+                if todo.content.is_empty() {
+                    <span class="basis-3 invisible" aria-hidden="true">{"≡"}</span>
+                } else {
+                    <button
+                        type="button"
+                        class={classes!(SMALL_BUTTON_CLASS, if todo.notes.is_some() {"text-neutral-800"} else {"text-neutral-400"})}
+                        aria-label={(t.notes_of)(&label)}
+                        aria-expanded={if *notes_open {"true"} else {"false"}}
+                        onclick={{let notes_open = notes_open.clone(); move |_| notes_open.set(!*notes_open)}}
+                    >
+                        {"≡"}
+                    </button>
+                }
+                if todo.content.is_empty() || todo.completed || props.full || path.len() >= Todo::MAX_DEPTH {
+                    <span class="basis-3 invisible" aria-hidden="true">{"+"}</span>
+                } else {
+                    <button
+                        type="button"
+                        class={classes!(SMALL_BUTTON_CLASS, "text-neutral-500")}
+                        aria-label={(t.add_subtodo_to)(&label)}
+                        onclick={props.on_add_subtodo.reform({let (path, collapsed) = (path.clone(), collapsed.clone()); move |_| {
+                            collapsed.set(false);
+                            path.clone()
+                        }})}
+                    >
+                        {"+"}
+                    </button>
+                }
+            </div>
+            if *notes_open && !todo.content.is_empty() {
+                <TextArea
+                    class="mt-1"
+                    label={(t.notes_of)(&label)}
+                    placeholder={t.notes_placeholder}
+                    value={todo.notes.clone().unwrap_or_default()}
+                    status={props.saves.status(&Field::Notes(id.clone()))}
+                    on_change={(!todo.completed).then(|| props.on_edit_notes.reform({let path = path.clone(); move |notes| (path.clone(), notes)}))}
+                />
+            }
+            if !*collapsed && !todo.children.is_empty() {
+                <ul class="m-0 p-0 mt-2 space-y-2">
+                    {for todo.children.iter().enumerate().map(|(i, child)| html!(
+                        <TodoItem
+                            todo={child.clone()}
+                            index={props.index}
+                            path={child_path(i)}
+                            saves={props.saves.clone()}
+                            checkable={props.checkable}
+                            on_check={props.on_check.clone()}
+                            on_edit={props.on_edit.clone()}
+                            on_edit_notes={props.on_edit_notes.clone()}
+                            on_add_subtodo={props.on_add_subtodo.clone()}
+                            full={props.full}
+                        />
+                    ))}
+                </ul>
+            }
+        </li>
+    )
+}
@@ -3,3 +3,4 @@ mod cards;
 mod layouts;
 
 pub use cards::*;
+pub use atoms::RetryBanner;
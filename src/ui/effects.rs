@@ -0,0 +1,658 @@
+//! API calls for the actions on the board
+//!
+//! Creating, deleting and editing a card are dispatched to `Board` before
+//! the request and rolled back when it fails, and recorded in `History`.
+//! The others, including archiving and the actions in the trash, are
+//! dispatched after the server accepts them.
+
+use super::board::{Board, BoardAction, CardChange, Field, SaveStatus, Shelf};
+use super::history::{History, Op};
+use super::fetch::{self, Client};
+use super::i18n::{use_messages, Messages};
+use super::toasts::{use_toaster, Toast, Toaster};
+use super::utils::reload_page;
+use models::{Attachment, Card, CreateCardRequest, PairingCodeResponse, Template, Todo, UpdateCard};
+use yew::prelude::*;
+use yew::platform::{pinned::oneshot, spawn_local, time::sleep};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::rc::Rc;
+use std::time::Duration;
+
+
+#[derive(Clone)]
+pub struct Effects {
+    client:  Rc<Client>,
+    board:   UseReducerHandle<Board>,
+    /// `board` of the latest render, for the retried tasks
+    /// ( a handle holds the board of the render it's from )
+    latest:  Rc<RefCell<UseReducerHandle<Board>>>,
+    toaster: Toaster,
+    edits:   Rc<RefCell<PendingEdits>>,
+    history: Rc<RefCell<History>>,
+    t:       &'static Messages,
+}
+
+#[hook]
+pub fn use_effects(client: &Rc<Client>, board: &UseReducerHandle<Board>) -> Effects {
+    let latest = use_mut_ref(|| board.clone());
+    *latest.borrow_mut() = board.clone();
+
+    Effects {
+        client:  client.clone(),
+        board:   board.clone(),
+        latest,
+        toaster: use_toaster(),
+        edits:   use_mut_ref(PendingEdits::default),
+        history: use_mut_ref(History::default),
+        t:       use_messages(),
+    }
+}
+
+impl Effects {
+    /// `Callback` calling `f` with these effects
+    pub fn callback<T: 'static>(&self, f: impl Fn(&Self, T) + 'static) -> Callback<T> {
+        let this = self.clone();
+        Callback::from(move |arg| f(&this, arg))
+    }
+
+    /// Run `task` with the toast of `failure` and Retry when it fails
+    fn run<Fut: Future<Output = Result<(), fetch::Error>> + 'static>(&self,
+        failure: impl Into<String>,
+        task:    impl Fn(Self) -> Fut + 'static,
+    ) {
+        let this = self.clone();
+        self.toaster.run(failure, move || task(this.clone()))
+    }
+
+    /// Show the toast of `message` with Undo of the history entry `entry`
+    fn offer_undo(&self, message: &'static str, entry: usize) {
+        let this = self.clone();
+        self.toaster.show(Toast::success(message).with_action(self.t.undo, move || {
+            let op = this.history.borrow_mut().undo_entry(entry);
+            match op {
+                Some(op) => this.replay(op),
+                None if this.history.borrow().is_buried(entry) => this.toaster.show(Toast::warning(this.t.undo_outdated)),
+                None => (),
+            }
+        }))
+    }
+
+    pub fn undo(&self) {
+        let op = self.history.borrow_mut().undo();
+        if let Some(op) = op {self.replay(op)}
+    }
+
+    pub fn redo(&self) {
+        let op = self.history.borrow_mut().redo();
+        if let Some(op) = op {self.replay(op)}
+    }
+
+    /// Do `op` again, without recording it
+    fn replay(&self, op: Op) {
+        match op {
+            Op::Create { at, card } => self.restore_card(at, card),
+            Op::Delete { card, .. } => self.remove_card(self.t.delete_card_failed, card.id),
+            Op::Edit { after, .. }  => self.save_change(&after.id, CardChange::Restore { title: after.title, todos: after.todos }),
+        }
+    }
+
+    pub async fn load(self) {
+        let loaded = async {
+            let cards     = self.client.list_cards().await?;
+            let archived  = self.client.list_archived_cards().await?;
+            let trashed   = self.client.list_trashed_cards().await?;
+            let templates = self.client.list_templates().await?;
+            self.board.dispatch(BoardAction::Load { cards, archived, trashed, templates });
+            Result::<(), fetch::Error>::Ok(())
+        }.await;
+        if let Err(err) = loaded {
+            self.toaster.show(Toast::error(format!("{}: {}", self.t.fetch_failed, fetch::describe(&err, self.t)))
+                .with_action(self.t.retry, reload_page))
+        }
+    }
+
+    pub fn create_blank_card(&self) {
+        /* the same for Retry, to create only one after a lost response */
+        let (id, key) = (fetch::new_card_id(), fetch::new_idempotency_key());
+        let at = self.board.cards.len();
+
+        self.run(self.t.create_card_failed, move |this| {
+            let (id, key) = (id.clone(), key.clone());
+            this.board.dispatch(BoardAction::AddPendingCard { at, card: blank_card(id.clone()) });
+
+            async move {
+                let req = CreateCardRequest { id: Some(id.clone()), ..CreateCardRequest::empty() };
+                let created = match this.client.idempotent(key).create_card(&req).await {
+                    Ok(created) => created,
+                    /* keep it pending : Retry with the same key gets the created one */
+                    Err(fetch::Error::InProgress) => return Err(fetch::Error::InProgress),
+                    /* already created by the previous attempt */
+                    Err(err) if err.status() == Some(fetch::StatusCode::CONFLICT) => id.clone(),
+                    Err(err) => {
+                        this.board.dispatch(BoardAction::RemoveCard(id));
+                        return Err(err)
+                    }
+                };
+                this.history.borrow_mut().record(Op::Create { at, card: blank_card(created.clone()) });
+                this.board.dispatch(BoardAction::PersistCard { pending_id: id, id: created });
+                Ok(())
+            }
+        })
+    }
+
+    pub fn create_card_from_template(&self, template_id: &str) {
+        let Some(Template { id: template_id, title, todos }) = self.board.template(template_id).cloned() else {return};
+
+        self.run(self.t.create_from_template_failed, move |this| {
+            let (template_id, title, todos) = (template_id.clone(), title.clone(), todos.clone());
+            async move {
+                let card = Card {
+                    id: this.client.instantiate_template(&template_id).await?,
+                    title,
+                    todos: todos.map(Todo::new),
+                    attachments: vec![],
+                };
+                this.created(this.t.created_from_template, card);
+                Ok(())
+            }
+        })
+    }
+
+    pub fn duplicate_card(&self, card_id: &str) {
+        let Some(card) = self.board.card(card_id).cloned() else {return};
+
+        self.run(self.t.duplicate_failed, move |this| {
+            let card = card.clone();
+            async move {
+                let duplicated = Card {
+                    id: this.client.duplicate_card(&card.id).await?,
+                    title: card.title.clone(),
+                    todos: card.todos.clone().map(|mut todo| {
+                        todo.set_completed(false);
+                        todo
+                    }),
+                    attachments: vec![],
+                };
+                this.created(this.t.duplicated, duplicated);
+                Ok(())
+            }
+        })
+    }
+
+    /// Show `card` created by the server, with Undo
+    fn created(&self, message: &'static str, card: Card) {
+        let at = self.board.cards.len();
+        self.board.dispatch(BoardAction::AddCard(card.clone()));
+        let entry = self.history.borrow_mut().record(Op::Create { at, card });
+        self.offer_undo(message, entry)
+    }
+
+    pub fn delete_card(&self, card_id: &str) {
+        let Some(at) = self.board.position(card_id) else {return};
+        let entry = self.history.borrow_mut().record(Op::Delete { at, card: (*self.board.cards[at]).clone() });
+        self.remove_card(self.t.delete_card_failed, card_id.to_owned());
+        self.offer_undo(self.t.deleted_card, entry)
+    }
+
+    /// Move the card on the board to the trash
+    fn remove_card(&self, failure: &'static str, card_id: String) {
+        self.run(failure, move |this| {
+            let (card_id, board) = (card_id.clone(), this.latest_board());
+            let before = board.position(&card_id).map(|at| (at, board.cards[at].clone()));
+            this.board.dispatch(BoardAction::ShelveCard { card_id: card_id.clone(), shelf: Shelf::Trash });
+
+            async move {
+                this.client.delete_card(&card_id).await.inspect_err(|_| if let Some((at, card)) = before {
+                    this.board.dispatch(BoardAction::UnshelveCard { at, card })
+                })
+            }
+        })
+    }
+
+    /// Put `card` back from the trash at `at` of the board, to undo deleting it
+    /// or redo creating it
+    fn restore_card(&self, at: usize, card: Card) {
+        self.run(self.t.restore_failed, move |this| {
+            /* with the attachments it has in the trash */
+            let card = this.latest_board().any_card(&card.id).cloned().unwrap_or_else(|| Rc::new(card.clone()));
+            this.board.dispatch(BoardAction::UnshelveCard { at, card: card.clone() });
+
+            async move {
+                this.client.restore_card(&card.id).await.inspect_err(|_| {
+                    this.board.dispatch(BoardAction::ShelveCard { card_id: card.id.clone(), shelf: Shelf::Trash })
+                })
+            }
+        })
+    }
+
+    pub fn archive_card(&self, card_id: &str) {
+        let card_id = card_id.to_owned();
+        self.run(self.t.archive_failed, move |this| {
+            let card_id = card_id.clone();
+            async move {
+                this.client.archive_card(&card_id).await?;
+                this.board.dispatch(BoardAction::ShelveCard { card_id: card_id.clone(), shelf: Shelf::Archive });
+                this.toaster.show(Toast::success(this.t.archived_card).with_action(this.t.undo, {
+                    let this = this.clone();
+                    move || this.unarchive_card(&card_id)
+                }));
+                Ok(())
+            }
+        })
+    }
+
+    pub fn unarchive_card(&self, card_id: &str) {
+        let card_id = card_id.to_owned();
+        self.run(self.t.unarchive_failed, move |this| {
+            let card_id = card_id.clone();
+            async move {
+                this.client.unarchive_card(&card_id).await?;
+                this.put_back(&card_id);
+                Ok(())
+            }
+        })
+    }
+
+    /// Move the archived card to the trash
+    pub fn trash_archived_card(&self, card_id: &str) {
+        let card_id = card_id.to_owned();
+        self.run(self.t.delete_card_failed, move |this| {
+            let card_id = card_id.clone();
+            async move {
+                this.client.delete_card(&card_id).await?;
+                this.board.dispatch(BoardAction::ShelveCard { card_id, shelf: Shelf::Trash });
+                Ok(())
+            }
+        })
+    }
+
+    pub fn restore_trashed_card(&self, card_id: &str) {
+        let card_id = card_id.to_owned();
+        self.run(self.t.restore_failed, move |this| {
+            let card_id = card_id.clone();
+            async move {
+                this.client.restore_card(&card_id).await?;
+                this.put_back(&card_id);
+                Ok(())
+            }
+        })
+    }
+
+    pub fn delete_card_forever(&self, card_id: &str) {
+        let card_id = card_id.to_owned();
+        self.run(self.t.delete_forever_failed, move |this| {
+            let card_id = card_id.clone();
+            async move {
+                this.client.delete_card_forever(&card_id).await?;
+                this.board.dispatch(BoardAction::RemoveCard(card_id));
+                Ok(())
+            }
+        })
+    }
+
+    /// Put the card on a shelf at the end of the board
+    fn put_back(&self, card_id: &str) {
+        let board = self.latest_board();
+        if let Some(card) = board.any_card(card_id).cloned() {
+            self.board.dispatch(BoardAction::UnshelveCard { at: board.cards.len(), card })
+        }
+    }
+
+    pub fn change_card(&self, card_id: &str, change: CardChange) {
+        if let (true, Some(before)) = (change.is_saved(), self.board.card(card_id)) {
+            let mut after = (**before).clone();
+            change.apply(&mut after);
+            self.history.borrow_mut().record(Op::Edit { before: (**before).clone(), after });
+        }
+        self.save_change(card_id, change)
+    }
+
+    /// Save `change` without recording it
+    fn save_change(&self, card_id: &str, change: CardChange) {
+        if !change.is_saved() {
+            return self.board.dispatch(BoardAction::ChangeCard { card_id: card_id.to_owned(), change })
+        }
+        let card_id = card_id.to_owned();
+
+        self.run(change.failure(self.t), move |this| {
+            /* the card shown now, not at the first attempt, for Retry
+               not to put back the edits saved since then */
+            let begun = this.latest_board().card(&card_id).cloned().map(|shown| {
+                let edit = this.edits.borrow_mut().begin(&shown, change.clone());
+                this.board.dispatch(BoardAction::ChangeCard { card_id: shown.id.clone(), change: change.clone() });
+                this.show_save(&shown.id, &change.field(), edit, SaveStatus::Saving);
+                edit
+            });
+            let (card_id, field) = (card_id.clone(), change.field());
+
+            async move {
+                /* deleted in the meantime */
+                let Some(edit) = begun else {return Ok(())};
+
+                loop {
+                    let turn = this.edits.borrow_mut().turn(&card_id, edit);
+                    let sent = match turn {
+                        Turn::Wait(done) => {let _ = done.await; continue}
+                        Turn::Saved => {
+                            this.show_save(&card_id, &field, edit, SaveStatus::Saved);
+                            return Ok(())
+                        }
+                        Turn::Send(card) => card,
+                    };
+
+                    let result = this.client.update_card(&sent.id, &UpdateCard {
+                        title: sent.title.clone(),
+                        todos: sent.todos.clone(),
+                    }).await;
+                    match &result {
+                        Ok(()) => {
+                            this.show_save(&card_id, &field, edit, SaveStatus::Saved);
+                            this.edits.borrow_mut().succeed(edit, sent)
+                        }
+                        Err(_) => {
+                            this.show_save(&card_id, &field, edit, SaveStatus::Failed);
+                            if let Some(card) = this.edits.borrow_mut().fail(&card_id, edit) {
+                                this.board.dispatch(BoardAction::RevertCard(card))
+                            }
+                        }
+                    }
+                    return result
+                }
+            }
+        })
+    }
+
+    /// Show `status` of saving `field` of the card by `edit`, and hide it a while after saved
+    fn show_save(&self, card_id: &str, field: &Option<Field>, edit: usize, status: SaveStatus) {
+        const SAVED_SHOWN: Duration = Duration::from_secs(2);
+
+        let Some(field) = field.clone() else {return};
+        self.board.dispatch(BoardAction::SaveField { card_id: card_id.to_owned(), field: field.clone(), edit, status });
+        if status == SaveStatus::Saved {
+            let (board, card_id) = (self.board.clone(), card_id.to_owned());
+            spawn_local(async move {
+                sleep(SAVED_SHOWN).await;
+                board.dispatch(BoardAction::ClearSaved { card_id, field, edit })
+            })
+        }
+    }
+
+    fn latest_board(&self) -> UseReducerHandle<Board> {
+        self.latest.borrow().clone()
+    }
+
+    /// Whether any edit is still to be saved, not to leave the page then
+    pub fn is_saving(&self) -> bool {
+        !self.edits.borrow().is_empty()
+    }
+
+    pub fn save_card_as_template(&self, card_id: &str) {
+        let Some(card) = self.board.card(card_id).cloned() else {return};
+
+        self.run(self.t.save_as_template_failed, move |this| {
+            let card = card.clone();
+            async move {
+                let id = this.client.create_template(&card.id).await?;
+                this.board.dispatch(BoardAction::AddTemplate(Template {
+                    id: id.clone(),
+                    title: card.title.clone(),
+                    todos: card.todos.clone().map(|Todo { content, .. }| content),
+                }));
+                this.toaster.show(Toast::success(this.t.saved_as_template).with_action(this.t.undo, {
+                    let this = this.clone();
+                    move || this.remove_template(this.t.undo_template_failed, id.clone())
+                }));
+                Ok(())
+            }
+        })
+    }
+
+    pub fn delete_template(&self, template_id: &str) {
+        self.remove_template(self.t.delete_template_failed, template_id.to_owned())
+    }
+
+    fn remove_template(&self, failure: &'static str, template_id: String) {
+        self.run(failure, move |this| {
+            let template_id = template_id.clone();
+            async move {
+                this.client.delete_template(&template_id).await?;
+                this.board.dispatch(BoardAction::RemoveTemplate(template_id));
+                Ok(())
+            }
+        })
+    }
+
+    pub fn upload_attachment(&self, card_id: &str, file: web_sys::File) {
+        use web_sys::js_sys::Uint8Array;
+
+        let (this, card_id) = (self.clone(), card_id.to_owned());
+        wasm_bindgen_futures::spawn_local(async move {
+            let (name, mime_type) = (file.name(), file.type_());
+            if !Attachment::MIME_TYPES.contains(&&*mime_type) {
+                return this.toaster.show(Toast::warning((this.t.unsupported_attachment)(&name)))
+            }
+            if file.size() as usize > Attachment::MAX_SIZE {
+                return this.toaster.show(Toast::warning((this.t.attachment_too_large)(&name, Attachment::MAX_SIZE / 1024 / 1024)))
+            }
+
+            let Ok(buffer) = wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await else {
+                return this.toaster.show(Toast::error((this.t.read_failed)(&name)))
+            };
+            let content = Uint8Array::new(&buffer).to_vec();
+
+            this.run((this.t.attach_failed)(&name), move |this| {
+                let (card_id, name, mime_type, content) = (card_id.clone(), name.clone(), mime_type.clone(), content.clone());
+                async move {
+                    let attachment = this.client.upload_attachment(&card_id, &name, &mime_type, content).await?;
+                    this.board.dispatch(BoardAction::AddAttachment { card_id, attachment });
+                    Ok(())
+                }
+            })
+        })
+    }
+
+    pub fn delete_attachment(&self, card_id: &str, attachment_id: String) {
+        let card_id = card_id.to_owned();
+        self.run(self.t.delete_attachment_failed, move |this| {
+            let (card_id, attachment_id) = (card_id.clone(), attachment_id.clone());
+            async move {
+                this.client.delete_attachment(&card_id, &attachment_id).await?;
+                this.board.dispatch(BoardAction::RemoveAttachment { card_id, attachment_id });
+                Ok(())
+            }
+        })
+    }
+
+    /// Issue a code for `todo login --code` and give it to `on_issued`
+    pub fn issue_pairing_code(&self, on_issued: Callback<PairingCodeResponse>) {
+        self.run(self.t.pairing_code_failed, move |this| {
+            let on_issued = on_issued.clone();
+            async move {
+                on_issued.emit(this.client.create_pairing_code().await?);
+                Ok(())
+            }
+        })
+    }
+}
+
+/// Save the text being typed when the page is hidden or left, and ask to stay
+/// on the page while any edit is still being saved
+#[hook]
+pub fn use_flush_on_leave(effects: &Effects) {
+    use web_sys::{BeforeUnloadEvent, Event, HtmlElement, VisibilityState};
+    use web_sys::wasm_bindgen::{closure::Closure, JsCast};
+
+    /* the field commits its text by `change` on the blur */
+    fn blur_active_field() {
+        let active = web_sys::window().and_then(|w| w.document()).and_then(|d| d.active_element());
+        if let Some(active) = active.and_then(|a| a.dyn_into::<HtmlElement>().ok()) {
+            let _ = active.blur();
+        }
+    }
+
+    use_effect_with((), {
+        let effects = effects.clone();
+        move |()| {
+            let on_visibility_change = Closure::<dyn Fn(Event)>::new(|_| {
+                let document = web_sys::window().and_then(|w| w.document());
+                if document.is_some_and(|d| d.visibility_state() == VisibilityState::Hidden) {
+                    blur_active_field()
+                }
+            });
+            let on_before_unload = Closure::<dyn Fn(BeforeUnloadEvent)>::new(move |e: BeforeUnloadEvent| {
+                blur_active_field();
+                if effects.is_saving() {
+                    e.prevent_default();
+                    e.set_return_value("");
+                }
+            });
+
+            let (window, document) = (web_sys::window(), web_sys::window().and_then(|w| w.document()));
+            if let Some(document) = &document {
+                let _ = document.add_event_listener_with_callback("visibilitychange", on_visibility_change.as_ref().unchecked_ref());
+            }
+            if let Some(window) = &window {
+                let _ = window.add_event_listener_with_callback("beforeunload", on_before_unload.as_ref().unchecked_ref());
+            }
+            move || {
+                if let Some(document) = document {
+                    let _ = document.remove_event_listener_with_callback("visibilitychange", on_visibility_change.as_ref().unchecked_ref());
+                }
+                if let Some(window) = window {
+                    let _ = window.remove_event_listener_with_callback("beforeunload", on_before_unload.as_ref().unchecked_ref());
+                }
+            }
+        }
+    });
+}
+
+fn blank_card(id: String) -> Card {
+    Card {
+        id,
+        title: String::new(),
+        todos: std::array::from_fn(|_| Todo::new("")),
+        attachments: vec![],
+    }
+}
+
+
+/// Edits of each card not saved yet
+///
+/// Each save puts the whole card, and only one save of a card is sent at a
+/// time, with all the edits of the card so far, not for an older one to
+/// arrive after a newer one. The edits made meanwhile wait for it to end,
+/// and are saved together by the next one. So the card on the server is the
+/// last one saved, and what to show after a save is rejected is that with
+/// the edits still to be saved.
+#[derive(Default)]
+struct PendingEdits {
+    count:   usize,
+    by_card: HashMap<models::ID, CardEdits>,
+}
+
+struct CardEdits {
+    saved:   Card,
+    /// with whether it's in the save being sent
+    pending: Vec<(usize, CardChange, bool)>,
+    sending: bool,
+    /// saved by the save of another edit, and not told to its task yet
+    done:    Vec<usize>,
+    waiting: Vec<oneshot::Sender<()>>,
+}
+
+/// What the task of an edit is to do next
+enum Turn {
+    /// send the card, with the edit and the others not saved yet
+    Send(Card),
+    /// wait for the save being sent, and ask again
+    Wait(oneshot::Receiver<()>),
+    /// the edit has been saved by another one
+    Saved,
+}
+
+impl PendingEdits {
+    fn is_empty(&self) -> bool {
+        self.by_card.is_empty()
+    }
+
+    /// Register `change` of the card shown as `shown`, and return the id of this edit
+    fn begin(&mut self, shown: &Card, change: CardChange) -> usize {
+        self.count += 1;
+        let edits = self.by_card.entry(shown.id.clone()).or_insert_with(|| CardEdits {
+            saved:   shown.clone(),
+            pending: vec![],
+            sending: false,
+            done:    vec![],
+            waiting: vec![],
+        });
+        edits.pending.push((self.count, change, false));
+        self.count
+    }
+
+    fn turn(&mut self, card_id: &str, edit: usize) -> Turn {
+        let Some(edits) = self.by_card.get_mut(card_id) else {return Turn::Saved};
+        if let Some(i) = edits.done.iter().position(|e| *e == edit) {
+            edits.done.remove(i);
+            self.clean(card_id);
+            return Turn::Saved
+        }
+        if edits.sending {
+            let (tx, rx) = oneshot::channel();
+            edits.waiting.push(tx);
+            return Turn::Wait(rx)
+        }
+        edits.sending = true;
+        for (_, _, sent) in &mut edits.pending {
+            *sent = true
+        }
+        Turn::Send(edits.current())
+    }
+
+    fn succeed(&mut self, edit: usize, sent: Card) {
+        let Some(edits) = self.by_card.get_mut(&sent.id) else {return};
+        edits.done.extend(edits.pending.iter().filter(|(e, _, sent)| *sent && *e != edit).map(|(e, _, _)| *e));
+        edits.pending.retain(|(_, _, sent)| !sent);
+        edits.saved = sent.clone();
+        edits.end_sending();
+        self.clean(&sent.id)
+    }
+
+    /// The card to show instead after `edit` is rejected, leaving the others
+    /// sent with it to be sent again
+    fn fail(&mut self, card_id: &str, edit: usize) -> Option<Card> {
+        let edits = self.by_card.get_mut(card_id)?;
+        edits.pending.retain(|(e, _, _)| *e != edit);
+        for (_, _, sent) in &mut edits.pending {
+            *sent = false
+        }
+        edits.end_sending();
+        let card = edits.current();
+        self.clean(card_id);
+        Some(card)
+    }
+
+    fn clean(&mut self, card_id: &str) {
+        if self.by_card.get(card_id).is_some_and(|edits| edits.pending.is_empty() && edits.done.is_empty() && !edits.sending) {
+            self.by_card.remove(card_id);
+        }
+    }
+}
+
+impl CardEdits {
+    fn current(&self) -> Card {
+        let mut card = self.saved.clone();
+        for (_, change, _) in &self.pending {
+            change.apply(&mut card)
+        }
+        card
+    }
+
+    fn end_sending(&mut self) {
+        self.sending = false;
+        for waiting in self.waiting.drain(..) {
+            let _ = waiting.send(());
+        }
+    }
+}
@@ -1,73 +1,72 @@
-use ohkami::serde::Serialize;
-use crate::models::SignupResponse;
+pub use client::{new_card_id, new_idempotency_key, Client, Error, RetryState, StatusCode};
 
-pub use reqwest::Error;
+use super::i18n::Messages;
+use yew::Callback;
 
+use std::sync::OnceLock;
 
-pub struct Client(reqwest::Client);
 
-impl Client {
-    const TOKEN_STORAGE_KEY: &'static str = "ohkami-yew-todo-demo-token";
+const TOKEN_STORAGE_KEY: &str = "ohkami-yew-todo-demo-token";
 
-    const ORIGIN: &'static str = {
-        #[cfg(debug_assertions)] {"http://localhost:8787"}
-        #[cfg(not(debug_assertions))] {"https://ohkami-yew-todo.kanarus.workers.dev"}
-    };
-
-    pub async fn new() -> Result<Self, Error> {
-        let token = {
-            let local_storage = web_sys::window().unwrap().local_storage().unwrap().unwrap();
+/// Origin of the API, from the first found of
+///
+/// 1. `<meta name="api-origin" content="...">` in the page ( for staging builds )
+/// 2. `API_ORIGIN` environment variable at build time
+/// 3. the origin of the page itself ( `dist` is served by the Worker in production )
+fn origin() -> &'static str {
+    static ORIGIN: OnceLock<String> = OnceLock::new();
 
-            match local_storage.get(Self::TOKEN_STORAGE_KEY).unwrap() {
-                Some(token) => token,
-                None => {
-                    let SignupResponse { token } = reqwest::Client::new()
-                        .post(format!("{}/signup", Self::ORIGIN)).send().await?.json().await?;
-                    local_storage.set(Self::TOKEN_STORAGE_KEY, &token).unwrap();
-                    token.into()
-                }
-            }
-        };
+    ORIGIN.get_or_init(|| {
+        let window = web_sys::window().unwrap();
 
-        let client = reqwest::ClientBuilder::new()
-            .default_headers(FromIterator::from_iter([(
-                "Authorization".try_into().unwrap(),
-                format!("Bearer {token}").try_into().unwrap()
-            )]))
-            .build().unwrap();
+        let meta = window.document().unwrap()
+            .query_selector(r#"meta[name="api-origin"]"#).unwrap()
+            .and_then(|meta| meta.get_attribute("content"))
+            .filter(|content| !content.is_empty());
 
-        Ok(Self(client))
-    }
+        meta.or_else(|| option_env!("API_ORIGIN").filter(|o| !o.is_empty()).map(String::from))
+            .unwrap_or_else(|| window.location().origin().unwrap())
+            .trim_end_matches('/').into()
+    })
 }
 
-macro_rules! call {
-    ( $( $method:ident & $with_body_method:ident ),* ) => {
-        #[allow(non_snake_case, unused)]
-        impl Client {$(
-            pub async fn $method(&self,
-                path: impl AsRef<str>
-            ) -> Result<reqwest::Response, Error> {
-                self.0.request(
-                    reqwest::Method::$method,
-                    format!("{}{}", Self::ORIGIN, path.as_ref())
-                ).send().await
-            }
+/// Client as the user of the token in local storage, signing up at the first time,
+/// notifying `on_retry` of its retries
+pub async fn client(on_retry: Callback<RetryState>) -> Result<Client, Error> {
+    let local_storage = web_sys::window().unwrap().local_storage().unwrap().unwrap();
 
-            pub async fn $with_body_method<Body: Serialize>(&self,
-                body: Body,
-                path: impl AsRef<str>
-            ) -> Result<reqwest::Response, Error> {
-                self.0.request(
-                    reqwest::Method::$method,
-                    format!("{}{}", Self::ORIGIN, path.as_ref())
-                ).json(&body).send().await
-            }
-        )*}
+    let client = match local_storage.get(TOKEN_STORAGE_KEY).unwrap() {
+        Some(token) => Client::new(origin(), token),
+        None => {
+            let client = Client::signup(origin()).await?;
+            local_storage.set(TOKEN_STORAGE_KEY, client.token()).unwrap();
+            client
+        }
     };
-} call! {
-    GET & GETwith,
-    PUT & PUTwith,
-    POST & POSTwith,
-    PATCH & PATCHwith,
-    DELETE & DELETEwith
+    Ok(client.on_retry(move |state| on_retry.emit(state)))
+}
+
+/// What went wrong in words for the user
+pub fn describe(err: &Error, t: &Messages) -> String {
+    match (err, err.status()) {
+        (Error::Timeout(_), _) => t.error_timeout.into(),
+        (Error::Decode(_),  _) => t.error_decode.into(),
+        (_, None) => t.error_unreachable.into(),
+        (_, Some(status)) => match status.as_u16() {
+            401 => t.error_unauthorized.into(),
+            403 => t.error_forbidden.into(),
+            404 => t.error_not_found.into(),
+            409 => t.error_conflict.into(),
+            413 => t.error_too_large.into(),
+            415 => t.error_unsupported_type.into(),
+            429 => t.error_too_many_requests.into(),
+            500..=599 => (t.error_server)(&status.to_string()),
+            _ => (t.error_unexpected)(&status.to_string()),
+        },
+    }
+}
+
+/// Resolve `path` ( like `Attachment::url` ) into the full URL of the API
+pub fn url(path: impl AsRef<str>) -> String {
+    format!("{}{}", origin(), path.as_ref())
 }
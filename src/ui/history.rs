@@ -0,0 +1,80 @@
+//! Undo / redo history of creating, deleting and editing cards
+//!
+//! Each entry is replayed against the API by `Effects`: its inverse to undo
+//! it, and itself to redo it.
+
+use models::Card;
+
+
+/// Entries older than this are forgotten
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Clone)]
+pub enum Op {
+    /// `card` as created, at `at` of the board
+    Create { at: usize, card: Card },
+    /// `card` as moved to the trash from `at` of the board
+    Delete { at: usize, card: Card },
+    /// The title and the todos changed from `before` to `after`
+    Edit { before: Card, after: Card },
+}
+
+impl Op {
+    pub fn inverse(&self) -> Self {
+        match self.clone() {
+            Self::Create { at, card }    => Self::Delete { at, card },
+            Self::Delete { at, card }    => Self::Create { at, card },
+            Self::Edit { before, after } => Self::Edit { before: after, after: before },
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    count: usize,
+    undo:  Vec<(usize, Op)>,
+    redo:  Vec<(usize, Op)>,
+}
+
+impl History {
+    /// Record `op` just done, and return the id of this entry
+    pub fn record(&mut self, op: Op) -> usize {
+        self.count += 1;
+        self.redo.clear();
+        self.undo.push((self.count, op));
+        if self.undo.len() > MAX_ENTRIES {
+            self.undo.remove(0);
+        }
+        self.count
+    }
+
+    /// The op to replay to undo the latest entry
+    pub fn undo(&mut self) -> Option<Op> {
+        let (id, op) = self.undo.pop()?;
+        let inverse = op.inverse();
+        self.redo.push((id, op));
+        Some(inverse)
+    }
+
+    /// The op to replay to undo the entry `id` ( by Undo of its toast ),
+    /// only while it's the latest : the inverse of an older one may put back
+    /// what the later ones have changed
+    pub fn undo_entry(&mut self, id: usize) -> Option<Op> {
+        if self.undo.last().map(|(i, _)| *i) != Some(id) {
+            return None
+        }
+        self.undo()
+    }
+
+    /// Whether the entry `id` is not undone yet but under later ones
+    pub fn is_buried(&self, id: usize) -> bool {
+        self.undo.iter().rev().skip(1).any(|(i, _)| *i == id)
+    }
+
+    /// The op to replay to redo the latest undone entry
+    pub fn redo(&mut self) -> Option<Op> {
+        let (id, op) = self.redo.pop()?;
+        self.undo.push((id, op.clone()));
+        Some(op)
+    }
+}
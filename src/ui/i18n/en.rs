@@ -0,0 +1,123 @@
+use super::Messages;
+
+
+pub static MESSAGES: Messages = Messages {
+    loading:              "Loading...",
+    views:                "Views",
+    board:                "Board",
+    search:               "Search",
+    archive:              "Archive",
+    trash:                "Trash",
+    settings:             "Settings",
+    back_to_board:        "Back to the board",
+    card_not_found:       "This card doesn't exist, or has been deleted.",
+    page_not_found:       "This page doesn't exist.",
+    search_placeholder:   "Search titles, todos and notes",
+    no_cards_found:       "No cards found.",
+    archive_empty:        "No archived cards.",
+    trash_empty:          "The trash is empty.",
+    terminal_heading:     "Use this account from the terminal",
+    terminal_description: "Log in to it by the `todo` command with a code, which is valid only once:",
+    issue_pairing_code:   "Get a code",
+    pairing_code_expires: |minutes| format!("Expires in {minutes} minutes"),
+    language:             "Language",
+
+    keyboard_shortcuts:   "Keyboard shortcuts",
+    close:                "Close",
+    shortcut_new_card:    "Create a new card",
+    shortcut_search:      "Search cards",
+    shortcut_move_todo:   "Move to the next / previous todo",
+    shortcut_check_todo:  "Check or uncheck the todo",
+    shortcut_edit_todo:   "Edit the todo, or save it and move to the next one",
+    shortcut_cancel:      "Cancel editing, or close this help",
+    shortcut_undo:        "Undo the last change of the cards",
+    shortcut_redo:        "Redo the undone change",
+    shortcut_tab:         "Move to the next button or field",
+    shortcut_help:        "Show or hide this help",
+
+    dismiss:                  "Dismiss",
+    retry:                    "Retry",
+    undo:                     "Undo",
+    undo_outdated:            "Can't undo this: other changes have been made since ( undo them first by Ctrl+Z )",
+    retrying:                 |attempt, max_retries, delay| format!("Connection is unstable. Retrying ({attempt}/{max_retries}) in {delay:.1}s..."),
+    error_timeout:            "the server didn't respond in time",
+    error_decode:             "unexpected response from the server",
+    error_unreachable:        "can't reach the server ( check your connection )",
+    error_unauthorized:       "your session is invalid ( try reloading the page )",
+    error_forbidden:          "it's not yours, or already deleted",
+    error_not_found:          "not found",
+    error_conflict:           "the same action is still in progress",
+    error_too_large:          "too large",
+    error_unsupported_type:   "unsupported type",
+    error_too_many_requests:  "too many requests ( wait a moment )",
+    error_server:             |status| format!("the server failed ( {status} )"),
+    error_unexpected:         |status| format!("unexpected response ( {status} )"),
+
+    sign_up_failed:              "Can't perform sign up",
+    fetch_failed:                "Failed to fetch your TODOs",
+    create_card_failed:          "Failed to create TODO card",
+    create_from_template_failed: "Failed to create TODO card from the template",
+    created_from_template:       "Created a TODO card from the template",
+    duplicate_failed:            "Failed to duplicate this TODO card",
+    duplicated:                  "Duplicated the TODO card",
+    delete_card_failed:          "Failed to delete the TODO card",
+    deleted_card:                "Moved the TODO card to the trash",
+    restore_failed:              "Failed to restore the TODO card",
+    archive_failed:              "Failed to archive the TODO card",
+    archived_card:               "Archived the TODO card",
+    unarchive_failed:            "Failed to put the TODO card back on the board",
+    delete_forever_failed:       "Failed to delete the TODO card for good",
+    update_title_failed:         "Failed to update title",
+    update_todo_failed:          "Failed to update TODO",
+    update_notes_failed:         "Failed to update notes",
+    save_as_template_failed:     "Failed to save this TODO card as a template",
+    saved_as_template:           "Saved as a template",
+    undo_template_failed:        "Failed to undo saving the template",
+    delete_template_failed:      "Failed to delete the template",
+    unsupported_attachment:      |name| format!("Can't attach `{name}`: only images and PDFs are supported"),
+    attachment_too_large:        |name, mb| format!("Can't attach `{name}`: it must be at most {mb} MB"),
+    read_failed:                 |name| format!("Failed to read `{name}`"),
+    attach_failed:               |name| format!("Failed to attach `{name}`"),
+    delete_attachment_failed:    "Failed to delete the attachment",
+    pairing_code_failed:         "Failed to get a code",
+
+    saving:                "Saving",
+    saved:                 "Saved",
+    save_failed:           "Failed to save",
+    remove_attachment:     |name| format!("Remove {name}"),
+    todos:                 "Todos",
+    todo_number:           |number| format!("Todo {number}"),
+    sub_todos_of:          |todo| format!("Sub-todos of {todo}"),
+    notes_of:              |todo| format!("Notes of {todo}"),
+    add_subtodo_to:        |todo| format!("Add a sub-todo to {todo}"),
+    notes_placeholder:     "notes",
+    card_named:            |title| format!("card “{title}”"),
+    untitled_card:         "untitled card",
+    title_of_card:         "Title of the card",
+    open_card:             |card| format!("Open {card}"),
+    duplicate_card:        |card| format!("Duplicate {card}"),
+    save_card_as_template: |card| format!("Save {card} as a template"),
+    attach_to_card:        |card| format!("Attach an image or a PDF to {card}"),
+    delete_card:           |card| format!("Delete {card}"),
+    archive_card:          |card| format!("Archive {card}"),
+    unarchive_card:        |card| format!("Put {card} back on the board"),
+    restore_card:          |card| format!("Restore {card}"),
+    delete_card_forever:   |card| format!("Delete {card} for good"),
+    note:                  "Note",
+    title_of_note:         "Title of the note",
+    cover_notes:           [
+        "A permanent token is issued automatically and saved in localStorage, and it identifies you.",
+        "Just in case, we recommend not entering anything that must be kept secret.",
+    ],
+    repository:            "repository",
+    new_card:              "New card",
+    blank:                 "blank",
+    from_template:         "from template",
+    untitled_template:     "(untitled)",
+    delete_template:       |title| format!("Delete the template {title}"),
+
+    unarchive:             "Unarchive",
+    delete:                "Delete",
+    restore:               "Restore",
+    delete_forever:        "Delete for good",
+};
@@ -0,0 +1,123 @@
+use super::Messages;
+
+
+pub static MESSAGES: Messages = Messages {
+    loading:              "読み込み中...",
+    views:                "表示",
+    board:                "ボード",
+    search:               "検索",
+    archive:              "アーカイブ",
+    trash:                "ゴミ箱",
+    settings:             "設定",
+    back_to_board:        "ボードに戻る",
+    card_not_found:       "このカードは存在しないか、削除されています。",
+    page_not_found:       "このページは存在しません。",
+    search_placeholder:   "タイトル・TODO・メモを検索",
+    no_cards_found:       "カードが見つかりません。",
+    archive_empty:        "アーカイブされたカードはありません。",
+    trash_empty:          "ゴミ箱は空です。",
+    terminal_heading:     "ターミナルからこのアカウントを使う",
+    terminal_description: "`todo` コマンドでコードを使ってログインできます。コードは一度だけ使えます:",
+    issue_pairing_code:   "コードを発行",
+    pairing_code_expires: |minutes| format!("{minutes}分で無効になります"),
+    language:             "言語",
+
+    keyboard_shortcuts:   "キーボードショートカット",
+    close:                "閉じる",
+    shortcut_new_card:    "新しいカードを作成",
+    shortcut_search:      "カードを検索",
+    shortcut_move_todo:   "次 / 前のTODOに移動",
+    shortcut_check_todo:  "TODOのチェックを切り替え",
+    shortcut_edit_todo:   "TODOを編集、または保存して次のTODOに移動",
+    shortcut_cancel:      "編集を取り消す、またはこのヘルプを閉じる",
+    shortcut_undo:        "カードへの直前の変更を元に戻す",
+    shortcut_redo:        "元に戻した変更をやり直す",
+    shortcut_tab:         "次のボタンや入力欄に移動",
+    shortcut_help:        "このヘルプを表示 / 非表示",
+
+    dismiss:                  "閉じる",
+    retry:                    "再試行",
+    undo:                     "元に戻す",
+    undo_outdated:            "この後に他の変更があるため元に戻せません ( 先に Ctrl+Z でそれらを元に戻してください )",
+    retrying:                 |attempt, max_retries, delay| format!("接続が不安定です。{delay:.1}秒後に再試行します ({attempt}/{max_retries})..."),
+    error_timeout:            "サーバーが時間内に応答しませんでした",
+    error_decode:             "サーバーから予期しない応答がありました",
+    error_unreachable:        "サーバーに接続できません ( 接続を確認してください )",
+    error_unauthorized:       "セッションが無効です ( ページを再読み込みしてください )",
+    error_forbidden:          "あなたのものではないか、すでに削除されています",
+    error_not_found:          "見つかりません",
+    error_conflict:           "同じ操作をまだ処理中です",
+    error_too_large:          "大きすぎます",
+    error_unsupported_type:   "対応していない形式です",
+    error_too_many_requests:  "リクエストが多すぎます ( しばらくお待ちください )",
+    error_server:             |status| format!("サーバーでエラーが発生しました ( {status} )"),
+    error_unexpected:         |status| format!("予期しない応答です ( {status} )"),
+
+    sign_up_failed:              "サインアップできません",
+    fetch_failed:                "TODOの取得に失敗しました",
+    create_card_failed:          "TODOカードの作成に失敗しました",
+    create_from_template_failed: "テンプレートからのTODOカードの作成に失敗しました",
+    created_from_template:       "テンプレートからTODOカードを作成しました",
+    duplicate_failed:            "TODOカードの複製に失敗しました",
+    duplicated:                  "TODOカードを複製しました",
+    delete_card_failed:          "TODOカードの削除に失敗しました",
+    deleted_card:                "TODOカードをゴミ箱に移動しました",
+    restore_failed:              "TODOカードの復元に失敗しました",
+    archive_failed:              "TODOカードのアーカイブに失敗しました",
+    archived_card:               "TODOカードをアーカイブしました",
+    unarchive_failed:            "TODOカードをボードに戻せませんでした",
+    delete_forever_failed:       "TODOカードの完全な削除に失敗しました",
+    update_title_failed:         "タイトルの更新に失敗しました",
+    update_todo_failed:          "TODOの更新に失敗しました",
+    update_notes_failed:         "メモの更新に失敗しました",
+    save_as_template_failed:     "TODOカードのテンプレートとしての保存に失敗しました",
+    saved_as_template:           "テンプレートとして保存しました",
+    undo_template_failed:        "テンプレートの保存を元に戻せませんでした",
+    delete_template_failed:      "テンプレートの削除に失敗しました",
+    unsupported_attachment:      |name| format!("`{name}` を添付できません: 添付できるのは画像とPDFのみです"),
+    attachment_too_large:        |name, mb| format!("`{name}` を添付できません: {mb} MB以下にしてください"),
+    read_failed:                 |name| format!("`{name}` の読み込みに失敗しました"),
+    attach_failed:               |name| format!("`{name}` の添付に失敗しました"),
+    delete_attachment_failed:    "添付ファイルの削除に失敗しました",
+    pairing_code_failed:         "コードの発行に失敗しました",
+
+    saving:                "保存中",
+    saved:                 "保存しました",
+    save_failed:           "保存に失敗しました",
+    remove_attachment:     |name| format!("{name} を削除"),
+    todos:                 "TODO",
+    todo_number:           |number| format!("TODO {number}"),
+    sub_todos_of:          |todo| format!("{todo} のサブTODO"),
+    notes_of:              |todo| format!("{todo} のメモ"),
+    add_subtodo_to:        |todo| format!("{todo} にサブTODOを追加"),
+    notes_placeholder:     "メモ",
+    card_named:            |title| format!("カード「{title}」"),
+    untitled_card:         "無題のカード",
+    title_of_card:         "カードのタイトル",
+    open_card:             |card| format!("{card}を開く"),
+    duplicate_card:        |card| format!("{card}を複製"),
+    save_card_as_template: |card| format!("{card}をテンプレートとして保存"),
+    attach_to_card:        |card| format!("{card}に画像やPDFを添付"),
+    delete_card:           |card| format!("{card}を削除"),
+    archive_card:          |card| format!("{card}をアーカイブ"),
+    unarchive_card:        |card| format!("{card}をボードに戻す"),
+    restore_card:          |card| format!("{card}を復元"),
+    delete_card_forever:   |card| format!("{card}を完全に削除"),
+    note:                  "ノート",
+    title_of_note:         "ノートのタイトル",
+    cover_notes:           [
+        "自動的に永久トークンを発行してlocalStorageに保存し、それをもってユーザーを識別しています。",
+        "念のため、知られてはいけない情報は入力しないことをおすすめします。",
+    ],
+    repository:            "リポジトリ",
+    new_card:              "新しいカード",
+    blank:                 "空のカード",
+    from_template:         "テンプレートから",
+    untitled_template:     "(無題)",
+    delete_template:       |title| format!("テンプレート {title} を削除"),
+
+    unarchive:             "ボードに戻す",
+    delete:                "削除",
+    restore:               "復元",
+    delete_forever:        "完全に削除",
+};
@@ -0,0 +1,240 @@
+//! Messages of the front in the locale chosen in Settings, or detected from `navigator.language`
+//!
+//! Every text shown to the user is taken from `Messages` by `use_messages`
+//! ( or `I18n::messages` outside the components ), with a catalog per locale.
+
+mod en;
+mod ja;
+
+pub use models::Locale;
+
+use yew::prelude::*;
+
+
+const LOCALE_STORAGE_KEY: &str = "ohkami-yew-todo-demo-locale";
+
+/// Catalog of the messages, the ones taking arguments as functions
+pub struct Messages {
+    /* the header and the views */
+    pub loading:              &'static str,
+    pub views:                &'static str,
+    pub board:                &'static str,
+    pub search:               &'static str,
+    pub archive:              &'static str,
+    pub trash:                &'static str,
+    pub settings:             &'static str,
+    pub back_to_board:        &'static str,
+    pub card_not_found:       &'static str,
+    pub page_not_found:       &'static str,
+    pub search_placeholder:   &'static str,
+    pub no_cards_found:       &'static str,
+    pub archive_empty:        &'static str,
+    pub trash_empty:          &'static str,
+    pub terminal_heading:     &'static str,
+    pub terminal_description: &'static str,
+    pub issue_pairing_code:   &'static str,
+    /// the minutes
+    pub pairing_code_expires: fn(u64) -> String,
+    pub language:             &'static str,
+
+    /* keyboard shortcuts */
+    pub keyboard_shortcuts:   &'static str,
+    pub close:                &'static str,
+    pub shortcut_new_card:    &'static str,
+    pub shortcut_search:      &'static str,
+    pub shortcut_move_todo:   &'static str,
+    pub shortcut_check_todo:  &'static str,
+    pub shortcut_edit_todo:   &'static str,
+    pub shortcut_cancel:      &'static str,
+    pub shortcut_undo:        &'static str,
+    pub shortcut_redo:        &'static str,
+    pub shortcut_tab:         &'static str,
+    pub shortcut_help:        &'static str,
+
+    /* toasts, and the failures of requests */
+    pub dismiss:                  &'static str,
+    pub retry:                    &'static str,
+    pub undo:                     &'static str,
+    /// Undo of a toast clicked after another change
+    pub undo_outdated:            &'static str,
+    /// `attempt`, `max_retries` and the delay in seconds
+    pub retrying:                 fn(u32, u32, f32) -> String,
+    pub error_timeout:            &'static str,
+    pub error_decode:             &'static str,
+    pub error_unreachable:        &'static str,
+    pub error_unauthorized:       &'static str,
+    pub error_forbidden:          &'static str,
+    pub error_not_found:          &'static str,
+    pub error_conflict:           &'static str,
+    pub error_too_large:          &'static str,
+    pub error_unsupported_type:   &'static str,
+    pub error_too_many_requests:  &'static str,
+    /// the status like `503 Service Unavailable`
+    pub error_server:             fn(&str) -> String,
+    /// the status like `418 I'm a teapot`
+    pub error_unexpected:         fn(&str) -> String,
+
+    /* the actions on the board */
+    pub sign_up_failed:              &'static str,
+    pub fetch_failed:                &'static str,
+    pub create_card_failed:          &'static str,
+    pub create_from_template_failed: &'static str,
+    pub created_from_template:       &'static str,
+    pub duplicate_failed:            &'static str,
+    pub duplicated:                  &'static str,
+    pub delete_card_failed:          &'static str,
+    pub deleted_card:                &'static str,
+    pub restore_failed:              &'static str,
+    pub archive_failed:              &'static str,
+    pub archived_card:               &'static str,
+    pub unarchive_failed:            &'static str,
+    pub delete_forever_failed:       &'static str,
+    pub update_title_failed:         &'static str,
+    pub update_todo_failed:          &'static str,
+    pub update_notes_failed:         &'static str,
+    pub save_as_template_failed:     &'static str,
+    pub saved_as_template:           &'static str,
+    pub undo_template_failed:        &'static str,
+    pub delete_template_failed:      &'static str,
+    /// the name of the file
+    pub unsupported_attachment:      fn(&str) -> String,
+    /// the name of the file and the max size in MB
+    pub attachment_too_large:        fn(&str, usize) -> String,
+    /// the name of the file
+    pub read_failed:                 fn(&str) -> String,
+    /// the name of the file
+    pub attach_failed:               fn(&str) -> String,
+    pub delete_attachment_failed:    &'static str,
+    pub pairing_code_failed:         &'static str,
+
+    /* the cards */
+    pub saving:                &'static str,
+    pub saved:                 &'static str,
+    pub save_failed:           &'static str,
+    /// the name of the file
+    pub remove_attachment:     fn(&str) -> String,
+    pub todos:                 &'static str,
+    /// the number like `1.2`
+    pub todo_number:           fn(&str) -> String,
+    /// the content or `todo_number` of the todo, and so are the followings
+    pub sub_todos_of:          fn(&str) -> String,
+    pub notes_of:              fn(&str) -> String,
+    pub add_subtodo_to:        fn(&str) -> String,
+    pub notes_placeholder:     &'static str,
+    /// the title
+    pub card_named:            fn(&str) -> String,
+    pub untitled_card:         &'static str,
+    pub title_of_card:         &'static str,
+    /// `card_named` or `untitled_card`, and so are the followings
+    pub open_card:             fn(&str) -> String,
+    pub duplicate_card:        fn(&str) -> String,
+    pub save_card_as_template: fn(&str) -> String,
+    pub attach_to_card:        fn(&str) -> String,
+    pub delete_card:           fn(&str) -> String,
+    pub archive_card:          fn(&str) -> String,
+    pub unarchive_card:        fn(&str) -> String,
+    pub restore_card:          fn(&str) -> String,
+    pub delete_card_forever:   fn(&str) -> String,
+    pub note:                  &'static str,
+    pub title_of_note:         &'static str,
+    pub cover_notes:           [&'static str; 2],
+    pub repository:            &'static str,
+    pub new_card:              &'static str,
+    pub blank:                 &'static str,
+    pub from_template:         &'static str,
+    pub untitled_template:     &'static str,
+    /// the title, or `untitled_template`
+    pub delete_template:       fn(&str) -> String,
+
+    /* the cards on the shelves, with the labels like `unarchive_card` for screen readers */
+    pub unarchive:             &'static str,
+    pub delete:                &'static str,
+    pub restore:               &'static str,
+    pub delete_forever:        &'static str,
+}
+
+impl Messages {
+    pub fn of(locale: Locale) -> &'static Self {
+        match locale {
+            Locale::En => &en::MESSAGES,
+            Locale::Ja => &ja::MESSAGES,
+        }
+    }
+}
+
+
+/// The locale by `I18nProvider`, and to change it
+#[derive(Clone, PartialEq)]
+pub struct I18n {
+    pub locale: Locale,
+    set_locale: Callback<Locale>,
+}
+
+impl I18n {
+    pub fn messages(&self) -> &'static Messages {
+        Messages::of(self.locale)
+    }
+
+    /// Change the locale of the page, and remember it for the next visits
+    pub fn set_locale(&self, locale: Locale) {
+        self.set_locale.emit(locale)
+    }
+}
+
+/// The locale under `I18nProvider`, or English without it
+/// ( like the components rendered alone by tests/a11y.rs )
+#[hook]
+pub fn use_i18n() -> I18n {
+    use_context::<I18n>().unwrap_or_else(|| I18n {
+        locale:     Locale::default(),
+        set_locale: Callback::noop(),
+    })
+}
+
+#[hook]
+pub fn use_messages() -> &'static Messages {
+    use_i18n().messages()
+}
+
+
+#[derive(Properties, PartialEq)]
+pub struct I18nProviderProps {
+    pub children: Html,
+}
+
+#[function_component]
+pub fn I18nProvider(I18nProviderProps { children }: &I18nProviderProps) -> Html {
+    let locale = use_state(initial_locale);
+
+    /* for screen readers and the fonts */
+    use_effect_with(*locale, |locale| {
+        let root = web_sys::window().and_then(|w| w.document()).and_then(|d| d.document_element());
+        if let Some(root) = root {
+            let _ = root.set_attribute("lang", locale.tag());
+        }
+    });
+
+    let set_locale = use_callback(locale.clone(), |chosen: Locale, locale| {
+        if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+            let _ = storage.set(LOCALE_STORAGE_KEY, chosen.tag());
+        }
+        locale.set(chosen)
+    });
+
+    html!(
+        <ContextProvider<I18n> context={I18n { locale: *locale, set_locale }}>
+            {children.clone()}
+        </ContextProvider<I18n>>
+    )
+}
+
+/// The one chosen before, or the one of `navigator.language` if supported
+fn initial_locale() -> Locale {
+    let Some(window) = web_sys::window() else {return Locale::default()};
+
+    let chosen = window.local_storage().ok().flatten()
+        .and_then(|storage| storage.get(LOCALE_STORAGE_KEY).ok().flatten());
+    chosen.as_deref().and_then(Locale::from_tag)
+        .or_else(|| window.navigator().language().as_deref().and_then(Locale::from_tag))
+        .unwrap_or_default()
+}
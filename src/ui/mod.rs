@@ -1,30 +1,67 @@
 mod utils;
 mod fetch;
-mod components;
+mod toasts;
+mod board;
+mod effects;
+mod history;
+mod routes;
+mod i18n;
+/* `pub(crate)` for `tests/a11y.rs` */
+pub(crate) mod shortcuts;
+pub(crate) mod components;
 
 use fetch::Client;
-use utils::{set_state, report_error};
-use components::{FrontCoverCard, PlusCard, TodoCard, TodoCardProps};
+use utils::reload_page;
+use toasts::{use_toaster, Toast, ToastProvider};
+use board::{Board, CardChange, Shelf, TodoEdit, TodoId};
+use effects::{use_effects, use_flush_on_leave, Effects};
+use routes::{search_query, Route};
+use i18n::{use_i18n, use_messages, I18nProvider, Locale, Messages};
+use shortcuts::{move_todo_focus, use_shortcuts, Shortcut, ShortcutsHelp};
+use components::{FrontCoverCard, PlusCard, RetryBanner, ShelvedCard, TodoCard};
 
-use crate::models::{Card, CreateCardRequest, CreateCardResponse, Todo, UpdateCard};
+use models::{Card, PairingCodeResponse};
 use yew::prelude::*;
 use yew::suspense::{use_future, Suspense};
+use yew_router::prelude::*;
 use std::rc::Rc;
 
 
 #[function_component]
 pub fn App() -> Html {
+    html!(
+        <I18nProvider>
+            <ToastProvider>
+                <BrowserRouter>
+                    <Page />
+                </BrowserRouter>
+            </ToastProvider>
+        </I18nProvider>
+    )
+}
+
+#[function_component]
+fn Page() -> Html {
+    let t = use_messages();
+    let retry = use_state(|| None);
+    let on_retry = use_callback(retry.clone(), |state, retry| retry.set(match state {
+        fetch::RetryState::Finished => None,
+        waiting                     => Some(waiting),
+    }));
+
     html! (
         <main class="h-full flex flex-col">
+            <RetryBanner state={(*retry).clone()}/>
             <header class="basis-12 mt-12">
                 <h1 class="m-0 w-full h-12 text-center text-neutral-800 underline underline-offset-8">
                     {"Ohkami×Yew TODO Demo"}
                 </h1>
+                <Menu />
             </header>
             <div class="grow flex items-center">
                 <div class="overflow-hidden">
-                    <Suspense fallback={html!(<p class="w-screen text-center">{"Loading..."}</p>)}>
-                        <Main />
+                    <Suspense fallback={html!(<p class="w-screen text-center">{t.loading}</p>)}>
+                        <Main {on_retry}/>
                     </Suspense>
                 </div>
             </div>
@@ -33,189 +70,298 @@ pub fn App() -> Html {
 }
 
 #[function_component]
-fn Main() -> HtmlResult {
-    let client = match &*use_future(|| async {Client::new().await.map(Rc::new)})? {
+fn Menu() -> Html {
+    let (route, t) = (use_route::<Route>(), use_messages());
+
+    html!(
+        <nav class="mt-2 flex justify-center space-x-4 text-sm" aria-label={t.views}>
+            {for Route::MENU.into_iter().map(|(to, label)| {
+                let class = if route.as_ref() == Some(&to) {"text-neutral-800 underline"} else {"text-neutral-500 no-underline"};
+                html!(<Link<Route> {to} classes={class}>{label(t)}</Link<Route>>)
+            })}
+        </nav>
+    )
+}
+
+#[derive(Properties, PartialEq)]
+struct MainProps {
+    on_retry: Callback<fetch::RetryState>,
+}
+
+#[function_component]
+fn Main(MainProps { on_retry }: &MainProps) -> HtmlResult {
+    let (on_retry, toaster, t) = (on_retry.clone(), use_toaster(), use_messages());
+    let client = match &*use_future(|| async move {
+        fetch::client(on_retry).await.map(Rc::new).inspect_err(|err| toaster.show(
+            Toast::error(format!("{}: {}", t.sign_up_failed, fetch::describe(err, t)))
+                .with_action(t.retry, reload_page)
+        ))
+    })? {
         Ok(client) => client.clone(),
-        Err(err)   => {
-            report_error(format!("Can't perform sign up: {err}"));
-            return Ok(html!(/* empty */))
-        }
+        Err(_)     => return Ok(html!(/* empty */)),
     };
 
     Ok(html!(
-        <Suspense fallback={html!(<p class="w-screen text-center">{"Loading..."}</p>)}>
-            <TodoCardList client={client.clone()}/>
+        <Suspense fallback={html!(<p class="w-screen text-center">{t.loading}</p>)}>
+            <Workspace client={client.clone()}/>
         </Suspense>
     ))
 }
 
 
 #[derive(Properties)]
-struct TodoCardListProps {
+struct WorkspaceProps {
     client: Rc<Client>,
 }
-impl PartialEq for TodoCardListProps {
+impl PartialEq for WorkspaceProps {
     fn eq(&self, other: &Self) -> bool {
         Rc::ptr_eq(&self.client, &other.client)
     }
 }
 
+/// The view of the route, on the board loaded once for all the routes
 #[function_component]
-fn TodoCardList(TodoCardListProps { client }: &TodoCardListProps) -> HtmlResult {
-    let cards = use_state(|| vec![]);
-
-    if use_future(|| {
-        let (client, cards) = (client.clone(), cards.clone());
-        async move {
-            let fetched_cards: Vec<Card> = client.GET("/api/cards").await?.json().await?;
-            cards.set(fetched_cards);
-            Result::<(), fetch::Error>::Ok(())
-        }
-    })?.is_err() {
-        report_error("Failed to fetch your TODOs");
-    }
+fn Workspace(WorkspaceProps { client }: &WorkspaceProps) -> HtmlResult {
+    let board   = use_reducer(Board::default);
+    let effects = use_effects(client, &board);
+    let i18n    = use_i18n();
+    let t       = i18n.messages();
 
-    let todo_props = cards.iter().cloned().enumerate().map(|(i, bind)| TodoCardProps {
-        bind,
-
-        on_click_delete: Callback::from({
-            let (client, cards) = (client.clone(), cards.clone());
-            move |_| wasm_bindgen_futures::spawn_local({
-                let (client, cards) = (client.clone(), cards.clone());
-                async move {
-                    let Card { id, .. } = cards[i].clone();
-                    match client.DELETE(format!("/api/cards/{id}")).await {
-                        Err(_) => report_error("Failed to delete this TODO"),
-                        Ok(_)  => set_state(&cards, |cs| {cs.remove(i);})
-                    }
-                }
-            })
-        }),
-        on_edit_title: Callback::from({
-            let (client, cards) = (client.clone(), cards.clone());
-            move |new_title: String| wasm_bindgen_futures::spawn_local({
-                let (client, cards) = (client.clone(), cards.clone());
-
-                set_state(&cards, |cs| cs[i].title = new_title.clone());
-
-                async move {
-                    let Card { id, todos, title:_ } = cards[i].clone();
-                    if let Err(err) = client.PUTwith(UpdateCard {
-                        todos,
-                        title: new_title
-                    }, format!("/api/cards/{id}")).await {
-                        report_error(&format!("Failed to update title: {err}"));
-                        set_state(&cards, |_| (/* stay */));
-                    }
-                }
-            })
-        }),
-        on_check_todo_by: std::array::from_fn(|j| Callback::from({
-            let (client, cards) = (client.clone(), cards.clone());
-            move |_| wasm_bindgen_futures::spawn_local({
-                let (client, cards) = (client.clone(), cards.clone());
-
-                let new_todos: [_; Card::N_TODOS] = std::array::from_fn(|k| if k == j {
-                    let mut current = cards[i].todos[k].clone();
-                    current.completed = !current.completed;
-                    current
-                } else {
-                    cards[i].todos[k].clone()
-                });
-
-                set_state(&cards, |cs| cs[i].todos = new_todos.clone());
-
-                async move {
-                    let Card { id, title, todos:_ } = cards[i].clone();
-                    if let Err(err) = client.PUTwith(UpdateCard {
-                        title,
-                        todos: new_todos
-                    }, format!("/api/cards/{id}")).await {
-                        report_error(&format!("Failed to update TODO: {err}"));
-                        set_state(&cards, |_| (/* stay */));
-                    }
+    use_future(|| effects.clone().load())?;
+    use_flush_on_leave(&effects);
+
+    let (route, location, navigator) = (use_route::<Route>(), use_location(), use_navigator());
+    let open = |id: &str| navigator.clone().map(|navigator| {
+        let id = id.to_owned();
+        Callback::from(move |()| navigator.push(&Route::Card { id: id.clone() }))
+    });
+
+    let (help_open, search_input) = (use_state(|| false), use_node_ref());
+    let pairing_code = use_state(|| None);
+    use_effect_with(route.clone(), {
+        let search_input = search_input.clone();
+        move |route| if *route == Some(Route::Search) {
+            if let Some(input) = search_input.cast::<web_sys::HtmlElement>() {
+                let _ = input.focus();
+            }
+        }
+    });
+    use_shortcuts({
+        let (effects, route, navigator, help_open, search_input) = (effects.clone(), route.clone(), navigator.clone(), help_open.clone(), search_input.clone());
+        Callback::from(move |shortcut| match shortcut {
+            Shortcut::NewCard => {
+                /* to be shown where it's created */
+                if route != Some(Route::Board) {
+                    if let Some(navigator) = &navigator {navigator.push(&Route::Board)}
                 }
-            })
-        })),
-        on_edit_todo_by: std::array::from_fn(|j| Callback::from({
-            let (client, cards) = (client.clone(), cards.clone());
-            move |new_content: String| wasm_bindgen_futures::spawn_local({
-                let (client, cards) = (client.clone(), cards.clone());
-                
-                let new_todos: [_; Card::N_TODOS] = std::array::from_fn(|k| if k == j {
-                    let mut current = cards[i].todos[k].clone();
-                    current.content = new_content.clone();
-                    current
-                } else {
-                    cards[i].todos[k].clone()
-                });
-
-                set_state(&cards, |cs| cs[i].todos = new_todos.clone());
-
-                async move {
-                    let Card { id, title, todos:_ } = cards[i].clone();
-                    if let Err(err) = client.PUTwith(UpdateCard {
-                        title,
-                        todos: new_todos
-                    }, format!("/api/cards/{id}")).await {
-                        report_error(&format!("Failed to update TODO: {err}"));
-                        set_state(&cards, |_| (/* stay */));
-                    }
+                effects.create_blank_card()
+            }
+            Shortcut::Search => match (&route, &navigator) {
+                (Some(Route::Search), _) => if let Some(input) = search_input.cast::<web_sys::HtmlElement>() {
+                    let _ = input.focus();
                 }
-            })
-        })),
+                (_, Some(navigator)) => navigator.push(&Route::Search),
+                (_, None) => (),
+            }
+            Shortcut::NextTodo     => move_todo_focus(true),
+            Shortcut::PreviousTodo => move_todo_focus(false),
+            Shortcut::Undo         => effects.undo(),
+            Shortcut::Redo         => effects.redo(),
+            Shortcut::ToggleHelp   => help_open.set(!*help_open),
+            Shortcut::CloseHelp    => help_open.set(false),
+        })
     });
 
-    let handle_click_plus = Callback::from({
-        let (client, cards) = (client.clone(), cards.clone());
-        move |_| wasm_bindgen_futures::spawn_local({
-            let (client, cards) = (client.clone(), cards.clone());
-
-            set_state(&cards, |cs| cs.push(Card {
-                id:    String::new(),
-                title: String::new(),
-                todos: std::array::from_fn(|_| Todo {
-                    content:   String::new(),
-                    completed: false,
-                }),
-            }));
+    let view = match route.unwrap_or(Route::NotFound) {
+        Route::Board => html!(
+            <div class="m-0 px-6 space-x-4 overflow-x-scroll overflow-y-hidden flex">
+                <FrontCoverCard />
+                {for board.cards.iter().map(|card| todo_card(&effects, &board, card, open(&card.id)))}
+                <PlusCard
+                    templates={board.templates.clone()}
+                    on_click_blank={effects.callback(|e, ()| e.create_blank_card())}
+                    on_click_template={effects.callback(|e, template_id: String| e.create_card_from_template(&template_id))}
+                    on_delete_template={effects.callback(|e, template_id: String| e.delete_template(&template_id))}
+                />
+            </div>
+        ),
+
+        Route::Card { id } => match board.card(&id) {
+            Some(card) => html!(
+                <div class="m-0 px-6 flex justify-center">
+                    {todo_card(&effects, &board, card, None)}
+                </div>
+            ),
+            None => notice(t, t.card_not_found),
+        },
 
-            async move {
-                match async {Result::<_, fetch::Error>::Ok(client
-                    .POSTwith(CreateCardRequest::empty(), "/api/cards").await?
-                    .json().await?
-                )}.await {
-                    Ok(CreateCardResponse { id }) => {
-                        set_state(&cards, |cs| cs.push(Card {
-                            id,
-                            title: String::new(),
-                            todos: std::array::from_fn(|_| Todo {
-                                content:   String::new(),
-                                completed: false,
-                            }),
-                        }))
+        Route::Search => {
+            let query = location.as_ref().map(search_query).unwrap_or_default();
+            let on_input = navigator.clone().map(|navigator| Callback::from(move |e: InputEvent| {
+                use web_sys::{HtmlInputElement, wasm_bindgen::JsCast};
+                let q = e.target().unwrap().dyn_into::<HtmlInputElement>().unwrap().value();
+                let _ = navigator.replace_with_query(&Route::Search, &[("q", q)]);
+            }));
+            let found = board.search(&query).collect::<Vec<_>>();
+            html!(
+                <div class="flex flex-col items-center space-y-4">
+                    <input
+                        ref={search_input.clone()}
+                        class="w-72 px-3 py-1 rounded-md border border-solid border-neutral-300 text-base outline-none"
+                        type="search"
+                        aria-label={t.search}
+                        placeholder={t.search_placeholder}
+                        value={query.clone()}
+                        oninput={on_input}
+                    />
+                    if !query.trim().is_empty() && found.is_empty() {
+                        <p class="m-0 text-neutral-500">{t.no_cards_found}</p>
                     }
-                    Err(_) => {
-                        report_error("Failed to create TODO card");
-                        set_state(&cards, |_| (/* stay */));
+                    <div class="m-0 px-6 space-x-4 overflow-x-scroll overflow-y-hidden flex">
+                        {for found.into_iter().map(|card| todo_card(&effects, &board, card, open(&card.id)))}
+                    </div>
+                </div>
+            )
+        }
+
+        Route::Archive => shelf(t, &effects, &board, Shelf::Archive),
+        Route::Trash => shelf(t, &effects, &board, Shelf::Trash),
+
+        Route::Settings => html!(
+            <div class="w-screen flex justify-center">
+                <section class="w-96 text-neutral-800">
+                    <h2 class="text-lg">{t.language}</h2>
+                    <select
+                        class="px-2 py-1 rounded-md border border-solid border-neutral-300 bg-white text-sm outline-none focus-visible:ring-2 focus-visible:ring-sky-400"
+                        aria-label={t.language}
+                        onchange={{let i18n = i18n.clone(); move |e: Event| {
+                            use web_sys::{HtmlSelectElement, wasm_bindgen::JsCast};
+                            let tag = e.target().unwrap().dyn_into::<HtmlSelectElement>().unwrap().value();
+                            if let Some(locale) = Locale::from_tag(&tag) {i18n.set_locale(locale)}
+                        }}}
+                    >
+                        {for Locale::ALL.into_iter().map(|locale| html!(
+                            <option value={locale.tag()} lang={locale.tag()} selected={locale == i18n.locale}>{locale.name()}</option>
+                        ))}
+                    </select>
+
+                    <h2 class="text-lg">{t.terminal_heading}</h2>
+                    <p class="text-sm">{t.terminal_description}</p>
+                    if let Some(PairingCodeResponse { code, expires_in }) = &*pairing_code {
+                        <code class="block mt-2 p-2 rounded-md bg-neutral-100 text-xs break-all">
+                            {format!("todo login --code {code}")}
+                        </code>
+                        <p class="text-xs text-neutral-500">{(t.pairing_code_expires)(expires_in / 60)}</p>
                     }
-                }
-            }
-        })
+                    <button
+                        type="button"
+                        class="px-2 py-1 rounded-md border border-solid border-neutral-300 bg-white cursor-pointer text-sm outline-none focus-visible:ring-2 focus-visible:ring-sky-400"
+                        onclick={{let (effects, pairing_code) = (effects.clone(), pairing_code.clone()); move |_| {
+                            let pairing_code = pairing_code.clone();
+                            effects.issue_pairing_code(Callback::from(move |issued| pairing_code.set(Some(issued))))
+                        }}}
+                    >
+                        {t.issue_pairing_code}
+                    </button>
+                </section>
+            </div>
+        ),
+
+        Route::NotFound => notice(t, t.page_not_found),
+    };
+
+    Ok(html!(<>
+        {view}
+        <button
+            type="button"
+            class="fixed bottom-4 left-4 w-8 h-8 rounded-full cursor-pointer border border-solid border-neutral-300 bg-neutral-100 text-neutral-500 outline-none focus-visible:ring-2 focus-visible:ring-sky-400"
+            aria-label={t.keyboard_shortcuts}
+            title={format!("{} (?)", t.keyboard_shortcuts)}
+            onclick={{let help_open = help_open.clone(); move |_| help_open.set(true)}}
+        >
+            {"?"}
+        </button>
+        <ShortcutsHelp
+            open={*help_open}
+            on_close={{let help_open = help_open.clone(); Callback::from(move |()| help_open.set(false))}}
+        />
+    </>))
+}
+
+/// `TodoCard` of `card` on the board, opened alone by `on_click_open` if given
+fn todo_card(effects: &Effects, board: &Board, card: &Rc<Card>, on_click_open: Option<Callback<()>>) -> Html {
+    let id = &card.id;
+    let on_todo = |index: usize, edit: fn(String) -> TodoEdit| effects.callback({
+        let id = id.clone();
+        move |e, (path, value): (Vec<usize>, String)| e.change_card(&id, CardChange::EditTodo { todo: TodoId { index, path }, edit: edit(value) })
     });
 
-    Ok(html! {
-        <div class="m-0 px-6 space-x-4 overflow-x-scroll overflow-y-hidden flex">
-            <FrontCoverCard />
-            {for todo_props.map(|p| html!(
-                <TodoCard bind={p.bind}
-                    on_click_delete={p.on_click_delete}
-                    on_edit_title={p.on_edit_title}
-                    on_check_todo_by={p.on_check_todo_by}
-                    on_edit_todo_by={p.on_edit_todo_by}
-                />
-            ))}
-            <PlusCard on_click={handle_click_plus} />
+    html!(
+        <TodoCard key={id.clone()} bind={card.clone()} pending={board.is_pending(id)} saves={board.saves(id)}
+            {on_click_open}
+            on_click_archive={effects.callback({let id = id.clone(); move |e, ()| e.archive_card(&id)})}
+            on_click_delete={effects.callback({let id = id.clone(); move |e, ()| e.delete_card(&id)})}
+            on_click_duplicate={effects.callback({let id = id.clone(); move |e, ()| e.duplicate_card(&id)})}
+            on_click_save_as_template={effects.callback({let id = id.clone(); move |e, ()| e.save_card_as_template(&id)})}
+            on_upload_attachment={effects.callback({let id = id.clone(); move |e, file| e.upload_attachment(&id, file)})}
+            on_delete_attachment={effects.callback({let id = id.clone(); move |e, attachment_id| e.delete_attachment(&id, attachment_id)})}
+            on_edit_title={effects.callback({let id = id.clone(); move |e, title| e.change_card(&id, CardChange::SetTitle(title))})}
+            on_check_todo_by={std::array::from_fn(|index| effects.callback({
+                let id = id.clone();
+                move |e, path| e.change_card(&id, CardChange::EditTodo { todo: TodoId { index, path }, edit: TodoEdit::Toggle })
+            }))}
+            on_edit_todo_by={std::array::from_fn(|index| on_todo(index, TodoEdit::SetContent))}
+            on_edit_notes_by={std::array::from_fn(|index| on_todo(index, TodoEdit::SetNotes))}
+            on_add_subtodo_by={std::array::from_fn(|index| effects.callback({
+                let id = id.clone();
+                move |e, path| e.change_card(&id, CardChange::EditTodo { todo: TodoId { index, path }, edit: TodoEdit::AddSubtodo })
+            }))}
+        />
+    )
+}
+
+/// The list of the cards on `shelf`, or the notice that it's empty
+fn shelf(t: &Messages, effects: &Effects, board: &Board, shelf: Shelf) -> Html {
+    let cards = board.shelf(shelf);
+    if cards.is_empty() {
+        return notice(t, match shelf {
+            Shelf::Archive => t.archive_empty,
+            Shelf::Trash   => t.trash_empty,
+        })
+    }
+
+    html!(
+        <div class="w-screen flex justify-center">
+            <ul class="w-96 m-0 p-0 space-y-2" aria-label={match shelf {Shelf::Archive => t.archive, Shelf::Trash => t.trash}}>
+                {for cards.iter().map(|card| {
+                    let id = card.id.clone();
+                    let (on_click_restore, on_click_delete) = match shelf {
+                        Shelf::Archive => (
+                            effects.callback({let id = id.clone(); move |e, ()| e.unarchive_card(&id)}),
+                            effects.callback({let id = id.clone(); move |e, ()| e.trash_archived_card(&id)}),
+                        ),
+                        Shelf::Trash => (
+                            effects.callback({let id = id.clone(); move |e, ()| e.restore_trashed_card(&id)}),
+                            effects.callback({let id = id.clone(); move |e, ()| e.delete_card_forever(&id)}),
+                        ),
+                    };
+                    html!(
+                        <ShelvedCard key={id} bind={card.clone()} in_trash={shelf == Shelf::Trash}
+                            {on_click_restore}
+                            {on_click_delete}
+                        />
+                    )
+                })}
+            </ul>
         </div>
-    })
+    )
+}
+
+fn notice(t: &Messages, message: &'static str) -> Html {
+    html!(
+        <div class="w-screen flex flex-col items-center space-y-2 text-neutral-800">
+            <p class="m-0">{message}</p>
+            <Link<Route> to={Route::Board} classes="text-sm text-neutral-500">{t.back_to_board}</Link<Route>>
+        </div>
+    )
 }
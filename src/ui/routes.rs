@@ -0,0 +1,49 @@
+//! Paths of the views, for bookmarking and linking them
+//!
+//! The server answers `index.html` to these paths ( see `assets` of the server ).
+
+use super::i18n::Messages;
+use models::ID;
+use yew_router::prelude::*;
+
+
+#[derive(Clone, PartialEq, Routable)]
+pub enum Route {
+    #[at("/")]
+    Board,
+    #[at("/cards/:id")]
+    Card { id: ID },
+    /// with `?q=`
+    #[at("/search")]
+    Search,
+    #[at("/archive")]
+    Archive,
+    #[at("/trash")]
+    Trash,
+    #[at("/settings")]
+    Settings,
+    #[not_found]
+    #[at("/404")]
+    NotFound,
+}
+
+/// Text of a link in the locale
+type Label = fn(&Messages) -> &'static str;
+
+impl Route {
+    /// Links in the header, with their labels
+    pub const MENU: [(Self, Label); 5] = [
+        (Self::Board,    |t| t.board),
+        (Self::Search,   |t| t.search),
+        (Self::Archive,  |t| t.archive),
+        (Self::Trash,    |t| t.trash),
+        (Self::Settings, |t| t.settings),
+    ];
+}
+
+/// `q` of the query of the current location
+pub fn search_query(location: &Location) -> String {
+    web_sys::UrlSearchParams::new_with_str(location.query_str()).ok()
+        .and_then(|params| params.get("q"))
+        .unwrap_or_default()
+}
@@ -0,0 +1,183 @@
+//! Keyboard shortcuts on the whole page, and the overlay listing them
+//!
+//! The ones on a todo ( `x`, `Enter` and `Esc` ) are handled by the row of
+//! the todo itself, marked by `data-todo` to be moved through by `j` / `k`.
+
+use super::i18n::{use_messages, Messages};
+use yew::prelude::*;
+use web_sys::{Element, HtmlElement, KeyboardEvent};
+use web_sys::wasm_bindgen::{closure::Closure, JsCast};
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Shortcut {
+    NewCard,
+    Search,
+    NextTodo,
+    PreviousTodo,
+    Undo,
+    Redo,
+    ToggleHelp,
+    CloseHelp,
+}
+
+impl Shortcut {
+    /// Keys and what they do, in the help overlay
+    pub fn help(t: &Messages) -> [(&'static str, &'static str); 10] {
+        [
+            ("n",            t.shortcut_new_card),
+            ("/",            t.shortcut_search),
+            ("j / k",        t.shortcut_move_todo),
+            ("x",            t.shortcut_check_todo),
+            ("Enter",        t.shortcut_edit_todo),
+            ("Esc",          t.shortcut_cancel),
+            ("Ctrl+Z",       t.shortcut_undo),
+            ("Ctrl+Shift+Z", t.shortcut_redo),
+            ("Tab",          t.shortcut_tab),
+            ("?",            t.shortcut_help),
+        ]
+    }
+
+    /// The shortcut of `e`, not while typing in a field
+    fn of(e: &KeyboardEvent) -> Option<Self> {
+        let in_field = e.target()
+            .and_then(|target| target.dyn_into::<HtmlElement>().ok())
+            .is_some_and(|target| matches!(&*target.tag_name(), "INPUT" | "TEXTAREA" | "SELECT") || target.is_content_editable());
+        if in_field || e.alt_key() {
+            return None
+        }
+
+        if e.ctrl_key() || e.meta_key() {
+            return match (&*e.key(), e.shift_key()) {
+                ("z" | "Z", false) => Some(Self::Undo),
+                ("z" | "Z", true ) => Some(Self::Redo),
+                _                  => None,
+            }
+        }
+        match &*e.key() {
+            "n"      => Some(Self::NewCard),
+            "/"      => Some(Self::Search),
+            "j"      => Some(Self::NextTodo),
+            "k"      => Some(Self::PreviousTodo),
+            "?"      => Some(Self::ToggleHelp),
+            "Escape" => Some(Self::CloseHelp),
+            _        => None,
+        }
+    }
+}
+
+/// Call `on_shortcut` for the shortcuts pressed while this component is shown
+#[hook]
+pub fn use_shortcuts(on_shortcut: Callback<Shortcut>) {
+    /* listening once, with the callback of the latest render */
+    let latest = use_mut_ref(Callback::noop);
+    *latest.borrow_mut() = on_shortcut;
+
+    use_effect_with((), move |()| {
+        let listener = Closure::<dyn Fn(KeyboardEvent)>::new(move |e: KeyboardEvent| if let Some(shortcut) = Shortcut::of(&e) {
+            e.prevent_default();
+            latest.borrow().emit(shortcut)
+        });
+        let document = web_sys::window().and_then(|w| w.document());
+        if let Some(document) = &document {
+            let _ = document.add_event_listener_with_callback("keydown", listener.as_ref().unchecked_ref());
+        }
+        move || if let Some(document) = document {
+            let _ = document.remove_event_listener_with_callback("keydown", listener.as_ref().unchecked_ref());
+        }
+    });
+}
+
+
+/// Focus the todo next to ( or before, unless `forward` ) the one containing `from`,
+/// or the first ( last ) one when `from` isn't in any
+pub fn focus_todo_next_to(from: Option<&Element>, forward: bool) {
+    let Some(document) = web_sys::window().and_then(|w| w.document()) else {return};
+    let Ok(rows) = document.query_selector_all("[data-todo]") else {return};
+
+    /* not the ones of the cards being created */
+    let rows = (0..rows.length())
+        .filter_map(|i| rows.get(i)?.dyn_into::<HtmlElement>().ok())
+        .filter(|row| row.closest("[inert]").ok().flatten().is_none())
+        .collect::<Vec<_>>();
+    if rows.is_empty() {
+        return
+    }
+
+    let current = from
+        .and_then(|from| from.closest("[data-todo]").ok().flatten())
+        .and_then(|current| rows.iter().position(|row| row.is_same_node(Some(&current))));
+    let next = match (current, forward) {
+        (None,    true ) => 0,
+        (None,    false) => rows.len() - 1,
+        (Some(i), true ) => (i + 1).min(rows.len() - 1),
+        (Some(i), false) => i.saturating_sub(1),
+    };
+    let _ = rows[next].focus();
+}
+
+/// `focus_todo_next_to` the focused element
+pub fn move_todo_focus(forward: bool) {
+    let active = web_sys::window().and_then(|w| w.document()).and_then(|d| d.active_element());
+    focus_todo_next_to(active.as_ref(), forward)
+}
+
+
+#[derive(Properties, PartialEq)]
+pub struct ShortcutsHelpProps {
+    pub open:     bool,
+    pub on_close: Callback<()>,
+}
+
+#[function_component]
+pub fn ShortcutsHelp(ShortcutsHelpProps { open, on_close }: &ShortcutsHelpProps) -> Html {
+    let (close, t) = (use_node_ref(), use_messages());
+
+    /* into the dialog when it's opened */
+    use_effect_with(*open, {
+        let close = close.clone();
+        move |open| if *open {
+            if let Some(close) = close.cast::<HtmlElement>() {
+                let _ = close.focus();
+            }
+        }
+    });
+
+    if !*open {
+        return html!()
+    }
+
+    html!(
+        <div
+            class="fixed inset-0 bg-neutral-800/40 flex items-center justify-center"
+            onclick={on_close.reform(|_| ())}
+        >
+            <section
+                class="w-96 p-6 rounded-xl bg-neutral-100 shadow-lg text-neutral-800"
+                role="dialog"
+                aria-modal="true"
+                aria-labelledby="shortcuts-help-title"
+                onclick={|e: MouseEvent| e.stop_propagation()}
+            >
+                <header class="flex items-center">
+                    <h2 id="shortcuts-help-title" class="grow m-0 text-lg">{t.keyboard_shortcuts}</h2>
+                    <button
+                        ref={close}
+                        type="button"
+                        class="cursor-pointer bg-transparent border-0 rounded-sm text-neutral-500 text-lg outline-none focus-visible:ring-2 focus-visible:ring-sky-400"
+                        aria-label={t.close}
+                        onclick={on_close.reform(|_| ())}
+                    >
+                        {"×"}
+                    </button>
+                </header>
+                <dl class="mt-4 mb-0 grid grid-cols-[auto_1fr] gap-x-4 gap-y-2 text-sm">
+                    {for Shortcut::help(t).into_iter().map(|(keys, description)| html!(<>
+                        <dt><kbd class="px-1 rounded border border-solid border-neutral-300 bg-white">{keys}</kbd></dt>
+                        <dd class="m-0">{description}</dd>
+                    </>))}
+                </dl>
+            </section>
+        </div>
+    )
+}
@@ -0,0 +1,218 @@
+//! Notifications stacked at the bottom right of the page, dismissed automatically
+
+use super::fetch;
+use super::i18n::{use_i18n, use_messages, Locale, Messages};
+use yew::prelude::*;
+use yew::platform::{spawn_local, time::sleep};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::time::Duration;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Severity {
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn duration(self) -> Duration {
+        match self {
+            Self::Success => Duration::from_secs(4),
+            Self::Warning => Duration::from_secs(6),
+            Self::Error   => Duration::from_secs(10),
+        }
+    }
+
+    fn class(self) -> &'static str {
+        match self {
+            Self::Success => "bg-emerald-50 border-emerald-300 text-emerald-900",
+            Self::Warning => "bg-amber-50 border-amber-300 text-amber-900",
+            Self::Error   => "bg-red-50 border-red-300 text-red-900",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Toast {
+    pub severity: Severity,
+    pub message:  String,
+    /// Button like Retry or Undo, dismissing the toast when clicked
+    pub action:   Option<(&'static str, Callback<()>)>,
+}
+
+impl Toast {
+    pub fn success(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Success, message: message.into(), action: None }
+    }
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, message: message.into(), action: None }
+    }
+    pub fn error(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Error, message: message.into(), action: None }
+    }
+
+    pub fn with_action(mut self, label: &'static str, on_click: impl Fn() + 'static) -> Self {
+        self.action = Some((label, Callback::from(move |()| on_click())));
+        self
+    }
+}
+
+
+type Task = Rc<dyn Fn() -> Pin<Box<dyn Future<Output = Result<(), fetch::Error>>>>>;
+
+/// Handle to show toasts, by `use_toaster` under `ToastProvider`
+#[derive(Clone, PartialEq)]
+pub struct Toaster {
+    dispatcher: UseReducerDispatcher<Toasts>,
+    /// of the errors of `run`
+    locale:     Locale,
+}
+
+impl Toaster {
+    pub fn show(&self, toast: Toast) {
+        self.dispatcher.dispatch(ToastsAction::Show(toast))
+    }
+
+    /// Run `task` now, and show the error of it as `failure: {description}`
+    /// with Retry button to run it again when it fails
+    pub fn run<Fut: Future<Output = Result<(), fetch::Error>> + 'static>(&self,
+        failure: impl Into<String>,
+        task:    impl Fn() -> Fut + 'static,
+    ) {
+        self.attempt(failure.into().into(), Rc::new(move || Box::pin(task())))
+    }
+
+    fn attempt(&self, failure: Rc<str>, task: Task) {
+        /* started here for what it does before the request to be done at once */
+        let (toaster, attempt) = (self.clone(), task());
+        spawn_local(async move {
+            if let Err(err) = attempt.await {
+                let t = Messages::of(toaster.locale);
+                toaster.show(Toast::error(format!("{failure}: {}", fetch::describe(&err, t))).with_action(t.retry, {
+                    let toaster = toaster.clone();
+                    move || toaster.attempt(failure.clone(), task.clone())
+                }))
+            }
+        })
+    }
+}
+
+#[hook]
+pub fn use_toaster() -> Toaster {
+    use_context::<Toaster>().expect("`use_toaster` outside of `ToastProvider`")
+}
+
+
+#[derive(Default, PartialEq)]
+pub struct Toasts {
+    next_id: usize,
+    shown:   Vec<(usize, Toast)>,
+}
+
+impl Toasts {
+    /// Older ones are dismissed when more are shown
+    const MAX_SHOWN: usize = 5;
+}
+
+pub enum ToastsAction {
+    Show(Toast),
+    Dismiss(usize),
+}
+
+impl Reducible for Toasts {
+    type Action = ToastsAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut shown = self.shown.clone();
+        let mut next_id = self.next_id;
+        match action {
+            ToastsAction::Show(toast) => {
+                shown.push((next_id, toast));
+                next_id += 1;
+                if shown.len() > Self::MAX_SHOWN {
+                    shown.remove(0);
+                }
+            }
+            ToastsAction::Dismiss(id) => shown.retain(|(i, _)| *i != id),
+        }
+        Rc::new(Self { next_id, shown })
+    }
+}
+
+
+#[derive(Properties, PartialEq)]
+pub struct ToastProviderProps {
+    pub children: Html,
+}
+
+#[function_component]
+pub fn ToastProvider(ToastProviderProps { children }: &ToastProviderProps) -> Html {
+    let (toasts, locale) = (use_reducer(Toasts::default), use_i18n().locale);
+
+    html!(
+        <ContextProvider<Toaster> context={Toaster { dispatcher: toasts.dispatcher(), locale }}>
+            {children.clone()}
+            <ol class="fixed bottom-4 right-4 m-0 p-0 w-80 list-none flex flex-col space-y-2" aria-live="polite">
+                {for toasts.shown.iter().map(|(id, toast)| html!(
+                    <ToastItem
+                        key={*id}
+                        toast={toast.clone()}
+                        on_dismiss={{
+                            let (dispatcher, id) = (toasts.dispatcher(), *id);
+                            Callback::from(move |()| dispatcher.dispatch(ToastsAction::Dismiss(id)))
+                        }}
+                    />
+                ))}
+            </ol>
+        </ContextProvider<Toaster>>
+    )
+}
+
+#[derive(Properties, PartialEq)]
+struct ToastItemProps {
+    toast:      Toast,
+    on_dismiss: Callback<()>,
+}
+
+#[function_component]
+fn ToastItem(ToastItemProps { toast, on_dismiss }: &ToastItemProps) -> Html {
+    let t = use_messages();
+
+    use_effect_with((), {
+        let (on_dismiss, duration) = (on_dismiss.clone(), toast.severity.duration());
+        move |()| spawn_local(async move {
+            sleep(duration).await;
+            on_dismiss.emit(())
+        })
+    });
+
+    html!(
+        <li
+            class={classes!("px-3", "py-2", "rounded-md", "border", "border-solid", "shadow", "text-sm", "flex", "items-center", "space-x-2", toast.severity.class())}
+            role={if toast.severity == Severity::Error {"alert"} else {"status"}}
+        >
+            <span class="grow">{&toast.message}</span>
+            if let Some((label, on_click)) = &toast.action {
+                <button
+                    class="cursor-pointer bg-transparent border-0 font-bold underline text-inherit"
+                    onclick={{
+                        let (on_click, on_dismiss) = (on_click.clone(), on_dismiss.clone());
+                        move |_| {on_dismiss.emit(()); on_click.emit(())}
+                    }}
+                >
+                    {*label}
+                </button>
+            }
+            <button
+                class="cursor-pointer bg-transparent border-0 text-inherit"
+                aria-label={t.dismiss}
+                onclick={on_dismiss.reform(|_| ())}
+            >
+                {"×"}
+            </button>
+        </li>
+    )
+}
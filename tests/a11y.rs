@@ -0,0 +1,260 @@
+//! Accessibility of the components of the front, checked in a headless browser
+//! ( see [Test](../README.md#test) )
+
+#![cfg(target_arch = "wasm32")]
+
+#[allow(dead_code)]
+#[path = "../src/ui/mod.rs"]
+mod ui;
+
+use ui::components::{FrontCoverCard, PlusCard, TodoCard, TodoCardProps, PlusCardProps};
+use ui::shortcuts::{ShortcutsHelp, ShortcutsHelpProps};
+use models::{Attachment, Card, Template, Todo};
+use yew::{BaseComponent, Callback};
+use web_sys::{Element, HtmlElement, wasm_bindgen::JsCast};
+use wasm_bindgen_test::*;
+use std::rc::Rc;
+use std::time::Duration;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+
+/// Render `C` into a new element in the document, and return the element
+async fn render<C: BaseComponent>(props: C::Properties) -> Element {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let root = document.create_element("div").unwrap();
+    document.body().unwrap().append_child(&root).unwrap();
+
+    yew::Renderer::<C>::with_root_and_props(root.clone(), props).render();
+    settle().await;
+    root
+}
+
+/// Wait for the scheduled renders
+async fn settle() {
+    yew::platform::time::sleep(Duration::from_millis(10)).await
+}
+
+fn select(root: &Element, selector: &str) -> Vec<HtmlElement> {
+    let found = root.query_selector_all(selector).unwrap();
+    (0..found.length()).filter_map(|i| found.get(i)?.dyn_into().ok()).collect()
+}
+
+/// `aria-label`, the text of `aria-labelledby`, or the text and the `alt`s of the contents
+fn accessible_name(element: &Element) -> String {
+    if let Some(label) = element.get_attribute("aria-label") {
+        return label
+    }
+    if let Some(ids) = element.get_attribute("aria-labelledby") {
+        let document = element.owner_document().unwrap();
+        return ids.split_whitespace()
+            .filter_map(|id| document.get_element_by_id(id)?.text_content())
+            .collect::<Vec<_>>().join(" ")
+    }
+    let text = element.text_content().unwrap_or_default();
+    let alts = select(element, "img[alt]").into_iter().filter_map(|img| img.get_attribute("alt"));
+    std::iter::once(text).chain(alts).collect::<Vec<_>>().join(" ").trim().to_owned()
+}
+
+/// What is against the rules in `root`, except in the parts hidden from screen readers
+fn violations(root: &Element) -> Vec<String> {
+    let mut found = vec![];
+    let mut violate = |element: &HtmlElement, rule: &str| if element.closest("[aria-hidden='true']").unwrap().is_none() {
+        found.push(format!("{rule}: {}", element.outer_html()))
+    };
+
+    for image in select(root, "img:not([alt])") {
+        violate(&image, "image without `alt`")
+    }
+    for link in select(root, "a:not([href])") {
+        violate(&link, "link without `href`, not focusable")
+    }
+    for operable in select(root, "
+        button, a[href], input, textarea, select,
+        [role='button'], [role='checkbox'], [role='textbox'], [role='group'], [role='dialog'],
+        [tabindex='0']
+    ") {
+        if accessible_name(&operable).is_empty() {
+            violate(&operable, "without an accessible name")
+        }
+    }
+    for focusable in select(root, "div[tabindex='0'], span[tabindex='0'], p[tabindex='0'], li[tabindex='0']") {
+        if !focusable.has_attribute("role") {
+            violate(&focusable, "focusable without `role`")
+        }
+    }
+    for focusable in select(root, "[tabindex]") {
+        if focusable.get_attribute("tabindex").unwrap().parse::<i32>().is_ok_and(|i| i > 0) {
+            violate(&focusable, "positive `tabindex`, out of the order of the page")
+        }
+    }
+    for checkbox in select(root, "[role='checkbox']") {
+        if !matches!(checkbox.get_attribute("aria-checked").as_deref(), Some("true" | "false")) {
+            violate(&checkbox, "checkbox without `aria-checked`")
+        }
+    }
+    for toggle in select(root, "[aria-expanded]") {
+        if !matches!(toggle.get_attribute("aria-expanded").as_deref(), Some("true" | "false")) {
+            violate(&toggle, "`aria-expanded` other than true / false")
+        }
+    }
+    found
+}
+
+fn assert_accessible(root: &Element) {
+    let found = violations(root);
+    assert!(found.is_empty(), "{}", found.join("\n"))
+}
+
+
+fn sample_card() -> Card {
+    let mut todos: [Todo; Card::N_TODOS] = std::array::from_fn(|_| Todo::new(""));
+    todos[0] = Todo::new("Buy **milk**");
+    todos[0].notes = Some(String::from("low-fat"));
+    todos[0].children.push(Todo::new("Check the [shop](https://example.com)"));
+    todos[1] = Todo::new("Wash the dishes");
+    todos[1].set_completed(true);
+
+    Card {
+        id:    String::from("2b9bb2f3-3e1b-4a4c-8b0e-3d5b1a1f0c11"),
+        title: String::from("Groceries"),
+        todos,
+        attachments: vec![
+            Attachment {
+                id:        String::from("photo"),
+                name:      String::from("receipt.png"),
+                mime_type: String::from("image/png"),
+                size:      1024,
+                url:       String::from("/api/attachments/photo"),
+            },
+            Attachment {
+                id:        String::from("list"),
+                name:      String::from("list.pdf"),
+                mime_type: String::from("application/pdf"),
+                size:      2048,
+                url:       String::from("/api/attachments/list"),
+            },
+        ],
+    }
+}
+
+fn todo_card_props(card: Card) -> TodoCardProps {
+    TodoCardProps {
+        bind:                      Rc::new(card),
+        pending:                   false,
+        saves:                     Default::default(),
+        on_click_open:             Some(Callback::noop()),
+        on_click_archive:          Callback::noop(),
+        on_click_delete:           Callback::noop(),
+        on_click_duplicate:        Callback::noop(),
+        on_click_save_as_template: Callback::noop(),
+        on_upload_attachment:      Callback::noop(),
+        on_delete_attachment:      Callback::noop(),
+        on_edit_title:             Callback::noop(),
+        on_check_todo_by:          std::array::from_fn(|_| Callback::noop()),
+        on_edit_todo_by:           std::array::from_fn(|_| Callback::noop()),
+        on_edit_notes_by:          std::array::from_fn(|_| Callback::noop()),
+        on_add_subtodo_by:         std::array::from_fn(|_| Callback::noop()),
+    }
+}
+
+
+#[wasm_bindgen_test]
+async fn todo_card_is_accessible() {
+    let root = render::<TodoCard>(todo_card_props(sample_card())).await;
+    assert_accessible(&root);
+
+    let checkboxes = select(&root, "[role='checkbox']");
+    assert_eq!(checkboxes[0].get_attribute("aria-label").as_deref(), Some("Buy **milk**"));
+    assert_eq!(checkboxes[0].get_attribute("aria-checked").as_deref(), Some("false"));
+    assert_eq!(checkboxes[2].get_attribute("aria-label").as_deref(), Some("Wash the dishes"));
+    assert_eq!(checkboxes[2].get_attribute("aria-checked").as_deref(), Some("true"));
+    /* the empty todos can't be checked */
+    assert!(checkboxes[3].has_attribute("disabled"));
+
+    let labels = select(&root, "header button").into_iter()
+        .map(|button| accessible_name(&button))
+        .collect::<Vec<_>>();
+    assert_eq!(labels, [
+        "Open card “Groceries”",
+        "Duplicate card “Groceries”",
+        "Save card “Groceries” as a template",
+        "Attach an image or a PDF to card “Groceries”",
+        "Archive card “Groceries”",
+        "Delete card “Groceries”",
+    ]);
+}
+
+#[wasm_bindgen_test]
+async fn todo_card_is_accessible_while_editing() {
+    let root = render::<TodoCard>(todo_card_props(sample_card())).await;
+
+    /* the Markdown of the todo turns into the input by the focus */
+    select(&root, "p[role='textbox']")[0].focus().unwrap();
+    settle().await;
+    let input = select(&root, "[data-todo] input")[0].clone();
+    assert_eq!(input.get_attribute("aria-label").as_deref(), Some("Todo 1"));
+
+    let notes = select(&root, "button[aria-label='Notes of Buy **milk**']")[0].clone();
+    assert_eq!(notes.get_attribute("aria-expanded").as_deref(), Some("false"));
+    notes.click();
+    settle().await;
+    assert_eq!(notes.get_attribute("aria-expanded").as_deref(), Some("true"));
+    assert_eq!(select(&root, "textarea")[0].get_attribute("aria-label").as_deref(), Some("Notes of Buy **milk**"));
+
+    assert_accessible(&root);
+}
+
+#[wasm_bindgen_test]
+async fn untitled_and_pending_cards_are_accessible() {
+    let mut card = sample_card();
+    card.title = String::new();
+    let root = render::<TodoCard>(TodoCardProps { pending: true, ..todo_card_props(card) }).await;
+    assert_accessible(&root);
+
+    let article = select(&root, "article")[0].clone();
+    assert_eq!(article.get_attribute("aria-label").as_deref(), Some("untitled card"));
+    assert_eq!(article.get_attribute("aria-busy").as_deref(), Some("true"));
+}
+
+#[wasm_bindgen_test]
+async fn front_cover_card_is_accessible() {
+    let root = render::<FrontCoverCard>(()).await;
+    assert_accessible(&root);
+}
+
+#[wasm_bindgen_test]
+async fn plus_card_is_accessible() {
+    let root = render::<PlusCard>(PlusCardProps {
+        templates: vec![
+            Template { id: String::from("weekly"), title: String::from("Weekly"), todos: std::array::from_fn(|_| String::new()) },
+            Template { id: String::from("blank"),  title: String::new(),           todos: std::array::from_fn(|_| String::new()) },
+        ],
+        on_click_blank:     Callback::noop(),
+        on_click_template:  Callback::noop(),
+        on_delete_template: Callback::noop(),
+    }).await;
+    assert_accessible(&root);
+
+    select(&root, "button[aria-label='New card']")[0].click();
+    settle().await;
+    assert_accessible(&root);
+    assert_eq!(
+        select(&root, "li button[aria-label^='Delete']").into_iter().map(|b| accessible_name(&b)).collect::<Vec<_>>(),
+        ["Delete the template Weekly", "Delete the template (untitled)"],
+    );
+}
+
+#[wasm_bindgen_test]
+async fn shortcuts_help_is_accessible() {
+    let root = render::<ShortcutsHelp>(ShortcutsHelpProps { open: true, on_close: Callback::noop() }).await;
+    assert_accessible(&root);
+
+    let dialog = select(&root, "[role='dialog']")[0].clone();
+    assert_eq!(dialog.get_attribute("aria-modal").as_deref(), Some("true"));
+    assert_eq!(accessible_name(&dialog), "Keyboard shortcuts");
+
+    /* the focus is moved into the dialog */
+    let active = root.owner_document().unwrap().active_element().unwrap();
+    assert_eq!(active.get_attribute("aria-label").as_deref(), Some("Close"));
+}
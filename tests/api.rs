@@ -0,0 +1,487 @@
+#![cfg(all(feature = "server", not(target_arch = "wasm32")))]
+
+mod common;
+
+use common::{TestServer, JWT_SECRET_KEY};
+use models::{Attachment, Card, CreateCardResponse, PairingCodeResponse, SignupResponse, Todo, UpdateCard};
+use ohkami::fang::JWT;
+use ohkami::serde::json::json;
+use reqwest::{Method, StatusCode};
+
+
+const EMPTY: [&str; Card::N_TODOS] = [""; Card::N_TODOS];
+
+#[tokio::test]
+async fn signup_issues_a_token_for_api() {
+    let server = TestServer::start();
+
+    let token = server.signup().await;
+    assert!(server.list_cards(&token).await.is_empty());
+
+    let another = server.signup().await;
+    assert_ne!(token, another);
+}
+
+#[tokio::test]
+async fn api_rejects_requests_without_valid_jwt() {
+    let server = TestServer::start();
+
+    let res = server.request(Method::GET, "/api/cards").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = server.request(Method::GET, "/api/cards").bearer_auth("not.a.token").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    /* signed by the secret, but not of `JWTPayload` */
+    let malformed = JWT::default(JWT_SECRET_KEY).issue(json!({ "sub": "someone" }));
+    let res = server.request(Method::GET, "/api/cards").bearer_auth(&*malformed).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let forged = JWT::default("another-secret-key").issue(json!({ "user_id": "someone", "iat": 0 }));
+    let res = server.request(Method::GET, "/api/cards").bearer_auth(&*forged).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = server.request(Method::POST, "/api/cards")
+        .json(&json!({ "title": "", "todos": EMPTY }))
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    /* but valid with the right secret */
+    let genuine = JWT::default(JWT_SECRET_KEY).issue(json!({ "user_id": "someone", "iat": 0 }));
+    let res = server.request(Method::GET, "/api/cards").bearer_auth(&*genuine).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn card_crud() {
+    let server = TestServer::start();
+    let token = server.signup().await;
+
+    /* create */
+    let id = server.create_card(&token, "groceries", ["milk", "eggs", "", "", "", "", "", "", "", ""]).await;
+
+    /* read */
+    let cards = server.list_cards(&token).await;
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].id, id);
+    assert_eq!(cards[0].title, "groceries");
+    assert_eq!(cards[0].todos[0], Todo::new("milk"));
+    assert_eq!(cards[0].todos[1], Todo::new("eggs"));
+
+    /* update */
+    let mut todos = cards[0].todos.clone();
+    todos[0].completed = true;
+    todos[2] = Todo { notes: Some("the soft one".into()), ..Todo::new("bread") };
+    let res = server.request(Method::PUT, &format!("/api/cards/{id}"))
+        .bearer_auth(&token)
+        .json(&UpdateCard { title: "shopping".into(), todos: todos.clone() })
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let cards = server.list_cards(&token).await;
+    assert_eq!(cards[0].title, "shopping");
+    assert_eq!(cards[0].todos, todos);
+
+    /* not too deep nor too many sub-todos */
+    let nested = |depth: usize| (0..depth).fold(Todo::new("leaf"), |child, _| Todo { children: vec![child], ..Todo::new("parent") });
+    let update_by = |todo: Todo| {
+        let mut todos = todos.clone();
+        todos[3] = todo;
+        server.request(Method::PUT, &format!("/api/cards/{id}"))
+            .bearer_auth(&token)
+            .json(&UpdateCard { title: "shopping".into(), todos })
+            .send()
+    };
+    assert_eq!(update_by(nested(Todo::MAX_DEPTH)).await.unwrap().status(), StatusCode::OK);
+    assert_eq!(update_by(nested(Todo::MAX_DEPTH + 1)).await.unwrap().status(), StatusCode::BAD_REQUEST);
+    let many = Todo { children: vec![Todo::new("child"); Todo::MAX_SUBTODOS + 1], ..Todo::new("parent") };
+    assert_eq!(update_by(many).await.unwrap().status(), StatusCode::BAD_REQUEST);
+    assert_eq!(server.list_cards(&token).await[0].todos[3], nested(Todo::MAX_DEPTH));
+
+    /* duplicate */
+    let res = server.request(Method::POST, &format!("/api/cards/{id}/duplicate"))
+        .bearer_auth(&token)
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let duplicated = res.json::<CreateCardResponse>().await.unwrap().id;
+
+    let cards = server.list_cards(&token).await;
+    assert_eq!(cards.iter().map(|c| &*c.id).collect::<Vec<_>>(), [&*id, &*duplicated]);
+    assert!(cards[1].todos.iter().all(|t| !t.completed));
+
+    /* delete */
+    let res = server.request(Method::DELETE, &format!("/api/cards/{id}"))
+        .bearer_auth(&token)
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let cards = server.list_cards(&token).await;
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].id, duplicated);
+}
+
+#[tokio::test]
+async fn deleted_cards_are_in_the_trash_until_deleted_for_good() {
+    let server = TestServer::start();
+    let token = server.signup().await;
+    let id = server.create_card(&token, "old", EMPTY).await;
+
+    let res = server.request(Method::POST, &format!("/api/cards/{id}/attachments?name=a.png"))
+        .bearer_auth(&token)
+        .header("Content-Type", "image/png")
+        .body(b"\x89PNG\r\n\x1a\n".to_vec())
+        .send().await.unwrap();
+    let attachment = res.json::<Attachment>().await.unwrap();
+
+    let send = |method: Method, path: String| server.request(method, &path).bearer_auth(&token).send();
+    let listed = |path: &'static str| async move {
+        send(Method::GET, path.into()).await.unwrap().json::<Vec<Card>>().await.unwrap()
+            .into_iter().map(|card| card.id).collect::<Vec<_>>()
+    };
+
+    /* only from the trash */
+    assert_eq!(send(Method::DELETE, format!("/api/trash/{id}")).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+    assert_eq!(send(Method::PUT, format!("/api/archive/{id}")).await.unwrap().status(), StatusCode::OK);
+    assert!(listed("/api/cards").await.is_empty());
+    assert_eq!(listed("/api/archive").await, [&*id]);
+
+    assert_eq!(send(Method::DELETE, format!("/api/cards/{id}")).await.unwrap().status(), StatusCode::OK);
+    assert!(listed("/api/archive").await.is_empty());
+    assert_eq!(listed("/api/trash").await, [&*id]);
+    /* with the attachment still there */
+    assert_eq!(server.request(Method::GET, &attachment.url).send().await.unwrap().status(), StatusCode::OK);
+
+    assert_eq!(send(Method::POST, format!("/api/trash/{id}/restore")).await.unwrap().status(), StatusCode::OK);
+    assert_eq!(listed("/api/cards").await, [&*id]);
+    assert!(listed("/api/trash").await.is_empty());
+
+    send(Method::DELETE, format!("/api/cards/{id}")).await.unwrap();
+    assert_eq!(send(Method::DELETE, format!("/api/trash/{id}")).await.unwrap().status(), StatusCode::OK);
+    assert!(listed("/api/trash").await.is_empty());
+    assert_eq!(server.request(Method::GET, &attachment.url).send().await.unwrap().status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn every_listed_card_has_exactly_ten_todos() {
+    let server = TestServer::start();
+    let token = server.signup().await;
+
+    server.create_card(&token, "empty", EMPTY).await;
+    let id = server.create_card(&token, "full", ["1", "2", "3", "4", "5", "6", "7", "8", "9", "10"]).await;
+
+    /* sub-todos don't count as the todos of the card */
+    let mut todos = EMPTY.map(Todo::new);
+    todos[0].children = vec![Todo::new("a"), Todo::new("b"), Todo::new("c")];
+    let res = server.request(Method::PUT, &format!("/api/cards/{id}"))
+        .bearer_auth(&token)
+        .json(&UpdateCard { title: "full".into(), todos })
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    /* neither fewer nor more todos are accepted */
+    for n in [Card::N_TODOS - 1, Card::N_TODOS + 1] {
+        let res = server.request(Method::POST, "/api/cards")
+            .bearer_auth(&token)
+            .json(&json!({ "title": "wrong", "todos": vec![""; n] }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let res = server.request(Method::GET, "/api/cards").bearer_auth(&token).send().await.unwrap();
+    let cards = res.json::<Vec<ohkami::serde::json::Value>>().await.unwrap();
+    assert_eq!(cards.len(), 2);
+    for card in cards {
+        assert_eq!(card["todos"].as_array().unwrap().len(), Card::N_TODOS);
+    }
+}
+
+#[tokio::test]
+async fn cards_of_another_user_are_forbidden() {
+    let server = TestServer::start();
+    let owner = server.signup().await;
+    let other = server.signup().await;
+
+    let id = server.create_card(&owner, "mine", ["secret", "", "", "", "", "", "", "", "", ""]).await;
+
+    assert!(server.list_cards(&other).await.is_empty());
+
+    for (method, path) in [
+        (Method::PUT,    format!("/api/cards/{id}")),
+        (Method::DELETE, format!("/api/cards/{id}")),
+        (Method::POST,   format!("/api/cards/{id}/duplicate")),
+        (Method::POST,   format!("/api/cards/{id}/attachments?name=a.png")),
+        (Method::PUT,    format!("/api/archive/{id}")),
+        (Method::POST,   format!("/api/trash/{id}/restore")),
+        (Method::DELETE, format!("/api/trash/{id}")),
+    ] {
+        let mut req = server.request(method.clone(), &path).bearer_auth(&other);
+        if method == Method::PUT && path.starts_with("/api/cards") {
+            req = req.json(&UpdateCard { title: "stolen".into(), todos: EMPTY.map(Todo::new) })
+        } else if path.contains("attachments") {
+            req = req.header("Content-Type", "image/png").body(b"\x89PNG\r\n\x1a\n".to_vec())
+        }
+        let res = req.send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN, "{method} {path}");
+    }
+
+    let res = server.request(Method::POST, "/api/templates")
+        .bearer_auth(&other)
+        .json(&json!({ "card_id": id }))
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let cards = server.list_cards(&owner).await;
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].title, "mine");
+    assert_eq!(cards[0].todos[0].content, "secret");
+}
+
+#[tokio::test]
+async fn cards_are_created_by_ids_of_the_client() {
+    let server = TestServer::start();
+    let token = server.signup().await;
+    let other = server.signup().await;
+
+    let id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    let res = server.request(Method::POST, "/api/cards")
+        .bearer_auth(&token)
+        .json(&json!({ "id": id, "title": "chosen", "todos": EMPTY }))
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert_eq!(res.json::<CreateCardResponse>().await.unwrap().id, id);
+
+    let cards = server.list_cards(&token).await;
+    assert_eq!(cards.len(), 1);
+    assert_eq!((&*cards[0].id, &*cards[0].title), (id, "chosen"));
+
+    /* taken, by whichever user */
+    for token in [&token, &other] {
+        let res = server.request(Method::POST, "/api/cards")
+            .bearer_auth(token)
+            .json(&json!({ "id": id, "title": "again", "todos": EMPTY }))
+            .send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        /* not to be retried like the one of `Idempotency-Key` in progress */
+        assert!(res.headers().get("Retry-After").is_none());
+    }
+
+    let res = server.request(Method::POST, "/api/cards")
+        .bearer_auth(&token)
+        .json(&json!({ "id": "../templates", "title": "", "todos": EMPTY }))
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    assert_eq!(server.list_cards(&token).await.len(), 1);
+    assert!(server.list_cards(&other).await.is_empty());
+}
+
+#[tokio::test]
+async fn pairing_code_logs_in_to_the_account_once() {
+    let server = TestServer::start();
+    let token = server.signup().await;
+    server.create_card(&token, "mine", EMPTY).await;
+
+    let res = server.request(Method::POST, "/api/pairing-codes").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = server.request(Method::POST, "/api/pairing-codes").bearer_auth(&token).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let PairingCodeResponse { code, expires_in } = res.json().await.unwrap();
+    assert!(expires_in > 0);
+
+    let pair = |code: String| server.request(Method::POST, "/pair")
+        .json(&json!({ "code": code }))
+        .send();
+
+    let res = pair(format!(" {} ", code.to_lowercase())).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let SignupResponse { token: paired } = res.json().await.unwrap();
+    let cards = server.list_cards(&paired).await;
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].title, "mine");
+
+    /* used up */
+    assert_eq!(pair(code).await.unwrap().status(), StatusCode::NOT_FOUND);
+    assert_eq!(pair("0123456789".into()).await.unwrap().status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn idempotency_key_replays_the_first_response() {
+    let server = TestServer::start();
+    let (token, another) = (server.signup().await, server.signup().await);
+
+    let create = |token: &str, key: &str| server.request(Method::POST, "/api/cards")
+        .bearer_auth(token)
+        .header("Idempotency-Key", key)
+        .json(&json!({ "title": "once", "todos": EMPTY }))
+        .send();
+
+    let first = create(&token, "key-1").await.unwrap();
+    assert_eq!(first.status(), StatusCode::CREATED);
+    assert!(first.headers().get("Idempotent-Replayed").is_none());
+    let CreateCardResponse { id } = first.json().await.unwrap();
+
+    let repeated = create(&token, "key-1").await.unwrap();
+    assert_eq!(repeated.status(), StatusCode::CREATED);
+    assert_eq!(repeated.headers().get("Idempotent-Replayed").unwrap(), "true");
+    assert_eq!(repeated.json::<CreateCardResponse>().await.unwrap().id, id);
+    assert_eq!(server.list_cards(&token).await.len(), 1);
+
+    /* keys are of each user */
+    assert_eq!(create(&another, "key-1").await.unwrap().status(), StatusCode::CREATED);
+    assert_eq!(server.list_cards(&another).await.len(), 1);
+
+    /* not for another request */
+    let res = server.request(Method::POST, &format!("/api/cards/{id}/duplicate"))
+        .bearer_auth(&token)
+        .header("Idempotency-Key", "key-1")
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    /* nor for another body */
+    let res = server.request(Method::POST, "/api/cards")
+        .bearer_auth(&token)
+        .header("Idempotency-Key", "key-1")
+        .json(&json!({ "title": "twice", "todos": EMPTY }))
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(server.list_cards(&token).await.len(), 1);
+
+    /* unsuccessful responses are not replayed, for the request to be retried */
+    let create_by = |id: &str| server.request(Method::POST, "/api/cards")
+        .bearer_auth(&token)
+        .header("Idempotency-Key", "key-2")
+        .json(&json!({ "id": id, "title": "retried", "todos": EMPTY }))
+        .send();
+    assert_eq!(create_by("not-a-uuid").await.unwrap().status(), StatusCode::BAD_REQUEST);
+    let res = create_by("67e55044-10b1-426f-9247-bb680e5fe0c8").await.unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert!(res.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(server.list_cards(&token).await.len(), 2);
+
+    /* PUT and DELETE are idempotent by themselves */
+    for _ in 0..2 {
+        let res = server.request(Method::DELETE, &format!("/api/cards/{id}"))
+            .bearer_auth(&token)
+            .header("Idempotency-Key", "key-3")
+            .send().await.unwrap();
+        assert!(res.headers().get("Idempotent-Replayed").is_none());
+    }
+    assert_eq!(server.list_cards(&token).await.len(), 1);
+
+    assert_eq!(create(&token, "").await.unwrap().status(), StatusCode::BAD_REQUEST);
+    assert_eq!(create(&token, &"k".repeat(256)).await.unwrap().status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn error_messages_are_in_the_language_of_accept_language() {
+    let server = TestServer::start();
+    let token = server.signup().await;
+    let another = server.signup().await;
+    let id = server.create_card(&token, "mine", EMPTY).await;
+
+    let res = server.request(Method::GET, "/api/cards")
+        .header("Accept-Language", "ja-JP,en-US;q=0.8")
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(res.headers().get("Content-Language").unwrap(), "ja");
+    assert_eq!(res.headers().get("Vary").unwrap(), "Accept-Language");
+    assert_eq!(res.text().await.unwrap(), "トークンがないか、無効です");
+
+    let res = server.request(Method::DELETE, &format!("/api/cards/{id}"))
+        .bearer_auth(&another)
+        .header("Accept-Language", "fr, en;q=0.5, ja;q=0.1")
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    assert_eq!(res.headers().get("Content-Language").unwrap(), "en");
+    assert_eq!(res.text().await.unwrap(), "It isn't yours, or has already been deleted");
+
+    /* English without `Accept-Language` */
+    let res = server.request(Method::POST, "/api/cards")
+        .bearer_auth(&token)
+        .json(&json!({ "id": "not-a-uuid", "title": "", "todos": EMPTY }))
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(res.text().await.unwrap(), "The request is malformed");
+
+    /* by the reason more specific than the status */
+    let res = server.request(Method::GET, "/attachments/42?expires=0&signature=forged")
+        .header("Accept-Language", "en")
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    assert!(res.headers().keys().all(|name| !name.as_str().contains("reason")), "{:?}", res.headers());
+    assert_eq!(res.text().await.unwrap(), "The link is invalid or has expired ( reload the page for a new one )");
+}
+
+#[tokio::test]
+async fn attachments_of_another_card_are_not_deleted() {
+    let server = TestServer::start();
+    let owner = server.signup().await;
+    let other = server.signup().await;
+
+    let card = server.create_card(&owner, "mine", EMPTY).await;
+    let res = server.request(Method::POST, &format!("/api/cards/{card}/attachments?name=a.png"))
+        .bearer_auth(&owner)
+        .header("Content-Type", "image/png")
+        .body(b"\x89PNG\r\n\x1a\n".to_vec())
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let attachment = res.json::<Attachment>().await.unwrap();
+
+    /* by the path of a card of the other user */
+    let others = server.create_card(&other, "theirs", EMPTY).await;
+    let res = server.request(Method::DELETE, &format!("/api/cards/{others}/attachments/{}", attachment.id))
+        .bearer_auth(&other)
+        .send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let res = server.request(Method::GET, &attachment.url).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["Content-Type"], "image/png");
+    assert_eq!(res.headers()["Content-Disposition"], "inline; filename=\"a.png\"; filename*=UTF-8''a.png");
+    assert_eq!(res.headers()["X-Content-Type-Options"], "nosniff");
+    assert_eq!(server.list_cards(&owner).await[0].attachments, [attachment.clone()]);
+
+    /* twice by the owner */
+    let delete = || server.request(Method::DELETE, &format!("/api/cards/{card}/attachments/{}", attachment.id))
+        .bearer_auth(&owner)
+        .send();
+    assert_eq!(delete().await.unwrap().status(), StatusCode::OK);
+    assert_eq!(delete().await.unwrap().status(), StatusCode::NOT_FOUND);
+    assert!(server.list_cards(&owner).await[0].attachments.is_empty());
+}
+
+#[tokio::test]
+async fn attachment_names_are_safe_in_headers() {
+    let server = TestServer::start();
+    let token = server.signup().await;
+    let card = server.create_card(&token, "names", EMPTY).await;
+
+    let upload = |name: &str| server.request(Method::POST, &format!("/api/cards/{card}/attachments"))
+        .query(&[("name", name)])
+        .bearer_auth(&token)
+        .header("Content-Type", "application/pdf")
+        .body(b"%PDF-1.4".to_vec())
+        .send();
+
+    let res = upload("a.pdf\r\nSet-Cookie:x=y").await.unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let attachment = res.json::<Attachment>().await.unwrap();
+    assert_eq!(attachment.name, "a.pdfSet-Cookie:x=y");
+    let res = server.request(Method::GET, &attachment.url).send().await.unwrap();
+    assert!(res.headers().get("Set-Cookie").is_none());
+    assert_eq!(res.headers()["Content-Disposition"],
+        "inline; filename=\"a.pdfSet-Cookie:x=y\"; filename*=UTF-8''a.pdfSet-Cookie%3Ax%3Dy");
+
+    let res = upload("予定.pdf").await.unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let attachment = res.json::<Attachment>().await.unwrap();
+    assert_eq!(attachment.name, "予定.pdf");
+    let res = server.request(Method::GET, &attachment.url).send().await.unwrap();
+    assert_eq!(res.headers()["Content-Disposition"],
+        "inline; filename=\"__.pdf\"; filename*=UTF-8''%E4%BA%88%E5%AE%9A.pdf");
+
+    assert_eq!(upload("\r\n").await.unwrap().status(), StatusCode::BAD_REQUEST);
+    assert_eq!(upload(&"a".repeat(Attachment::MAX_NAME_LEN + 1)).await.unwrap().status(), StatusCode::BAD_REQUEST);
+}
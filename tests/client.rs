@@ -0,0 +1,220 @@
+#![cfg(all(feature = "server", not(target_arch = "wasm32")))]
+
+//! Every method of `client::Client` against the routes of the server
+
+mod common;
+
+use common::TestServer;
+use client::{Client, Error, RetryPolicy, RetryState};
+use models::{Card, CreateCardRequest, Todo, UpdateCard};
+use reqwest::StatusCode;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+
+#[tokio::test]
+async fn every_method_reaches_its_route() {
+    let server = TestServer::start();
+    let client = Client::signup(server.origin()).await.unwrap();
+
+    assert!(client.list_cards().await.unwrap().is_empty());
+
+    /* cards */
+
+    let id = client.create_card(&CreateCardRequest {
+        id: None,
+        title: "groceries".into(),
+        todos: ["milk", "eggs", "", "", "", "", "", "", "", ""].map(String::from),
+    }).await.unwrap();
+
+    let mut todos = client.list_cards().await.unwrap()[0].todos.clone();
+    todos[0].completed = true;
+    todos[1].children = vec![Todo::new("a dozen")];
+    client.update_card(&id, &UpdateCard { title: "shopping".into(), todos: todos.clone() }).await.unwrap();
+
+    let duplicated = client.duplicate_card(&id).await.unwrap();
+
+    let cards = client.list_cards().await.unwrap();
+    assert_eq!(cards.iter().map(|c| &*c.id).collect::<Vec<_>>(), [&*id, &*duplicated]);
+    assert_eq!((&*cards[0].title, &cards[0].todos), ("shopping", &todos));
+
+    /* attachments */
+
+    let attachment = client.upload_attachment(&id, "a b&c 日本語.png", "image/png", b"\x89PNG\r\n\x1a\n".to_vec()).await.unwrap();
+    assert_eq!((&*attachment.name, &*attachment.mime_type, attachment.size), ("a b&c 日本語.png", "image/png", 8));
+
+    let downloaded = reqwest::get(client.url(&attachment.url)).await.unwrap();
+    assert_eq!(downloaded.status(), StatusCode::OK);
+    assert_eq!(&*downloaded.bytes().await.unwrap(), b"\x89PNG\r\n\x1a\n");
+
+    let listed = client.list_cards().await.unwrap().remove(0).attachments;
+    assert_eq!(listed.iter().map(|a| &*a.id).collect::<Vec<_>>(), [&*attachment.id]);
+
+    client.delete_attachment(&id, &attachment.id).await.unwrap();
+    assert!(client.list_cards().await.unwrap()[0].attachments.is_empty());
+
+    /* templates */
+
+    let template = client.create_template(&id).await.unwrap();
+
+    let templates = client.list_templates().await.unwrap();
+    assert_eq!(templates.len(), 1);
+    assert_eq!((&*templates[0].id, &*templates[0].title), (&*template, "shopping"));
+
+    let instantiated = client.instantiate_template(&template).await.unwrap();
+    let Card { title, todos, .. } = client.list_cards().await.unwrap().pop().unwrap();
+    assert_eq!(title, "shopping");
+    assert_eq!(todos.map(|t| t.content)[..2], ["milk", "eggs"]);
+
+    client.delete_template(&template).await.unwrap();
+    assert!(client.list_templates().await.unwrap().is_empty());
+
+    client.delete_card(&id).await.unwrap();
+    let cards = client.list_cards().await.unwrap();
+    assert_eq!(cards.iter().map(|c| &*c.id).collect::<Vec<_>>(), [&*duplicated, &*instantiated]);
+
+    /* by an id of the client */
+
+    let chosen = client::new_card_id();
+    assert!(models::is_uuid(&chosen));
+    let id = client.create_card(&CreateCardRequest { id: Some(chosen.clone()), ..CreateCardRequest::empty() }).await.unwrap();
+    assert_eq!(id, chosen);
+    assert_eq!(client.list_cards().await.unwrap().pop().unwrap().id, chosen);
+
+    /* pairing */
+
+    let pairing = client.create_pairing_code().await.unwrap();
+    let paired = Client::pair(server.origin(), &pairing.code).await.unwrap();
+    assert_eq!(paired.list_cards().await.unwrap(), client.list_cards().await.unwrap());
+}
+
+#[tokio::test]
+async fn unsuccessful_responses_are_errors() {
+    let server = TestServer::start();
+    let owner = Client::signup(server.origin()).await.unwrap();
+    let other = Client::signup(server.origin()).await.unwrap();
+
+    let id = owner.create_card(&CreateCardRequest::empty()).await.unwrap();
+
+    let err = other.delete_card(&id).await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::FORBIDDEN));
+
+    let err = owner.upload_attachment(&id, "a.txt", "text/plain", b"text".to_vec()).await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+
+    let err = Client::new(server.origin(), "not.a.token").list_cards().await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::UNAUTHORIZED));
+}
+
+/// Server answering the requests with `responses` in order ( or never when they run out ),
+/// and the heads of the requests it has received
+fn stub(responses: &'static [&'static str]) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let origin = format!("http://{}", listener.local_addr().unwrap());
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    std::thread::spawn({
+        let received = received.clone();
+        move || for (stream, response) in listener.incoming().zip(responses.iter().chain(std::iter::repeat(&""))) {
+            let mut stream = BufReader::new(stream.unwrap());
+
+            let mut head = String::new();
+            while !head.ends_with("\r\n\r\n") {
+                stream.read_line(&mut head).unwrap();
+            }
+            let content_length = head.lines()
+                .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length: ").map(|n| n.parse().unwrap()))
+                .unwrap_or(0);
+            stream.by_ref().take(content_length).read_to_end(&mut vec![]).unwrap();
+            received.lock().unwrap().push(head);
+
+            match *response {
+                "" => std::mem::forget(stream), /* keep the connection silent */
+                _  => stream.get_mut().write_all(response.as_bytes()).unwrap(),
+            }
+        }
+    });
+
+    (origin, received)
+}
+
+const UNAVAILABLE: &str = "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+fn quick_retries() -> RetryPolicy {
+    RetryPolicy { max_retries: 2, base_delay: Duration::from_millis(10), max_delay: Duration::from_millis(20) }
+}
+
+#[tokio::test]
+async fn transient_failures_are_retried_when_safe() {
+    let (origin, received) = stub(&[UNAVAILABLE, UNAVAILABLE, "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n[]"]);
+    let states = Arc::new(Mutex::new(Vec::new()));
+    let client = Client::new(origin, "token").retry(quick_retries()).on_retry({
+        let states = states.clone();
+        move |state| states.lock().unwrap().push(state)
+    });
+
+    assert!(client.list_cards().await.unwrap().is_empty());
+    assert_eq!(received.lock().unwrap().len(), 3);
+    assert!(matches!(states.lock().unwrap()[..], [
+        RetryState::Waiting { attempt: 1, max_retries: 2, .. },
+        RetryState::Waiting { attempt: 2, max_retries: 2, .. },
+        RetryState::Finished,
+    ]), "{states:?}");
+
+    /* `/signup` may have been done by the server */
+    let (origin, received) = stub(&[UNAVAILABLE]);
+    let err = Client::signup(origin).await.err().unwrap();
+    assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+    assert_eq!(received.lock().unwrap().len(), 1);
+
+    /* while the others are sent with the same `Idempotency-Key` for every retry */
+    let (origin, received) = stub(&[UNAVAILABLE, "HTTP/1.1 201 Created\r\nContent-Length: 11\r\nConnection: close\r\n\r\n{\"id\":\"42\"}"]);
+    let id = Client::new(origin, "token").retry(quick_retries()).create_card(&CreateCardRequest::empty()).await.unwrap();
+    assert_eq!(&*id, "42");
+    let keys = received.lock().unwrap().iter()
+        .map(|head| head.lines().find(|line| line.to_ascii_lowercase().starts_with("idempotency-key: ")).unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0], keys[1]);
+
+    /* and PUT or DELETE without one, being idempotent by themselves */
+    let (origin, received) = stub(&["HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"]);
+    Client::new(origin, "token").delete_card("42").await.unwrap();
+    assert!(!received.lock().unwrap()[0].to_ascii_lowercase().contains("idempotency-key"));
+}
+
+#[tokio::test]
+async fn each_attempt_times_out() {
+    let (origin, received) = stub(&[]);
+    let client = Client::new(origin, "token").timeout(Duration::from_millis(100)).retry(quick_retries());
+
+    let err = client.list_cards().await.unwrap_err();
+    assert!(matches!(err, Error::Timeout(_)), "{err}");
+    assert_eq!(received.lock().unwrap().len(), 3);
+
+    /* but PUT only once, as the timed-out one may still be applied */
+    let (origin, received) = stub(&[]);
+    let client = Client::new(origin, "token").timeout(Duration::from_millis(100)).retry(quick_retries());
+    let err = client.update_card("42", &UpdateCard { title: "".into(), todos: std::array::from_fn(|_| Todo::new("")) }).await.unwrap_err();
+    assert!(matches!(err, Error::Timeout(_)), "{err}");
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn conflicts_are_retried_only_while_in_progress() {
+    const IN_PROGRESS: &str = "HTTP/1.1 409 Conflict\r\nRetry-After: 1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const EXISTING:    &str = "HTTP/1.1 409 Conflict\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    let (origin, received) = stub(&[IN_PROGRESS, IN_PROGRESS, IN_PROGRESS]);
+    let err = Client::new(origin, "token").retry(quick_retries()).create_card(&CreateCardRequest::empty()).await.unwrap_err();
+    assert!(matches!(err, Error::InProgress), "{err}");
+    assert_eq!(received.lock().unwrap().len(), 3);
+
+    let (origin, received) = stub(&[EXISTING]);
+    let err = Client::new(origin, "token").retry(quick_retries()).create_card(&CreateCardRequest::empty()).await.unwrap_err();
+    assert!(matches!(err, Error::Request(_)), "{err}");
+    assert_eq!(err.status(), Some(StatusCode::CONFLICT));
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,67 @@
+//! Harness running the native server on a free local port with a fresh in-memory database
+#![allow(dead_code)] /* each test crate uses only some of them */
+
+use models::{Card, CreateCardRequest, CreateCardResponse, SignupResponse};
+use ohkami_yew_todo::native::{self, AttachmentsBucket};
+use ohkami_yew_todo::repository::SqliteRepository;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use tempfile::TempDir;
+
+
+pub const JWT_SECRET_KEY: &str = "ohkami-yew-todo-test-jwt-secret-key";
+
+pub struct TestServer {
+    origin: String,
+    client: Client,
+    /// of the attachments, removed with the server
+    _attachments: TempDir,
+}
+
+impl TestServer {
+    /// Spawn the server by `native::spawn`
+    pub fn start() -> Self {
+        std::env::set_var("JWT_SECRET_KEY", JWT_SECRET_KEY);
+
+        let repo   = SqliteRepository::open_in_memory().unwrap();
+        let attachments = tempfile::tempdir().unwrap();
+        let bucket = AttachmentsBucket::open(attachments.path()).unwrap();
+
+        Self {
+            origin: native::spawn(repo, bucket),
+            client: Client::new(),
+            _attachments: attachments,
+        }
+    }
+
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client.request(method, format!("{}{path}", self.origin))
+    }
+
+    /// Sign up a new user and return the token
+    pub async fn signup(&self) -> String {
+        let res = self.request(Method::POST, "/signup").send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        res.json::<SignupResponse>().await.unwrap().token.to_string()
+    }
+
+    pub async fn create_card(&self, token: &str, title: &str, todos: [&str; Card::N_TODOS]) -> String {
+        let res = self.request(Method::POST, "/api/cards")
+            .bearer_auth(token)
+            .json(&CreateCardRequest { id: None, title: title.into(), todos: todos.map(String::from) })
+            .send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        res.json::<CreateCardResponse>().await.unwrap().id
+    }
+
+    pub async fn list_cards(&self, token: &str) -> Vec<Card> {
+        let res = self.request(Method::GET, "/api/cards")
+            .bearer_auth(token)
+            .send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        res.json().await.unwrap()
+    }
+}
@@ -0,0 +1,357 @@
+#![cfg(all(feature = "server", not(target_arch = "wasm32")))]
+
+//! Exercises every operation of `/openapi.json` against the server
+//! and checks the statuses and bodies conform to the document
+
+mod common;
+
+use common::TestServer;
+use ohkami::serde::json::{json, Value};
+use reqwest::{Method, StatusCode};
+use std::collections::HashSet;
+
+
+enum Body {
+    None,
+    Json(Value),
+    /// JSON not conforming to the request body schema
+    Malformed(Value),
+    Bytes(&'static str, &'static [u8]),
+}
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// The server with its document, recording the operations answered successfully
+struct Contract {
+    server:  TestServer,
+    spec:    Value,
+    covered: HashSet<(String, String)>,
+}
+
+impl Contract {
+    async fn new() -> Self {
+        let server = TestServer::start();
+
+        let res = server.request(Method::GET, "/openapi.json").send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let spec = res.json::<Value>().await.unwrap();
+        assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+
+        Self { server, spec, covered: HashSet::new() }
+    }
+
+    fn operation(&self, method: &Method, template: &str) -> &Value {
+        let operation = &self.spec["paths"][template][method.as_str().to_lowercase()];
+        assert!(operation.is_object(), "{method} {template} is not in the document");
+        operation
+    }
+
+    fn resolve<'s>(&'s self, schema: &'s Value) -> &'s Value {
+        match schema["$ref"].as_str() {
+            None => schema,
+            Some(reference) => {
+                let name = reference.strip_prefix("#/components/schemas/").unwrap();
+                let resolved = &self.spec["components"]["schemas"][name];
+                assert!(resolved.is_object(), "{reference} is not in the document");
+                self.resolve(resolved)
+            }
+        }
+    }
+
+    /// Panics unless `value` conforms to `schema` ( of the subset of OpenAPI 3.0 the document uses )
+    fn validate(&self, schema: &Value, value: &Value, at: &str) {
+        let schema = self.resolve(schema);
+
+        for sub in schema["allOf"].as_array().into_iter().flatten() {
+            self.validate(sub, value, at)
+        }
+
+        if value.is_null() {
+            assert_eq!(schema["nullable"], true, "{at}: null for {schema}");
+            return
+        }
+
+        match schema["type"].as_str() {
+            None => assert!(schema["allOf"].is_array(), "{at}: untyped schema {schema}"),
+            Some("boolean") => assert!(value.is_boolean(), "{at}: {value} for {schema}"),
+            Some("string")  => assert!(value.is_string(), "{at}: {value} for {schema}"),
+            Some("integer") => assert!(value.is_u64() || value.is_i64(), "{at}: {value} for {schema}"),
+            Some("array") => {
+                let items = value.as_array().unwrap_or_else(|| panic!("{at}: {value} for {schema}"));
+                if let Some(min) = schema["minItems"].as_u64() {
+                    assert!(items.len() as u64 >= min, "{at}: {} items for {schema}", items.len())
+                }
+                if let Some(max) = schema["maxItems"].as_u64() {
+                    assert!(items.len() as u64 <= max, "{at}: {} items for {schema}", items.len())
+                }
+                for (i, item) in items.iter().enumerate() {
+                    self.validate(&schema["items"], item, &format!("{at}[{i}]"))
+                }
+            }
+            Some("object") => {
+                let fields = value.as_object().unwrap_or_else(|| panic!("{at}: {value} for {schema}"));
+                for required in schema["required"].as_array().into_iter().flatten() {
+                    assert!(fields.contains_key(required.as_str().unwrap()), "{at}: missing {required} in {value}")
+                }
+                for (name, field) in fields {
+                    let Some(field_schema) = schema["properties"].get(name) else {
+                        panic!("{at}: undocumented field `{name}` in {value}")
+                    };
+                    self.validate(field_schema, field, &format!("{at}.{name}"))
+                }
+            }
+            Some(other) => panic!("{at}: unsupported type `{other}`"),
+        }
+    }
+
+    /// Send the request to `path` ( an instance of `template` ) and check it and
+    /// its response conform to the operation, returning the status and JSON body
+    async fn call(&mut self,
+        method:   Method,
+        template: &str,
+        path:     &str,
+        token:    Option<&str>,
+        body:     Body,
+    ) -> (StatusCode, Value) {
+        let operation = self.operation(&method, template).clone();
+        let at = format!("{method} {path}");
+
+        let mut req = self.server.request(method.clone(), path);
+        if let Some(token) = token {
+            req = req.bearer_auth(token)
+        }
+        match body {
+            Body::None => (),
+            Body::Json(json) => {
+                let schema = &operation["requestBody"]["content"]["application/json"]["schema"];
+                assert!(schema.is_object(), "{at}: JSON request body is not documented");
+                self.validate(schema, &json, &format!("{at} request"));
+                req = req.json(&json)
+            }
+            Body::Malformed(json) => {
+                assert!(operation["requestBody"]["content"]["application/json"].is_object(), "{at}: JSON request body is not documented");
+                req = req.json(&json)
+            }
+            Body::Bytes(mime_type, bytes) => {
+                assert!(operation["requestBody"]["content"][mime_type].is_object(), "{at}: `{mime_type}` request body is not documented");
+                req = req.header("Content-Type", mime_type).body(bytes)
+            }
+        }
+
+        let res = req.send().await.unwrap();
+        let status = res.status();
+        let documented = &operation["responses"][status.as_str()];
+        assert!(documented.is_object(), "{at}: undocumented status {status}");
+
+        let content_type = res.headers().get("Content-Type")
+            .map(|v| v.to_str().unwrap().split(';').next().unwrap().to_string());
+        let bytes = res.bytes().await.unwrap();
+
+        let json = match documented["content"].as_object() {
+            None => {
+                if status.is_success() {
+                    assert!(bytes.is_empty(), "{at}: undocumented body for {status}")
+                }
+                Value::Null
+            }
+            Some(content) => {
+                let content_type = content_type.unwrap_or_else(|| panic!("{at}: no Content-Type for {status}"));
+                assert!(content.contains_key(&content_type), "{at}: undocumented Content-Type `{content_type}` for {status}");
+                if content_type == "application/json" {
+                    let json = ohkami::serde::json::from_slice(&bytes).unwrap();
+                    self.validate(&content["application/json"]["schema"], &json, &format!("{at} {status}"));
+                    json
+                } else {
+                    Value::Null
+                }
+            }
+        };
+
+        if status.is_success() {
+            self.covered.insert((method.as_str().to_lowercase(), template.into()));
+        }
+        (status, json)
+    }
+}
+
+
+#[tokio::test]
+async fn every_operation_conforms_to_openapi_json() {
+    let mut c = Contract::new().await;
+
+    let (_, signup) = c.call(Method::POST, "/signup", "/signup", None, Body::None).await;
+    let token = signup["token"].as_str().unwrap().to_string();
+    let token = Some(&*token);
+
+    let (status, _) = c.call(Method::GET, "/api/cards", "/api/cards", None, Body::None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (_, cards) = c.call(Method::GET, "/api/cards", "/api/cards", token, Body::None).await;
+    assert_eq!(cards, json!([]));
+
+    /* cards */
+
+    let (status, created) = c.call(Method::POST, "/api/cards", "/api/cards", token, Body::Json(json!({
+        "title": "groceries",
+        "todos": ["milk", "eggs", "", "", "", "", "", "", "", ""],
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let card_id = created["id"].as_str().unwrap().to_string();
+    let card_path = format!("/api/cards/{card_id}");
+
+    let (status, _) = c.call(Method::PUT, "/api/cards/{id}", &card_path, token, Body::Json(json!({
+        "title": "shopping",
+        "todos": [
+            { "content": "milk", "completed": true, "notes": "low fat" },
+            { "content": "eggs", "completed": false, "children": [{ "content": "a dozen", "completed": false }] },
+            { "content": "", "completed": false }, { "content": "", "completed": false },
+            { "content": "", "completed": false }, { "content": "", "completed": false },
+            { "content": "", "completed": false }, { "content": "", "completed": false },
+            { "content": "", "completed": false }, { "content": "", "completed": false },
+        ],
+    }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = c.call(Method::PUT, "/api/cards/{id}", &card_path, token, Body::Malformed(json!({
+        "title": "too few todos",
+        "todos": [],
+    }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, duplicated) = c.call(Method::POST, "/api/cards/{id}/duplicate", &format!("{card_path}/duplicate"), token, Body::None).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let duplicated_id = duplicated["id"].as_str().unwrap().to_string();
+
+    /* attachments */
+
+    let attachments_path = format!("{card_path}/attachments");
+    let (status, attachment) = c.call(Method::POST, "/api/cards/{id}/attachments", &format!("{attachments_path}?name=a.png"), token, Body::Bytes("image/png", PNG)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let attachment_id = attachment["id"].as_str().unwrap().to_string();
+    let attachment_url = attachment["url"].as_str().unwrap().to_string();
+
+    let (status, _) = c.call(Method::POST, "/api/cards/{id}/attachments", &attachments_path, token, Body::Bytes("image/png", PNG)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = c.call(Method::GET, "/attachments/{id}", &attachment_url, None, Body::None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = c.call(Method::GET, "/attachments/{id}", &format!("/attachments/{attachment_id}?expires=0&signature=00"), None, Body::None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (_, cards) = c.call(Method::GET, "/api/cards", "/api/cards", token, Body::None).await;
+    assert_eq!(cards[0]["attachments"][0]["id"], attachment_id);
+
+    let (status, _) = c.call(Method::DELETE, "/api/cards/{id}/attachments/{attachment_id}", &format!("{attachments_path}/{attachment_id}"), token, Body::None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = c.call(Method::GET, "/attachments/{id}", &attachment_url, None, Body::None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    /* templates */
+
+    let (status, template) = c.call(Method::POST, "/api/templates", "/api/templates", token, Body::Json(json!({ "card_id": card_id }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let template_path = format!("/api/templates/{}", template["id"].as_str().unwrap());
+
+    let (_, templates) = c.call(Method::GET, "/api/templates", "/api/templates", token, Body::None).await;
+    assert_eq!(templates[0]["title"], "shopping");
+
+    let (status, _) = c.call(Method::POST, "/api/templates/{id}/instantiate", &format!("{template_path}/instantiate"), token, Body::None).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, _) = c.call(Method::DELETE, "/api/templates/{id}", &template_path, token, Body::None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    /* pairing */
+
+    let (status, pairing) = c.call(Method::POST, "/api/pairing-codes", "/api/pairing-codes", token, Body::None).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let code = json!({ "code": pairing["code"] });
+
+    let (status, _) = c.call(Method::POST, "/pair", "/pair", None, Body::Json(code.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = c.call(Method::POST, "/pair", "/pair", None, Body::Json(code)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    /* of another user */
+
+    let (_, other) = c.call(Method::POST, "/signup", "/signup", None, Body::None).await;
+    let other = other["token"].as_str().unwrap().to_string();
+    let other = Some(&*other);
+
+    let (status, _) = c.call(Method::DELETE, "/api/cards/{id}", &format!("/api/cards/{duplicated_id}"), other, Body::None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    /* the archive and the trash */
+
+    let (status, _) = c.call(Method::PUT, "/api/archive/{id}", &format!("/api/archive/{duplicated_id}"), token, Body::None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, archived) = c.call(Method::GET, "/api/archive", "/api/archive", token, Body::None).await;
+    assert_eq!(archived[0]["id"], duplicated_id);
+    let (status, _) = c.call(Method::DELETE, "/api/archive/{id}", &format!("/api/archive/{duplicated_id}"), token, Body::None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = c.call(Method::DELETE, "/api/trash/{id}", &format!("/api/trash/{card_id}"), token, Body::None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = c.call(Method::DELETE, "/api/cards/{id}", &card_path, token, Body::None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, trashed) = c.call(Method::GET, "/api/trash", "/api/trash", token, Body::None).await;
+    assert_eq!(trashed[0]["id"], card_id);
+    let (status, _) = c.call(Method::POST, "/api/trash/{id}/restore", &format!("/api/trash/{card_id}/restore"), token, Body::None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = c.call(Method::DELETE, "/api/cards/{id}", &card_path, token, Body::None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = c.call(Method::DELETE, "/api/trash/{id}", &format!("/api/trash/{card_id}"), token, Body::None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    /* the whole document is exercised */
+
+    let documented = c.spec["paths"].as_object().unwrap().iter()
+        .flat_map(|(template, item)| item.as_object().unwrap().keys()
+            .filter(|key| *key != "parameters")
+            .map(|method| (method.clone(), template.clone())))
+        .collect::<HashSet<_>>();
+    let mut uncovered = documented.difference(&c.covered).collect::<Vec<_>>();
+    uncovered.sort();
+    assert!(uncovered.is_empty(), "never succeeded: {uncovered:?}");
+}
+
+#[tokio::test]
+async fn every_route_is_documented() {
+    let c = Contract::new().await;
+
+    let undocumented = ohkami_yew_todo::ROUTES.iter()
+        .filter(|(method, path)| {
+            let template = path.split('/')
+                .map(|segment| match segment.strip_prefix(':') {
+                    Some(param) => format!("{{{param}}}"),
+                    None => segment.to_owned(),
+                })
+                .collect::<Vec<_>>().join("/");
+            c.spec["paths"][template][method.to_lowercase()].is_null()
+        })
+        .collect::<Vec<_>>();
+    assert!(undocumented.is_empty(), "routed but not documented: {undocumented:?}");
+
+}
+
+#[tokio::test]
+async fn undocumented_methods_are_not_routed() {
+    let c = Contract::new().await;
+    let token = c.server.signup().await;
+
+    for (template, item) in c.spec["paths"].as_object().unwrap() {
+        let path = template.replace(['{', '}'], "");
+        for method in [Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE] {
+            if item.get(method.as_str().to_lowercase()).is_some() {
+                continue
+            }
+            let res = c.server.request(method.clone(), &path).bearer_auth(&token).send().await.unwrap();
+            assert!(
+                matches!(res.status(), StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED),
+                "{method} {template} is routed ( {} ) but not documented", res.status()
+            );
+        }
+    }
+}
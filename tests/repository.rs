@@ -0,0 +1,338 @@
+#![cfg(all(feature = "server", not(target_arch = "wasm32")))]
+
+use models::{Card, Todo, UpdateCard};
+use ohkami_yew_todo::repository::{IdempotencyRecord, Repository, RepositoryError, Shelf, SqliteRepository, IDEMPOTENCY_CLAIM_LEASE};
+use std::array;
+
+
+fn todos(contents: [&str; Card::N_TODOS]) -> [Todo; Card::N_TODOS] {
+    contents.map(Todo::new)
+}
+
+fn contents(todos: &[Todo; Card::N_TODOS]) -> [&str; Card::N_TODOS] {
+    array::from_fn(|i| &*todos[i].content)
+}
+
+fn assert_not_owner<T: std::fmt::Debug>(result: Result<T, RepositoryError>) {
+    assert!(
+        matches!(result, Err(RepositoryError::NotOwner { .. })),
+        "expected NotOwner, got {result:?}"
+    )
+}
+
+
+#[tokio::test]
+async fn create_and_list_cards() {
+    let repo = SqliteRepository::open_in_memory().unwrap();
+    let user = repo.create_user().await.unwrap();
+
+    assert!(repo.list_cards(&user).await.unwrap().is_empty());
+
+    let first = repo.create_card(&user, None, "first", todos(["a", "b", "", "", "", "", "", "", "", ""])).await.unwrap();
+    let second = repo.create_card(&user, None, "second", todos(["", "", "", "", "", "", "", "", "", "z"])).await.unwrap();
+
+    let cards = repo.list_cards(&user).await.unwrap();
+    assert_eq!(cards.len(), 2);
+
+    assert_eq!(cards[0].id, first);
+    assert_eq!(cards[0].title, "first");
+    assert_eq!(contents(&cards[0].todos), ["a", "b", "", "", "", "", "", "", "", ""]);
+    assert!(cards[0].todos.iter().all(|t| !t.completed && t.notes.is_none() && t.children.is_empty()));
+
+    assert_eq!(cards[1].id, second);
+    assert_eq!(cards[1].title, "second");
+    assert_eq!(contents(&cards[1].todos), ["", "", "", "", "", "", "", "", "", "z"]);
+
+    assert!(matches!(
+        repo.create_card(&user, Some(&first), "again", todos(["", "", "", "", "", "", "", "", "", ""])).await,
+        Err(RepositoryError::AlreadyExists { .. })
+    ));
+}
+
+#[tokio::test]
+async fn create_card_with_notes_and_subtodos() {
+    let repo = SqliteRepository::open_in_memory().unwrap();
+    let user = repo.create_user().await.unwrap();
+
+    let mut todos = todos(["parent", "done", "", "", "", "", "", "", "", ""]);
+    todos[1].completed = true;
+    todos[0].notes = Some("some\nnotes".into());
+    todos[0].children = vec![
+        Todo { children: vec![Todo::new("grandchild")], ..Todo::new("child 1") },
+        Todo::new("child 2"),
+    ];
+
+    let id = repo.create_card(&user, None, "nested", todos.clone()).await.unwrap();
+
+    let card = repo.get_card(&user, &id).await.unwrap();
+    assert_eq!(card.todos, todos);
+}
+
+#[tokio::test]
+async fn update_card() {
+    let repo = SqliteRepository::open_in_memory().unwrap();
+    let user = repo.create_user().await.unwrap();
+
+    let id = repo.create_card(&user, None, "before", todos(["a", "b", "c", "", "", "", "", "", "", ""])).await.unwrap();
+
+    let mut new_todos = todos(["a", "B", "c", "d", "", "", "", "", "", ""]);
+    new_todos[0].completed = true;
+    new_todos[2].notes = Some("notes".into());
+    new_todos[3].children = vec![Todo::new("sub 1"), Todo::new("sub 2")];
+
+    repo.update_card(&user, &id, UpdateCard { title: "after".into(), todos: new_todos.clone() }).await.unwrap();
+
+    let card = repo.get_card(&user, &id).await.unwrap();
+    assert_eq!(card.title, "after");
+    assert_eq!(card.todos, new_todos);
+
+    /* replacing sub-todos */
+    new_todos[3].children = vec![Todo { completed: true, ..Todo::new("only one") }];
+    repo.update_card(&user, &id, UpdateCard { title: "after".into(), todos: new_todos.clone() }).await.unwrap();
+
+    let card = repo.get_card(&user, &id).await.unwrap();
+    assert_eq!(card.todos, new_todos);
+
+    /* still exactly `N_TODOS` top-level todos */
+    let cards = repo.list_cards(&user).await.unwrap();
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].todos, new_todos);
+}
+
+#[tokio::test]
+async fn delete_card() {
+    let repo = SqliteRepository::open_in_memory().unwrap();
+    let user = repo.create_user().await.unwrap();
+
+    let deleted = repo.create_card(&user, None, "deleted", todos(["x", "", "", "", "", "", "", "", "", ""])).await.unwrap();
+    let kept = repo.create_card(&user, None, "kept", todos(["y", "", "", "", "", "", "", "", "", ""])).await.unwrap();
+
+    let attachment = repo.create_attachment(&user, &deleted, "a.png", "image/png", 42).await.unwrap();
+
+    /* only from the trash */
+    assert!(matches!(repo.delete_card(&user, &deleted).await, Err(RepositoryError::NotFound { .. })));
+    repo.shelve_card(&user, &deleted, Shelf::Trash).await.unwrap();
+
+    let attachment_ids = repo.delete_card(&user, &deleted).await.unwrap();
+    assert_eq!(attachment_ids, [attachment.clone()]);
+    assert!(repo.get_attachment(&attachment).await.unwrap().is_none());
+
+    let cards = repo.list_cards(&user).await.unwrap();
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].id, kept);
+    assert_eq!(contents(&cards[0].todos), ["y", "", "", "", "", "", "", "", "", ""]);
+
+    assert_not_owner(repo.get_card(&user, &deleted).await);
+    assert!(repo.list_shelved_cards(&user, Shelf::Trash).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn archive_and_trash() {
+    let repo = SqliteRepository::open_in_memory().unwrap();
+    let user = repo.create_user().await.unwrap();
+
+    let ids = [
+        repo.create_card(&user, None, "first", todos(["", "", "", "", "", "", "", "", "", ""])).await.unwrap(),
+        repo.create_card(&user, None, "second", todos(["", "", "", "", "", "", "", "", "", ""])).await.unwrap(),
+        repo.create_card(&user, None, "third", todos(["", "", "", "", "", "", "", "", "", ""])).await.unwrap(),
+    ];
+    let titles = |cards: Vec<Card>| cards.into_iter().map(|c| c.title).collect::<Vec<_>>();
+
+    repo.shelve_card(&user, &ids[0], Shelf::Archive).await.unwrap();
+    repo.shelve_card(&user, &ids[1], Shelf::Trash).await.unwrap();
+    assert_eq!(titles(repo.list_cards(&user).await.unwrap()), ["third"]);
+    assert_eq!(titles(repo.list_shelved_cards(&user, Shelf::Archive).await.unwrap()), ["first"]);
+    assert_eq!(titles(repo.list_shelved_cards(&user, Shelf::Trash).await.unwrap()), ["second"]);
+
+    /* from the archive to the trash, and back to the board */
+    repo.shelve_card(&user, &ids[0], Shelf::Trash).await.unwrap();
+    assert!(repo.list_shelved_cards(&user, Shelf::Archive).await.unwrap().is_empty());
+    assert_eq!(repo.list_shelved_cards(&user, Shelf::Trash).await.unwrap().len(), 2);
+
+    repo.unshelve_card(&user, &ids[0]).await.unwrap();
+    repo.unshelve_card(&user, &ids[1]).await.unwrap();
+    assert_eq!(titles(repo.list_cards(&user).await.unwrap()), ["first", "second", "third"]);
+    assert!(repo.list_shelved_cards(&user, Shelf::Trash).await.unwrap().is_empty());
+
+    let other = repo.create_user().await.unwrap();
+    assert_not_owner(repo.shelve_card(&other, &ids[2], Shelf::Trash).await);
+    assert_not_owner(repo.unshelve_card(&other, &ids[2]).await);
+    assert!(repo.list_shelved_cards(&other, Shelf::Trash).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn cards_are_only_of_the_owner() {
+    let repo = SqliteRepository::open_in_memory().unwrap();
+    let owner = repo.create_user().await.unwrap();
+    let other = repo.create_user().await.unwrap();
+
+    let id = repo.create_card(&owner, None, "mine", todos(["a", "", "", "", "", "", "", "", "", ""])).await.unwrap();
+
+    assert!(repo.list_cards(&other).await.unwrap().is_empty());
+
+    assert_not_owner(repo.get_card(&other, &id).await);
+    assert_not_owner(repo.update_card(&other, &id, UpdateCard {
+        title: "stolen".into(),
+        todos: todos(["", "", "", "", "", "", "", "", "", ""]),
+    }).await);
+    assert_not_owner(repo.delete_card(&other, &id).await);
+    assert_not_owner(repo.create_attachment(&other, &id, "a.pdf", "application/pdf", 1).await);
+
+    let card = repo.get_card(&owner, &id).await.unwrap();
+    assert_eq!(card.title, "mine");
+    assert_eq!(contents(&card.todos), ["a", "", "", "", "", "", "", "", "", ""]);
+
+    assert_not_owner(repo.get_card(&owner, "not-existing").await);
+}
+
+#[tokio::test]
+async fn templates() {
+    let repo = SqliteRepository::open_in_memory().unwrap();
+    let owner = repo.create_user().await.unwrap();
+    let other = repo.create_user().await.unwrap();
+
+    let id = repo.create_template(&owner, "daily", ["x", "y", "", "", "", "", "", "", "", ""].map(String::from)).await.unwrap();
+
+    let templates = repo.list_templates(&owner).await.unwrap();
+    assert_eq!(templates.len(), 1);
+    assert_eq!(templates[0].id, id);
+    assert_eq!(templates[0].title, "daily");
+    assert_eq!(templates[0].todos, ["x", "y", "", "", "", "", "", "", "", ""]);
+
+    assert!(repo.list_templates(&other).await.unwrap().is_empty());
+    assert_not_owner(repo.get_template(&other, &id).await);
+    assert_not_owner(repo.delete_template(&other, &id).await);
+
+    repo.delete_template(&owner, &id).await.unwrap();
+    assert!(repo.list_templates(&owner).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn attachments() {
+    let repo = SqliteRepository::open_in_memory().unwrap();
+    let owner = repo.create_user().await.unwrap();
+    let other = repo.create_user().await.unwrap();
+
+    let card = repo.create_card(&owner, None, "", todos(["", "", "", "", "", "", "", "", "", ""])).await.unwrap();
+    let id = repo.create_attachment(&owner, &card, "photo.jpg", "image/jpeg", 1024).await.unwrap();
+
+    let attachment = repo.get_attachment(&id).await.unwrap().unwrap();
+    assert_eq!((&*attachment.name, &*attachment.mime_type, attachment.size), ("photo.jpg", "image/jpeg", 1024));
+
+    let card_attachments = repo.get_card(&owner, &card).await.unwrap().attachments;
+    assert_eq!(card_attachments.len(), 1);
+    assert_eq!(card_attachments[0].id, id);
+
+    assert_not_owner(repo.delete_attachment(&other, &card, &id).await);
+    assert!(repo.get_attachment(&id).await.unwrap().is_some());
+
+    repo.delete_attachment(&owner, &card, &id).await.unwrap();
+    assert!(repo.get_attachment(&id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn idempotency_keys() {
+    let repo = SqliteRepository::open_in_memory().unwrap();
+    let (user, another) = (repo.create_user().await.unwrap(), repo.create_user().await.unwrap());
+
+    assert_eq!(repo.claim_idempotency_key(&user, "k", "POST /cards").await.unwrap(), None);
+    assert_eq!(repo.claim_idempotency_key(&user, "k", "POST /cards").await.unwrap(), Some(IdempotencyRecord {
+        request: "POST /cards".into(),
+        status:  None,
+        body:    None,
+    }));
+    assert_eq!(repo.claim_idempotency_key(&another, "k", "POST /cards").await.unwrap(), None);
+
+    repo.save_idempotent_response(&user, "k", 201, Some(r#"{"id":"42"}"#)).await.unwrap();
+    assert_eq!(repo.claim_idempotency_key(&user, "k", "POST /cards").await.unwrap(), Some(IdempotencyRecord {
+        request: "POST /cards".into(),
+        status:  Some(201),
+        body:    Some(r#"{"id":"42"}"#.into()),
+    }));
+
+    repo.release_idempotency_key(&another, "k").await.unwrap();
+    assert_eq!(repo.claim_idempotency_key(&another, "k", "DELETE /cards/42").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn idempotency_keys_in_progress_are_leased() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqlite");
+    let repo = SqliteRepository::open(&path).unwrap();
+    let user = repo.create_user().await.unwrap();
+
+    assert_eq!(repo.claim_idempotency_key(&user, "k", "POST /cards").await.unwrap(), None);
+    assert!(repo.claim_idempotency_key(&user, "k", "POST /cards").await.unwrap().is_some());
+
+    /* as if the first request has died */
+    rusqlite::Connection::open(&path).unwrap().execute(
+        "UPDATE idempotency_keys SET claimed_at = claimed_at - ?", [IDEMPOTENCY_CLAIM_LEASE + 1]
+    ).unwrap();
+    assert!(repo.claim_idempotency_key(&user, "k", "DELETE /cards/42").await.unwrap().is_some());
+    assert_eq!(repo.claim_idempotency_key(&user, "k", "POST /cards").await.unwrap(), None);
+    assert!(repo.claim_idempotency_key(&user, "k", "POST /cards").await.unwrap().is_some());
+
+    /* but not the recorded one */
+    repo.save_idempotent_response(&user, "k", 201, None).await.unwrap();
+    rusqlite::Connection::open(&path).unwrap().execute(
+        "UPDATE idempotency_keys SET claimed_at = claimed_at - ?", [IDEMPOTENCY_CLAIM_LEASE + 1]
+    ).unwrap();
+    assert_eq!(repo.claim_idempotency_key(&user, "k", "POST /cards").await.unwrap().unwrap().status, Some(201));
+}
+
+#[tokio::test]
+async fn pairing_codes() {
+    let repo = SqliteRepository::open_in_memory().unwrap();
+    let (user, another) = (repo.create_user().await.unwrap(), repo.create_user().await.unwrap());
+
+    let code = repo.create_pairing_code(&user).await.unwrap();
+    let another_code = repo.create_pairing_code(&another).await.unwrap();
+    assert_ne!(code, another_code);
+    assert_eq!(code.len(), 10);
+
+    assert_eq!(repo.consume_pairing_code(&code).await.unwrap(), user);
+    assert!(matches!(repo.consume_pairing_code(&code).await, Err(RepositoryError::NotFound { .. })));
+    assert_eq!(repo.consume_pairing_code(&another_code).await.unwrap(), another);
+}
+
+#[tokio::test]
+async fn databases_of_an_older_schema_are_migrated() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqlite");
+
+    /* as created by `schema.sql` of the first release, and `idempotency_keys` before `claimed_at` */
+    rusqlite::Connection::open(&path).unwrap().execute_batch("
+        CREATE TABLE users (id TEXT NOT NULL, PRIMARY KEY (id));
+        CREATE TABLE cards (id TEXT NOT NULL, user_id TEXT NOT NULL, title TEXT NOT NULL DEFAULT '', created_at INTEGER NOT NULL, PRIMARY KEY (id));
+        CREATE TABLE todos (id INTEGER NOT NULL, card_id TEXT NOT NULL, content TEXT NOT NULL DEFAULT '', completed_at INTEGER, PRIMARY KEY (id));
+        CREATE TABLE idempotency_keys (user_id TEXT NOT NULL, key TEXT NOT NULL, request TEXT NOT NULL, status INTEGER, body TEXT, created_at INTEGER NOT NULL, PRIMARY KEY (user_id, key));
+        INSERT INTO users (id) VALUES ('user');
+        INSERT INTO cards (id, user_id, title, created_at) VALUES ('card', 'user', 'old', 0);
+        INSERT INTO todos (card_id, content, completed_at) VALUES ('card', 'a', 1), ('card', 'b', NULL),
+            ('card', '', NULL), ('card', '', NULL), ('card', '', NULL), ('card', '', NULL),
+            ('card', '', NULL), ('card', '', NULL), ('card', '', NULL), ('card', '', NULL);
+    ").unwrap();
+
+    let repo = SqliteRepository::open(&path).unwrap();
+    let card = repo.get_card("user", "card").await.unwrap();
+    assert_eq!(card.title, "old");
+    assert_eq!(contents(&card.todos), ["a", "b", "", "", "", "", "", "", "", ""]);
+    assert!(card.todos[0].completed && card.todos[0].notes.is_none() && card.todos[0].children.is_empty());
+
+    let mut todos = card.todos.clone();
+    todos[1].notes = Some("notes".into());
+    todos[1].children = vec![Todo::new("child")];
+    repo.update_card("user", "card", UpdateCard { title: "old".into(), todos: todos.clone() }).await.unwrap();
+    assert_eq!(repo.get_card("user", "card").await.unwrap().todos, todos);
+
+    /* with the keys claimed before `claimed_at`, taken over at once */
+    rusqlite::Connection::open(&path).unwrap().execute_batch("
+        INSERT INTO idempotency_keys (user_id, key, request, created_at) VALUES ('user', 'k', 'POST /cards', unixepoch());
+    ").unwrap();
+    assert_eq!(repo.claim_idempotency_key("user", "k", "POST /cards").await.unwrap(), None);
+
+    /* and again without applying twice */
+    drop(repo);
+    let repo = SqliteRepository::open(&path).unwrap();
+    assert_eq!(repo.get_card("user", "card").await.unwrap().todos, todos);
+}